@@ -0,0 +1,71 @@
+/**
+ * Error classification for the bridge protocol.
+ * Every failure crosses to Rust as {code, message, data?} with a stable code
+ * the desktop UI can branch on instead of matching message text.
+ */
+
+export const ErrorCode = {
+  AuthFailed: 'AUTH_FAILED',
+  ConnectionRefused: 'CONNECTION_REFUSED',
+  PolicyBlocked: 'POLICY_BLOCKED',
+  LlmQuota: 'LLM_QUOTA',
+  LlmKeyMissing: 'LLM_KEY_MISSING',
+  UnknownMethod: 'UNKNOWN_METHOD',
+  ShuttingDown: 'SHUTTING_DOWN',
+  FrameTooLarge: 'FRAME_TOO_LARGE',
+  Internal: 'INTERNAL',
+} as const;
+
+export type ErrorCodeValue = (typeof ErrorCode)[keyof typeof ErrorCode];
+
+export interface WireError {
+  code: ErrorCodeValue;
+  message: string;
+  data?: unknown;
+}
+
+/** Throw from a handler to pick the wire code explicitly. */
+export class BridgeError extends Error {
+  readonly code: ErrorCodeValue;
+  readonly data?: unknown;
+
+  constructor(code: ErrorCodeValue, message: string, data?: unknown) {
+    super(message);
+    this.name = 'BridgeError';
+    this.code = code;
+    this.data = data;
+  }
+}
+
+// Postgres SQLSTATE classes: 28P01 invalid_password, 28000 invalid_authorization_specification.
+const PG_AUTH_STATES = new Set(['28P01', '28000']);
+
+function classify(err: unknown, message: string): { code: ErrorCodeValue; data?: unknown } {
+  const source = (err ?? {}) as { code?: unknown; status?: unknown; type?: unknown };
+  const rawCode = typeof source.code === 'string' ? source.code : undefined;
+
+  if ((rawCode && PG_AUTH_STATES.has(rawCode)) || /password authentication failed|authentication failed/i.test(message)) {
+    return { code: ErrorCode.AuthFailed, data: rawCode ? { sqlState: rawCode } : undefined };
+  }
+  if (rawCode === 'ECONNREFUSED' || /ECONNREFUSED|could not connect/i.test(message)) {
+    return { code: ErrorCode.ConnectionRefused };
+  }
+  if (rawCode === 'insufficient_quota' || source.status === 429 || /exceeded your current quota/i.test(message)) {
+    return { code: ErrorCode.LlmQuota, data: { status: source.status ?? null } };
+  }
+  if (/OpenAI API key is not configured|OPENAI_API_KEY/.test(message)) {
+    return { code: ErrorCode.LlmKeyMissing };
+  }
+  if (/blocked by policy|requires POWER mode/i.test(message)) {
+    return { code: ErrorCode.PolicyBlocked };
+  }
+  return { code: ErrorCode.Internal };
+}
+
+export function toWireError(err: unknown): WireError {
+  if (err instanceof BridgeError) {
+    return { code: err.code, message: err.message, data: err.data };
+  }
+  const message = err instanceof Error ? err.message : String(err);
+  return { message, ...classify(err, message) };
+}
@@ -0,0 +1,160 @@
+/**
+ * Content-Length framing for the bridge protocol, as in LSP:
+ *
+ *   Content-Length: 27\r\n
+ *   \r\n
+ *   {"id":"1","result":{"ok":true}}
+ *
+ * Once the shell asks for it with `bridge.encoding`, the bridge's messages are
+ * MessagePack instead, marked with `Content-Type: application/msgpack`. The
+ * shell's own messages are always JSON.
+ *
+ * The Rust side is src-tauri/src/framing.rs. Both read the frame size limit
+ * from OPENQUERY_BRIDGE_MAX_FRAME_MB (default 64).
+ */
+
+import type { Readable } from 'node:stream';
+import { encode as encodeMsgpack } from '@msgpack/msgpack';
+
+const DEFAULT_MAX_FRAME_MB = 64;
+const MAX_HEADER_BYTES = 1024;
+const HEADER_END = Buffer.from('\r\n\r\n');
+
+export function maxFrameBytes(): number {
+  const mb = Number(process.env.OPENQUERY_BRIDGE_MAX_FRAME_MB);
+  return (Number.isInteger(mb) && mb > 0 ? mb : DEFAULT_MAX_FRAME_MB) * 1024 * 1024;
+}
+
+/** Encodings the bridge can answer in, announced in the ready handshake. */
+export const ENCODINGS = ['json', 'msgpack'] as const;
+export type Encoding = (typeof ENCODINGS)[number];
+
+export function isEncoding(value: unknown): value is Encoding {
+  return (ENCODINGS as readonly unknown[]).includes(value);
+}
+
+/** Encode a message body. Its `id` (if any) must be the first key; see framing.rs. */
+export function encodeBody(msg: unknown, encoding: Encoding): Buffer {
+  if (encoding === 'json') return Buffer.from(JSON.stringify(msg), 'utf8');
+  const bytes = encodeMsgpack(jsonValue(msg), { useBigInt64: true });
+  return Buffer.from(bytes.buffer, bytes.byteOffset, bytes.byteLength);
+}
+
+/**
+ * `value` as JSON.stringify sees it, so the shell decodes a MessagePack body
+ * into the same shapes as the JSON one: `toJSON` is honoured (Dates become ISO
+ * strings, Buffers `{type, data}`), undefined, functions and symbols are
+ * dropped from objects and become null in arrays, non-finite numbers become
+ * null, and a BigInt throws.
+ *
+ * Integers past 2^53 are written by JSON.stringify as integer literals, which
+ * serde reads as i64/u64 when they fit. They become BigInts here so the encoder
+ * writes them in its 64-bit integer formats rather than as float64.
+ */
+function jsonValue(value: unknown, key = ''): unknown {
+  if (value !== null && typeof value === 'object' && typeof (value as { toJSON?: unknown }).toJSON === 'function') {
+    value = (value as { toJSON(key: string): unknown }).toJSON(key);
+  }
+  switch (typeof value) {
+    case 'number':
+      if (!Number.isFinite(value)) return null;
+      if (Number.isSafeInteger(value) || !Number.isInteger(value) || Math.abs(value) >= 2 ** 64) return value;
+      return integerLiteral(value);
+    case 'bigint':
+      throw new TypeError('Do not know how to serialize a BigInt');
+    case 'object': {
+      if (value === null) return null;
+      if (Array.isArray(value)) {
+        return value.map((item, i) => {
+          const converted = jsonValue(item, String(i));
+          return isOmitted(converted) ? null : converted;
+        });
+      }
+      const object: Record<string, unknown> = {};
+      for (const [k, v] of Object.entries(value)) {
+        const converted = jsonValue(v, k);
+        if (!isOmitted(converted)) object[k] = converted;
+      }
+      return object;
+    }
+    default:
+      return value;
+  }
+}
+
+/**
+ * The integer JSON.stringify writes for `n`, the shortest decimal that reads
+ * back as `n` (not always `n` exactly), if serde would read it as i64/u64.
+ */
+function integerLiteral(n: number): bigint | number {
+  const literal = BigInt(String(n));
+  return literal >= -(2n ** 63n) && literal < 2n ** 64n ? literal : n;
+}
+
+/** Whether JSON.stringify leaves this value out of an object (and writes null for it in an array). */
+function isOmitted(value: unknown): boolean {
+  return value === undefined || typeof value === 'function' || typeof value === 'symbol';
+}
+
+/** Frame an encoded body. JSON frames carry no Content-Type. */
+export function encodeFrame(payload: Buffer, encoding: Encoding = 'json'): Buffer {
+  const contentType = encoding === 'msgpack' ? 'Content-Type: application/msgpack\r\n' : '';
+  return Buffer.concat([Buffer.from(`Content-Length: ${payload.length}\r\n${contentType}\r\n`, 'latin1'), payload]);
+}
+
+export interface FrameHandlers {
+  message(body: string): void;
+  /** A frame over the limit was skipped without being buffered. */
+  oversized(size: number): void;
+  /** The stream is not framed; nothing after this can be trusted. */
+  malformed(reason: string): void;
+}
+
+/** Split `input` into frames. */
+export function readFrames(input: Readable, maxFrame: number, handlers: FrameHandlers): void {
+  let buffer = Buffer.alloc(0);
+  let expected = -1; // body size of the frame being read, once its header is in
+  let skipping = 0;
+  let broken = false;
+
+  input.on('data', (chunk: Buffer) => {
+    if (broken) return;
+    buffer = buffer.length === 0 ? chunk : Buffer.concat([buffer, chunk]);
+    for (;;) {
+      if (skipping > 0) {
+        const n = Math.min(skipping, buffer.length);
+        buffer = buffer.subarray(n);
+        skipping -= n;
+        if (skipping > 0) return;
+      }
+      if (expected < 0) {
+        const end = buffer.indexOf(HEADER_END);
+        if (end < 0) {
+          if (buffer.length > MAX_HEADER_BYTES) fail('frame header too long');
+          return;
+        }
+        const header = buffer.subarray(0, end).toString('latin1');
+        buffer = buffer.subarray(end + HEADER_END.length);
+        const match = /^content-length:\s*(\d+)\s*$/im.exec(header);
+        if (!match) return fail(`malformed frame header: ${header}`);
+        const size = Number(match[1]);
+        if (size > maxFrame) {
+          handlers.oversized(size);
+          skipping = size;
+          continue;
+        }
+        expected = size;
+      }
+      if (buffer.length < expected) return;
+      const body = buffer.subarray(0, expected).toString('utf8');
+      buffer = buffer.subarray(expected);
+      expected = -1;
+      handlers.message(body);
+    }
+  });
+
+  function fail(reason: string): void {
+    broken = true;
+    handlers.malformed(reason);
+  }
+}
@@ -1,6 +1,6 @@
 /**
  * Bridge handlers — maps RPC method names to core function calls.
- * Each handler receives params + password (from Tauri keychain) and returns a result.
+ * Each handler receives params + password (resolved by the Tauri shell) and returns a result.
  */
 
 import {
@@ -19,17 +19,20 @@ import {
   DefaultPolicyEngine,
   classifyStatement,
   defaultSafeModeConfig,
+  type RowStream,
   type StoredProfile,
   type SchemaSnapshot,
   type GuardrailMode,
 } from '@openquery/core';
 import Database from 'better-sqlite3';
-import { existsSync, mkdirSync, rmSync } from 'node:fs';
+import { existsSync, mkdirSync, readFileSync, rmSync } from 'node:fs';
 import { homedir } from 'node:os';
 import { dirname, join } from 'node:path';
 import { fileURLToPath } from 'node:url';
 import { createServer } from 'node:net';
 import { spawn } from 'node:child_process';
+import { BridgeError, ErrorCode } from './errors.js';
+import { ENCODINGS } from './framing.js';
 
 let store: LocalStore | null = null;
 const launchEnvOpenAiKey = process.env.OPENAI_API_KEY;
@@ -88,6 +91,16 @@ INSERT INTO internal_audit_log (action, actor) VALUES
   ('seed_loaded', 'system');
 `;
 
+/** Per-request hooks passed to handlers alongside params. */
+export interface RequestContext {
+  /** Send a progress notification for this request; ignored once it is cancelled. */
+  progress(data: Record<string, unknown>): void;
+  /** Aborted when the shell cancels the request or gives up waiting for it. */
+  signal: AbortSignal;
+}
+
+const NO_CONTEXT: RequestContext = { progress: () => {}, signal: new AbortController().signal };
+
 function getStore(): LocalStore {
   if (!store) {
     store = new LocalStore(defaultDbPath());
@@ -314,13 +327,13 @@ export function profilesAdd(params: {
   return profile;
 }
 
-export function profilesRemove(params: { name: string }): { ok: boolean } {
+export function profilesRemove(params: { name: string }): { ok: boolean; profileId: string } {
   const s = getStore();
   const profile = s.getProfileByName(params.name);
   if (!profile) throw new Error(`Profile "${params.name}" not found.`);
   s.deleteProfile(params.name);
   s.logAudit('profile_removed', { name: params.name });
-  return { ok: true, profileId: profile.id } as { ok: boolean };
+  return { ok: true, profileId: profile.id };
 }
 
 export function profilesUse(params: { name: string }): { ok: boolean } {
@@ -351,9 +364,13 @@ export function profilesGetActive(): { name: string | null } {
 
 // ── Schema handlers ───────────────────────────────────────────────
 
-export async function schemaRefresh(params: { name?: string; password: string }): Promise<{ tables: number; columns: number }> {
+export async function schemaRefresh(
+  params: { name?: string; password: string },
+  ctx: RequestContext = NO_CONTEXT,
+): Promise<{ tables: number; columns: number }> {
   const s = getStore();
   const profile = getProfile(params.name);
+  ctx.progress({ stage: 'introspecting', profile: profile.name });
   const snapshot = await introspectSchemaForConnection({
     dbType: profile.db_type,
     host: profile.host ?? 'localhost',
@@ -363,6 +380,7 @@ export async function schemaRefresh(params: { name?: string; password: string })
     password: params.password,
     ssl: profile.ssl === 1,
   });
+  ctx.progress({ stage: 'saving', tables: snapshot.tables.length });
   const snapshotJson = JSON.stringify(snapshot);
   s.storeSchemaSnapshot(profile.id, snapshotJson);
   s.logAudit('schema_refreshed', { profile: profile.name });
@@ -417,13 +435,16 @@ export function schemaGetSnapshot(params: { name?: string }): SchemaSnapshot | n
 
 // ── Ask handlers ──────────────────────────────────────────────────
 
-export async function askDryRun(params: {
-  question: string;
-  mode?: string;
-  password: string;
-  name?: string;
-  openAiApiKey?: string;
-}): Promise<unknown> {
+export async function askDryRun(
+  params: {
+    question: string;
+    mode?: string;
+    password: string;
+    name?: string;
+    openAiApiKey?: string;
+  },
+  ctx: RequestContext = NO_CONTEXT,
+): Promise<unknown> {
   const s = getStore();
   const profile = getProfile(params.name);
   const mode: GuardrailMode = params.mode === 'standard' ? 'standard' : 'safe';
@@ -445,18 +466,22 @@ export async function askDryRun(params: {
       mode,
       execute: false,
       dryRun: true,
+      signal: ctx.signal,
     },
     s,
   );
 }
 
-export async function askRun(params: {
-  question: string;
-  mode?: string;
-  password: string;
-  name?: string;
-  openAiApiKey?: string;
-}): Promise<unknown> {
+export async function askRun(
+  params: {
+    question: string;
+    mode?: string;
+    password: string;
+    name?: string;
+    openAiApiKey?: string;
+  },
+  ctx: RequestContext = NO_CONTEXT,
+): Promise<unknown> {
   const s = getStore();
   const profile = getProfile(params.name);
   const mode: GuardrailMode = params.mode === 'standard' ? 'standard' : 'safe';
@@ -478,6 +503,7 @@ export async function askRun(params: {
       mode,
       execute: true,
       dryRun: false,
+      signal: ctx.signal,
     },
     s,
   );
@@ -485,18 +511,36 @@ export async function askRun(params: {
 
 // ── Workspace SQL handlers ─────────────────────────────────────────
 
-export async function workspaceSql(params: {
-  sql: string;
-  mode?: string;
-  action?: string;
-  password: string;
-  name?: string;
-  policy?: {
-    maxRowsThreshold?: number;
-    maxCostThreshold?: number;
-    enforceLimit?: boolean;
+const ROW_BATCH_SIZE = 1_000;
+
+/**
+ * Send result rows as `rows` progress batches while the query fetches them.
+ * The final result has no rows but keeps `rowCount`, so the UI knows when
+ * every batch has arrived.
+ */
+function rowProgress(ctx: RequestContext): RowStream {
+  return {
+    batchSize: ROW_BATCH_SIZE,
+    onRows: (batch) => ctx.progress({ stage: 'rows', ...batch }),
   };
-}): Promise<unknown> {
+}
+
+export async function workspaceSql(
+  params: {
+    sql: string;
+    mode?: string;
+    action?: string;
+    password: string;
+    name?: string;
+    policy?: {
+      maxRowsThreshold?: number;
+      maxCostThreshold?: number;
+      enforceLimit?: boolean;
+    };
+    streamRows?: boolean;
+  },
+  ctx: RequestContext = NO_CONTEXT,
+): Promise<unknown> {
   const sql = (params.sql ?? '').trim();
   if (!sql) throw new Error('SQL cannot be empty.');
 
@@ -555,6 +599,7 @@ export async function workspaceSql(params: {
     user: profile.user ?? '',
     password: params.password,
     ssl: profile.ssl === 1,
+    signal: ctx.signal,
   };
 
   const shouldExplain =
@@ -565,6 +610,7 @@ export async function workspaceSql(params: {
   let explainBlockers: string[] = [];
 
   if (shouldExplain) {
+    ctx.progress({ stage: 'explaining' });
     try {
       const explain = await explainQuery({
         ...conn,
@@ -633,7 +679,10 @@ export async function workspaceSql(params: {
     };
   }
 
+  // Only a plain SELECT can be read through a cursor.
+  const streamRows = Boolean(params.streamRows) && classification.kind === 'select';
   try {
+    ctx.progress({ stage: 'executing' });
     const executionResult = await executeQuery({
       ...conn,
       sql: rewrittenSql,
@@ -641,6 +690,7 @@ export async function workspaceSql(params: {
         maxRows: SAFE_DEFAULTS.maxRows,
         statementTimeoutMs: SAFE_DEFAULTS.statementTimeoutMs,
       },
+      stream: streamRows ? rowProgress(ctx) : undefined,
     });
     return {
       ...base,
@@ -648,7 +698,7 @@ export async function workspaceSql(params: {
       explainSummary,
       explainWarnings,
       explainBlockers,
-      executionResult,
+      executionResult: streamRows ? { ...executionResult, rowsStreamed: true } : executionResult,
     };
   } catch (err: unknown) {
     const msg = err instanceof Error ? err.message : String(err);
@@ -1117,7 +1167,7 @@ export async function writeExecuteHandler(params: {
 
 // ── Method dispatch ──────────────────────────────────────────────
 
-type MethodHandler = (params: any) => unknown | Promise<unknown>;
+type MethodHandler = (params: any, ctx: RequestContext) => unknown | Promise<unknown>;
 
 const METHODS: Record<string, MethodHandler> = {
   'profiles.list': profilesList,
@@ -1153,10 +1203,37 @@ const METHODS: Record<string, MethodHandler> = {
   'write.execute': writeExecuteHandler,
 };
 
-export async function dispatch(method: string, params: unknown): Promise<unknown> {
+/**
+ * Wire protocol version announced in the ready handshake. Bump when the
+ * message format changes in a way the Rust shell has to know about.
+ */
+export const BRIDGE_PROTOCOL_VERSION = 5;
+
+function readBridgeVersion(): string {
+  // dist/handlers.js -> apps/desktop/package.json
+  const pkgPath = join(dirname(fileURLToPath(import.meta.url)), '..', '..', 'package.json');
+  try {
+    const pkg = JSON.parse(readFileSync(pkgPath, 'utf8')) as { version?: string };
+    if (pkg.version) return pkg.version;
+  } catch {
+    // fall through
+  }
+  return process.env.npm_package_version || '0.0.0';
+}
+
+export function handshake(): { protocolVersion: number; bridgeVersion: string; methods: string[]; encodings: string[] } {
+  return {
+    protocolVersion: BRIDGE_PROTOCOL_VERSION,
+    bridgeVersion: readBridgeVersion(),
+    methods: Object.keys(METHODS),
+    encodings: [...ENCODINGS],
+  };
+}
+
+export async function dispatch(method: string, params: unknown, ctx: RequestContext = NO_CONTEXT): Promise<unknown> {
   const handler = METHODS[method];
-  if (!handler) throw new Error(`Unknown method: ${method}`);
-  return handler(params as any);
+  if (!handler) throw new BridgeError(ErrorCode.UnknownMethod, `Unknown method: ${method}`);
+  return handler(params as any, ctx);
 }
 
 export function shutdown(): void {
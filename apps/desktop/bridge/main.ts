@@ -2,32 +2,159 @@
 
 /**
  * Bridge process — JSON-RPC over stdin/stdout.
- * Tauri spawns this process and communicates via newline-delimited JSON.
+ * Tauri spawns this process and exchanges JSON messages with it, each in a
+ * `Content-Length: N\r\n\r\n` frame (see framing.ts).
  *
- * Request:  {"id":"uuid","method":"profiles.list","params":{}}\n
- * Response: {"id":"uuid","result":{...}}\n
- * Error:    {"id":"uuid","error":"message"}\n
+ * Request:  {"id":"uuid","method":"profiles.list","params":{}}
+ * Response: {"id":"uuid","result":{...}}
+ * Error:    {"id":"uuid","error":{"code":"AUTH_FAILED","message":"...","data":...}}
+ *
+ * Requests are handled concurrently and may be answered out of order.
+ * Cancel:   {"method":"bridge.cancel","params":{"id":"uuid"}}  (notification, no reply)
+ * A cancelled request's handler sees its `signal` abort (a running query is
+ * cancelled on the server); whatever it still returns is discarded.
+ *
+ * Progress: {"id":"uuid","progress":{...}}  (zero or more, before the response)
+ * Event:    {"event":"name","data":{...}}   (not tied to a request)
+ *
+ * Ready:    {"id":null,"result":"bridge_ready","handshake":{"protocolVersion":5,"bridgeVersion":"x.y.z","methods":[...],"encodings":["json","msgpack"]}}
+ *
+ * Encoding: {"id":"uuid","method":"bridge.encoding","params":{"encoding":"msgpack"}}
+ * Acknowledged in the current encoding; every later message uses the new one
+ * (see framing.ts).
+ *
+ * A response larger than the frame limit is replaced by a FRAME_TOO_LARGE error.
+ *
+ * Ping:     {"id":"uuid","method":"bridge.ping","params":{}}  ->  {"id":"uuid","result":{}}
+ * Answered as soon as it is read, ahead of handler work and even while shutting
+ * down, so the shell can tell a busy bridge from a wedged one.
+ *
+ * Shutdown: {"id":"uuid","method":"bridge.shutdown","params":{}}
+ * Stops accepting requests, lets in-flight ones finish (up to SHUTDOWN_DRAIN_MS),
+ * closes the local store, replies {"id","result":{"drained":bool}} and exits.
+ *
+ * With OPENQUERY_BRIDGE_SOCKET set, the same protocol runs over a Unix socket
+ * instead: the bridge listens on that path and serves the first shell that
+ * connects, so it can be started by hand (e.g. under a debugger) and attached.
  */
 
-import { createInterface } from 'node:readline';
-import { dispatch, shutdown } from './handlers.js';
+import { createServer } from 'node:net';
+import type { Readable, Writable } from 'node:stream';
+import { dispatch, handshake, shutdown } from './handlers.js';
+import { BridgeError, ErrorCode, toWireError } from './errors.js';
+import { encodeBody, encodeFrame, isEncoding, maxFrameBytes, readFrames, type Encoding } from './framing.js';
 
-const rl = createInterface({ input: process.stdin });
+const SHUTDOWN_DRAIN_MS = 2_000;
+const MAX_FRAME = maxFrameBytes();
 
-rl.on('line', async (line: string) => {
-  let id: string | null = null;
+const channel = await openChannel();
+const inFlight = new Map<string, AbortController>();
+const cancelled = new Set<string>();
+const running = new Set<Promise<void>>();
+let stopping = false;
+let encoding: Encoding = 'json';
+
+readFrames(channel.input, MAX_FRAME, {
+  message: onMessage,
+  oversized: (size) => {
+    const error = new BridgeError(
+      ErrorCode.FrameTooLarge,
+      `Request of ${size} bytes exceeds the ${MAX_FRAME}-byte frame limit`,
+    );
+    write({ id: null, error: toWireError(error) });
+  },
+  malformed: (reason) => {
+    console.error(`[bridge] Input is not framed (${reason}); exiting`);
+    shutdown();
+    process.exit(1);
+  },
+});
+
+function onMessage(body: string): void {
+  let msg: any;
   try {
-    const msg = JSON.parse(line);
-    id = msg.id ?? null;
-    const result = await dispatch(msg.method, msg.params ?? {});
-    write({ id, result: result ?? null });
+    msg = JSON.parse(body);
+    if (typeof msg !== 'object' || msg === null) throw new Error('Request must be a JSON object');
   } catch (err: unknown) {
-    const message = err instanceof Error ? err.message : String(err);
-    write({ id, error: message });
+    reply(null, { id: null, error: toWireError(err) });
+    return;
   }
-});
+  if (msg.method === 'bridge.cancel' && msg.id == null) {
+    const target = msg.params?.id;
+    const controller = typeof target === 'string' ? inFlight.get(target) : undefined;
+    if (controller) {
+      cancelled.add(target);
+      controller.abort();
+    }
+    return;
+  }
+  if (msg.method === 'bridge.ping') {
+    write({ id: msg.id ?? null, result: {} });
+    return;
+  }
+  if (msg.method === 'bridge.encoding') {
+    const wanted = msg.params?.encoding;
+    if (!isEncoding(wanted)) {
+      const error = new BridgeError(ErrorCode.Internal, `Unsupported encoding: ${String(wanted)}`);
+      write({ id: msg.id ?? null, error: toWireError(error) });
+      return;
+    }
+    write({ id: msg.id ?? null, result: { encoding: wanted } });
+    encoding = wanted;
+    return;
+  }
+  if (msg.method === 'bridge.shutdown') {
+    void shutdownGracefully(msg.id ?? null);
+    return;
+  }
+  const task = handle(msg);
+  running.add(task);
+  void task.finally(() => running.delete(task));
+}
+
+async function handle(msg: any): Promise<void> {
+  const id: string | null = msg.id ?? null;
+  try {
+    if (stopping) throw new BridgeError(ErrorCode.ShuttingDown, 'Bridge is shutting down');
+    const controller = new AbortController();
+    if (id) inFlight.set(id, controller);
+    const requestId = id;
+    const result = await dispatch(msg.method, msg.params ?? {}, {
+      progress: (data) => {
+        if (requestId && inFlight.has(requestId) && !cancelled.has(requestId)) {
+          write({ id: requestId, progress: data });
+        }
+      },
+      signal: controller.signal,
+    });
+    reply(id, { id, result: result ?? null });
+  } catch (err: unknown) {
+    reply(id, { id, error: toWireError(err) });
+  }
+}
 
-rl.on('close', () => {
+async function shutdownGracefully(id: string | null): Promise<void> {
+  if (stopping) return;
+  stopping = true;
+  const drained = await Promise.race([
+    Promise.allSettled([...running]).then(() => true),
+    new Promise<boolean>((resolve) => setTimeout(() => resolve(false), SHUTDOWN_DRAIN_MS)),
+  ]);
+  shutdown();
+  // Exit only once the acknowledgement has been flushed to the shell.
+  write({ id, result: { drained } }, () => process.exit(0));
+}
+
+function reply(id: string | null, msg: Record<string, unknown>): void {
+  if (id) {
+    inFlight.delete(id);
+    if (cancelled.delete(id)) return;
+  }
+  write(msg);
+}
+
+channel.input.on('end', () => {
+  if (stopping) return;
   shutdown();
   process.exit(0);
 });
@@ -37,9 +164,38 @@ process.on('SIGTERM', () => {
   process.exit(0);
 });
 
-function write(msg: unknown): void {
-  process.stdout.write(JSON.stringify(msg) + '\n');
+/** Every message puts `id` first, so the shell can tell which call a frame too large to parse answers. */
+function write(msg: Record<string, unknown>, flushed?: () => void): void {
+  let body = encodeBody(msg, encoding);
+  const size = body.length;
+  if (size > MAX_FRAME) {
+    // A final result becomes an error the caller sees; an oversized progress update or event is dropped.
+    if (typeof msg.id !== 'string' || !('result' in msg)) {
+      console.error(`[bridge] Dropping ${size}-byte message over the ${MAX_FRAME}-byte frame limit`);
+      flushed?.();
+      return;
+    }
+    const error = new BridgeError(
+      ErrorCode.FrameTooLarge,
+      `Response of ${size} bytes exceeds the ${MAX_FRAME}-byte frame limit (OPENQUERY_BRIDGE_MAX_FRAME_MB)`,
+    );
+    body = encodeBody({ id: msg.id, error: toWireError(error) }, encoding);
+  }
+  channel.output.write(encodeFrame(body, encoding), flushed);
+}
+
+async function openChannel(): Promise<{ input: Readable; output: Writable }> {
+  const socketPath = process.env.OPENQUERY_BRIDGE_SOCKET;
+  if (!socketPath) return { input: process.stdin, output: process.stdout };
+  return new Promise((resolve, reject) => {
+    const server = createServer((socket) => {
+      server.close();
+      resolve({ input: socket, output: socket });
+    });
+    server.on('error', reject);
+    server.listen(socketPath, () => console.error(`[bridge] Waiting for the shell on ${socketPath}`));
+  });
 }
 
-// Signal ready
-write({ id: null, result: 'bridge_ready' });
+// Signal ready, announcing the protocol version and supported methods
+write({ id: null, result: 'bridge_ready', handshake: handshake() });
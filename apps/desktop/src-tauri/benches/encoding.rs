@@ -0,0 +1,91 @@
+// JSON vs MessagePack for a 100k-row result, through the steps the shell
+// takes: route the frame (reader task), decode the typed result (caller), and
+// serialize it for the webview. Run with:
+//
+//     cargo bench --bench encoding
+
+use openquery_desktop::bridge::{Incoming, Response};
+use openquery_desktop::framing::Encoding;
+use openquery_desktop::protocol;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+const ROWS: usize = 100_000;
+const RUNS: u32 = 5;
+
+fn main() {
+    let message = wide_result(ROWS);
+
+    println!("{} rows, best of {} runs", ROWS, RUNS);
+    println!("{:<8} {:>10} {:>10} {:>10} {:>10} {:>10}", "encoding", "size MB", "route ms", "typed ms", "webview ms", "total ms");
+    for encoding in [Encoding::Json, Encoding::MessagePack] {
+        // What the Node bridge sends for this encoding.
+        let body = match encoding {
+            Encoding::Json => serde_json::to_vec(&message).unwrap(),
+            Encoding::MessagePack => rmp_serde::to_vec(&message).unwrap(),
+        };
+        let mut best = [Duration::MAX; 3];
+        for _ in 0..RUNS {
+            let response = Response { body: body.clone(), encoding };
+            let start = Instant::now();
+            Incoming::decode(&response.body, encoding).unwrap();
+            let decoded = start.elapsed();
+            let typed = protocol::decode::<protocol::WorkspaceSql>(&response).unwrap();
+            let typed_at = start.elapsed();
+            let webview = serde_json::to_vec(&typed).unwrap();
+            let done = start.elapsed();
+            assert_eq!(typed.execution_result.unwrap().rows.len(), ROWS);
+            assert!(!webview.is_empty());
+            for (slot, time) in best.iter_mut().zip([decoded, typed_at - decoded, done - typed_at]) {
+                *slot = (*slot).min(time);
+            }
+        }
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        println!(
+            "{:<8} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>10.1}",
+            encoding.name(),
+            body.len() as f64 / (1024.0 * 1024.0),
+            ms(best[0]),
+            ms(best[1]),
+            ms(best[2]),
+            ms(best[0] + best[1] + best[2]),
+        );
+    }
+}
+
+/// A `workspace.sql` response with `rows` rows of a typical mixed-type table.
+fn wide_result(rows: usize) -> Value {
+    let columns = ["id", "email", "name", "created_at", "active", "score", "balance", "notes"];
+    let rows: Vec<Value> = (0..rows)
+        .map(|i| {
+            json!({
+                "id": i,
+                "email": format!("user{}@example.com", i),
+                "name": format!("User Number {}", i),
+                "created_at": "2026-01-01T12:34:56.000Z",
+                "active": i % 3 != 0,
+                "score": i as f64 * 0.25,
+                "balance": (i as i64 - 50_000) * 100,
+                "notes": if i % 4 == 0 { Value::Null } else { json!("lorem ipsum dolor sit amet") },
+            })
+        })
+        .collect();
+    let result = json!({
+        "status": "ok",
+        "classification": { "kind": "read" },
+        "validation": { "ok": true },
+        "rewrittenSql": "SELECT id FROM t LIMIT 100",
+        "explainSummary": {},
+        "explainWarnings": [],
+        "explainBlockers": [],
+        "error": null,
+        "executionResult": {
+            "columns": columns,
+            "rowCount": rows.len(),
+            "rows": rows,
+            "truncated": false,
+            "execMs": 12.5,
+        },
+    });
+    json!({ "id": "bench", "result": result })
+}
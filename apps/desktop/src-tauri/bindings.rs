@@ -0,0 +1,371 @@
+// TypeScript bindings for the Tauri commands, generated by build.rs.
+//
+// Reads the `#[tauri::command]` functions in src/main.rs, the serde types in
+// TYPE_SOURCES and the error kinds in src/error.rs, and renders
+// ../src/bindings.ts. The checked-in file must match what the Rust code would
+// generate: a stale file fails the build unless OPENQUERY_WRITE_BINDINGS=1 is
+// set, in which case it is rewritten.
+
+use std::collections::BTreeSet;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use syn::visit::Visit;
+use syn::{Attribute, Fields, FnArg, GenericArgument, Item, ItemFn, ItemStruct, Lit, PathArguments, ReturnType, Type};
+
+const OUTPUT: &str = "../src/bindings.ts";
+const WRITE_ENV: &str = "OPENQUERY_WRITE_BINDINGS";
+
+/// Modules whose serde types can appear in command signatures.
+const TYPE_SOURCES: &[&str] = &[
+    "src/protocol.rs",
+    "src/node.rs",
+    "src/settings.rs",
+    "src/diagnostics.rs",
+    "src/logs.rs",
+];
+
+/// Argument types Tauri injects itself; they are not part of the invoke payload.
+const INJECTED_ARGS: &[&str] = &["State", "AppHandle", "Window", "WebviewWindow", "Webview"];
+
+pub fn generate(manifest_dir: &Path) {
+    for source in ["src/main.rs", "src/error.rs"].iter().chain(TYPE_SOURCES) {
+        println!("cargo:rerun-if-changed={}", source);
+    }
+    println!("cargo:rerun-if-changed={}", OUTPUT);
+    println!("cargo:rerun-if-env-changed={}", WRITE_ENV);
+
+    let parse = |name: &str| -> syn::File {
+        let path = manifest_dir.join(name);
+        let text = fs::read_to_string(&path).unwrap_or_else(|e| panic!("read {}: {}", path.display(), e));
+        syn::parse_file(&text).unwrap_or_else(|e| panic!("parse {}: {}", path.display(), e))
+    };
+    let types: Vec<syn::File> = TYPE_SOURCES.iter().map(|name| parse(name)).collect();
+    let rendered = render(&parse("src/main.rs"), &types, &parse("src/error.rs"));
+
+    let output = manifest_dir.join(OUTPUT);
+    let current = fs::read_to_string(&output).unwrap_or_default();
+    if current == rendered {
+        return;
+    }
+    if std::env::var(WRITE_ENV).is_ok_and(|v| v == "1") {
+        fs::write(&output, rendered).unwrap_or_else(|e| panic!("write {}: {}", output.display(), e));
+        return;
+    }
+    panic!(
+        "apps/desktop/src/bindings.ts is out of date with the Tauri commands.\n\
+         Regenerate it with `pnpm --filter @openquery/desktop bindings` \
+         (or {}=1 cargo build) and commit the result.",
+        WRITE_ENV
+    );
+}
+
+fn render(main: &syn::File, types: &[syn::File], error: &syn::File) -> String {
+    let structs: Vec<&ItemStruct> = types
+        .iter()
+        .flat_map(|file| &file.items)
+        .filter_map(|item| match item {
+            // Types the UI can see are exactly the ones that cross back through serde.
+            Item::Struct(s) if derives_deserialize(&s.attrs) => Some(s),
+            _ => None,
+        })
+        .collect();
+    let known: BTreeSet<String> = structs.iter().map(|s| s.ident.to_string()).collect();
+
+    let mut out = String::new();
+    out.push_str(
+        "// Generated by src-tauri/build.rs from the Tauri commands and serde types in src-tauri/src.\n\
+         // Do not edit by hand: run `pnpm --filter @openquery/desktop bindings` to regenerate.\n\n",
+    );
+
+    let kinds = error_kinds(error);
+    let _ = writeln!(
+        out,
+        "export type BridgeErrorKind = {};\n",
+        kinds.iter().map(|k| format!("'{}'", k)).collect::<Vec<_>>().join(" | ")
+    );
+    out.push_str(
+        "/** Rejection value of every command. */\n\
+         export interface BridgeError {\n  kind: BridgeErrorKind;\n  code: string;\n  message: string;\n  details: unknown;\n}\n",
+    );
+
+    for s in &structs {
+        out.push('\n');
+        render_struct(&mut out, s, &known);
+    }
+
+    out.push_str("\n/** Every Tauri command, keyed by name, with its invoke arguments and result. */\nexport interface Commands {\n");
+    for item in &main.items {
+        if let Item::Fn(f) = item {
+            if is_command(f) {
+                render_command(&mut out, f, &known);
+            }
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn render_struct(out: &mut String, s: &ItemStruct, known: &BTreeSet<String>) {
+    write_doc(out, &s.attrs, "");
+    let Fields::Named(fields) = &s.fields else {
+        panic!("bindings: {} must have named fields", s.ident);
+    };
+    if fields.named.is_empty() {
+        let _ = writeln!(out, "export type {} = Record<string, never>;", s.ident);
+        return;
+    }
+
+    let camel = serde_value(&s.attrs, "rename_all").as_deref() == Some("camelCase");
+    let mut extends = Vec::new();
+    let mut body = String::new();
+    for field in &fields.named {
+        if serde_flag(&field.attrs, "flatten") {
+            extends.push(ts_type(&field.ty, known));
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field").to_string();
+        let name = serde_value(&field.attrs, "rename").unwrap_or_else(|| if camel { to_camel(&ident) } else { ident });
+        let optional = option_inner(&field.ty).is_some()
+            && (serde_flag(&field.attrs, "default") || serde_value(&field.attrs, "skip_serializing_if").is_some());
+        write_doc(&mut body, &field.attrs, "  ");
+        let _ = writeln!(body, "  {}{}: {};", name, if optional { "?" } else { "" }, ts_type(&field.ty, known));
+    }
+
+    let extends = if extends.is_empty() {
+        String::new()
+    } else {
+        format!(" extends {}", extends.join(", "))
+    };
+    let _ = write!(out, "export interface {}{} {{\n{}}}\n", s.ident, extends, body);
+}
+
+fn render_command(out: &mut String, f: &ItemFn, known: &BTreeSet<String>) {
+    let mut args = Vec::new();
+    for input in &f.sig.inputs {
+        let FnArg::Typed(arg) = input else { continue };
+        if INJECTED_ARGS.contains(&last_ident(&arg.ty).as_str()) {
+            continue;
+        }
+        let syn::Pat::Ident(pat) = arg.pat.as_ref() else {
+            panic!("bindings: {} has a non-identifier argument", f.sig.ident);
+        };
+        let optional = option_inner(&arg.ty).is_some();
+        args.push(format!(
+            "{}{}: {}",
+            to_camel(&pat.ident.to_string()),
+            if optional { "?" } else { "" },
+            ts_type(&arg.ty, known)
+        ));
+    }
+    let args = if args.is_empty() {
+        "Record<string, never>".to_string()
+    } else {
+        format!("{{ {} }}", args.join("; "))
+    };
+    let result = match &f.sig.output {
+        ReturnType::Default => "null".to_string(),
+        ReturnType::Type(_, ty) => ts_type(ty, known),
+    };
+    write_doc(out, &f.attrs, "  ");
+    let _ = writeln!(out, "  {}: {{ args: {}; result: {} }};", f.sig.ident, args, result);
+}
+
+fn ts_type(ty: &Type, known: &BTreeSet<String>) -> String {
+    match ty {
+        Type::Reference(r) => ts_type(&r.elem, known),
+        Type::Tuple(t) if t.elems.is_empty() => "null".to_string(),
+        Type::Path(p) => {
+            let segment = p.path.segments.last().expect("type path");
+            let generics: Vec<&Type> = match &segment.arguments {
+                PathArguments::AngleBracketed(a) => a
+                    .args
+                    .iter()
+                    .filter_map(|g| match g {
+                        GenericArgument::Type(t) => Some(t),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+            let name = segment.ident.to_string();
+            match (name.as_str(), generics.as_slice()) {
+                ("String" | "str", []) => "string".to_string(),
+                ("bool", []) => "boolean".to_string(),
+                ("u8" | "u16" | "u32" | "u64" | "usize" | "i32" | "i64" | "f32" | "f64", []) => "number".to_string(),
+                ("Value", []) => "unknown".to_string(),
+                ("Map" | "HashMap" | "BTreeMap", [_, value]) => format!("Record<string, {}>", ts_type(value, known)),
+                ("Vec", [item]) => {
+                    let item = ts_type(item, known);
+                    if item.contains(" | ") {
+                        format!("({})[]", item)
+                    } else {
+                        format!("{}[]", item)
+                    }
+                }
+                ("Option", [inner]) => {
+                    let inner = ts_type(inner, known);
+                    if inner.ends_with(" | null") {
+                        inner
+                    } else {
+                        format!("{} | null", inner)
+                    }
+                }
+                ("Result", [ok, _]) => ts_type(ok, known),
+                (other, []) if known.contains(other) => other.to_string(),
+                _ => panic!("bindings: no TypeScript mapping for `{}`", quote_type(ty)),
+            }
+        }
+        _ => panic!("bindings: no TypeScript mapping for `{}`", quote_type(ty)),
+    }
+}
+
+fn quote_type(ty: &Type) -> String {
+    use quote::ToTokens;
+    ty.to_token_stream().to_string()
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(p) = ty else { return None };
+    let segment = p.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    match &segment.arguments {
+        PathArguments::AngleBracketed(a) => a.args.iter().find_map(|g| match g {
+            GenericArgument::Type(t) => Some(t),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+fn last_ident(ty: &Type) -> String {
+    match ty {
+        Type::Path(p) => p.path.segments.last().map(|s| s.ident.to_string()).unwrap_or_default(),
+        Type::Reference(r) => last_ident(&r.elem),
+        _ => String::new(),
+    }
+}
+
+fn is_command(f: &ItemFn) -> bool {
+    f.attrs
+        .iter()
+        .any(|a| a.path().segments.last().is_some_and(|s| s.ident == "command"))
+}
+
+fn derives_deserialize(attrs: &[Attribute]) -> bool {
+    let mut found = false;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("derive")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("Deserialize") {
+                found = true;
+            }
+            Ok(())
+        });
+    }
+    found
+}
+
+/// Value of `#[serde(key = "...")]`, if present.
+fn serde_value(attrs: &[Attribute], key: &str) -> Option<String> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.input.peek(syn::Token![=]) {
+                let lit: Lit = meta.value()?.parse()?;
+                if meta.path.is_ident(key) {
+                    if let Lit::Str(s) = lit {
+                        value = Some(s.value());
+                    }
+                }
+            }
+            Ok(())
+        });
+    }
+    value
+}
+
+/// Whether `#[serde(key)]` or `#[serde(key = ...)]` is present.
+fn serde_flag(attrs: &[Attribute], key: &str) -> bool {
+    let mut found = false;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) {
+                found = true;
+            }
+            if meta.input.peek(syn::Token![=]) {
+                let _: Lit = meta.value()?.parse()?;
+            }
+            Ok(())
+        });
+    }
+    found
+}
+
+fn write_doc(out: &mut String, attrs: &[Attribute], indent: &str) {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|a| a.path().is_ident("doc"))
+        .filter_map(|a| match &a.meta {
+            syn::Meta::NameValue(nv) => match &nv.value {
+                syn::Expr::Lit(syn::ExprLit { lit: Lit::Str(s), .. }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+    match lines.as_slice() {
+        [] => {}
+        [line] => {
+            let _ = writeln!(out, "{}/** {} */", indent, line);
+        }
+        _ => {
+            let _ = writeln!(out, "{}/**", indent);
+            for line in &lines {
+                let _ = writeln!(out, "{} * {}", indent, line);
+            }
+            let _ = writeln!(out, "{} */", indent);
+        }
+    }
+}
+
+/// The string literals returned by `BridgeError::kind`, in declaration order.
+fn error_kinds(error: &syn::File) -> Vec<String> {
+    struct Kinds(Vec<String>);
+    impl<'ast> Visit<'ast> for Kinds {
+        fn visit_lit_str(&mut self, lit: &'ast syn::LitStr) {
+            self.0.push(lit.value());
+        }
+    }
+
+    for item in &error.items {
+        let Item::Impl(imp) = item else { continue };
+        for impl_item in &imp.items {
+            if let syn::ImplItem::Fn(f) = impl_item {
+                if f.sig.ident == "kind" {
+                    let mut kinds = Kinds(Vec::new());
+                    kinds.visit_block(&f.block);
+                    return kinds.0;
+                }
+            }
+        }
+    }
+    panic!("bindings: BridgeError::kind not found in src/error.rs");
+}
+
+fn to_camel(snake: &str) -> String {
+    let mut out = String::with_capacity(snake.len());
+    let mut upper = false;
+    for c in snake.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
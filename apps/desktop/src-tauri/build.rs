@@ -1,13 +1,9 @@
+mod bindings;
+
 fn main() {
-    // Embed the absolute bridge path at compile time
+    // Keep src/bindings.ts in step with the commands
     let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
-    let bridge_path = std::path::Path::new(&manifest_dir)
-        .join("../bridge/dist/main.js")
-        .canonicalize()
-        .expect("Bridge not built! Run: pnpm --filter @openquery/desktop build:bridge");
-    println!(
-        "cargo:rustc-env=BRIDGE_SCRIPT_PATH={}",
-        bridge_path.display()
-    );
+    bindings::generate(std::path::Path::new(&manifest_dir));
+
     tauri_build::build()
 }
@@ -1,130 +1,847 @@
-// Bridge process management — spawns Node.js, communicates via stdin/stdout JSON-RPC.
+// Bridge protocol client — JSON-RPC over a `Connection`.
+//
+// The byte transport (child process stdio, a Unix socket, or in memory for
+// tests) lives in `crate::transport`, and each message travels in a
+// Content-Length frame (`crate::framing`); everything protocol-level is here.
+//
+// Everything here is async on the Tauri (tokio) runtime. A reader task owns
+// the read half and routes each response to the caller waiting on its request
+// id, so several calls can be in flight at once and the Node side is free to
+// answer them out of order. When the connection closes without a shutdown
+// having been requested, the reader reaps the other end and reports the exit
+// through the hook passed to `connect`, which the supervisor uses to restart.
+//
+// `shutdown` is the orderly way out: the bridge is sent `bridge.shutdown`,
+// which makes it finish in-flight work, close its local store and exit. A
+// bridge that does not comply in time gets SIGTERM, then a kill, and the
+// process is always reaped.
+//
+// Besides one final response per request, the bridge may send notifications:
+// `{"id", "progress"}` for an in-flight request, or `{"event", "data"}` with no
+// id. These go to the notification sink and never complete a call.
+//
+// A bridge that lists "msgpack" in its handshake's `encodings` is asked, via
+// `bridge.encoding`, to send everything after the acknowledgement as
+// MessagePack, which is smaller than JSON and decodes faster for wide query
+// results. Each frame says how it is encoded, so the switch needs no
+// synchronization.
+//
+// Liveness: every frame read refreshes the bridge's last-seen time, and the
+// supervisor's heartbeat sends `bridge.ping`, which the bridge answers as soon
+// as it reads it, ahead of any handler work. A bridge that is busy but alive
+// keeps answering; one whose event loop is wedged stops. Pings share the pipe
+// with requests, so one sent while a large request is going out waits for it
+// and then for the bridge to read it; a ping that times out while any other
+// frame was being written is not counted as missed.
 
+use crate::error::BridgeError;
+use crate::framing::{self, Encoding, Frame, Wire};
+use crate::metrics::Metrics;
+use crate::recording::Recorder;
+use crate::transport::{BoxReader, BoxWriter, BridgeTransport, ChildOptions, Connection, LineSink, StdioTransport};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
-use std::process::{Child, Command, Stdio};
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncWriteExt, BufReader};
+use tokio::sync::oneshot;
+use tracing::{debug, info, trace, warn, Instrument};
 use uuid::Uuid;
 
-pub struct Bridge {
-    child: Mutex<Child>,
+type Reply = Result<Response, BridgeError>;
+type PendingMap = Arc<Mutex<Pending>>;
+
+/// Default deadline for a method. Lookups against the local store are quick;
+/// anything that talks to the database, the LLM or Docker gets much longer.
+pub fn default_timeout(method: &str) -> Duration {
+    match method {
+        "ask.run" | "ask.dryRun" | "schema.refresh" | "workspace.sql" | "write.preview"
+        | "write.execute" | "fixture.up" => Duration::from_secs(300),
+        "profiles.test" | "settings.testOpenAiKey" => Duration::from_secs(30),
+        m if m.starts_with("fixture.") || m.starts_with("demo.") => Duration::from_secs(120),
+        m if m.starts_with("profiles.") || m.starts_with("profile.") || m.starts_with("settings.") => {
+            Duration::from_secs(10)
+        }
+        _ => Duration::from_secs(60),
+    }
 }
 
-impl Bridge {
-    fn resolve_node_binary() -> String {
-        if let Ok(path) = std::env::var("OPENQUERY_NODE_PATH") {
-            if Path::new(&path).exists() {
-                return path;
+/// Called once from the reader task when the bridge exits without being asked to.
+pub type ExitHook = Box<dyn FnOnce(String) + Send + 'static>;
+
+pub type SpawnError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Out-of-band messages from the bridge, forwarded to the frontend as events.
+#[derive(Debug, Clone)]
+pub enum Notification {
+    /// Partial progress (stage updates, row batches) for an in-flight request.
+    Progress { request_id: String, data: Value },
+    /// Bridge-wide notification not tied to a request.
+    Event { name: String, data: Value },
+}
+
+pub type NotificationSink = Arc<dyn Fn(Notification) + Send + Sync>;
+
+/// Wire protocol version this shell speaks; must match the bridge's handshake.
+pub const PROTOCOL_VERSION: u32 = 5;
+
+/// How long `shutdown` waits for the bridge to acknowledge `bridge.shutdown`
+/// (it drains in-flight requests first), for the process to exit after that,
+/// and for it to exit after SIGTERM before killing it.
+const SHUTDOWN_ACK_TIMEOUT: Duration = Duration::from_secs(4);
+const SHUTDOWN_EXIT_TIMEOUT: Duration = Duration::from_secs(2);
+const SHUTDOWN_TERM_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How often the supervisor pings the bridge, how long each ping may take,
+/// and how many must go unanswered in a row before it is reported unresponsive.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+pub const PING_TIMEOUT: Duration = Duration::from_secs(5);
+pub const UNRESPONSIVE_AFTER: u32 = 2;
+
+/// How long to wait for the bridge to acknowledge `bridge.encoding`.
+const ENCODING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long a started bridge may take to send its ready handshake. Node loads
+/// the bundled bridge in well under a second; a script that never finishes
+/// starting must not keep the launch waiting forever.
+pub const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What the bridge announced in its ready handshake.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeInfo {
+    pub protocol_version: u32,
+    pub bridge_version: String,
+    pub methods: Vec<String>,
+    /// Encodings the bridge can answer in; JSON only if absent.
+    #[serde(default)]
+    pub encodings: Vec<String>,
+}
+
+impl BridgeInfo {
+    pub fn supports(&self, method: &str) -> bool {
+        self.methods.iter().any(|m| m == method)
+    }
+}
+
+/// Where the bridge script lives and, in packaged builds, the Node runtime shipped with it.
+#[derive(Debug, Clone)]
+pub struct BridgeLocation {
+    pub script: PathBuf,
+    pub bundled_node: Option<PathBuf>,
+}
+
+impl BridgeLocation {
+    /// `OPENQUERY_BRIDGE_PATH` wins, for development against a locally built bridge.
+    /// Otherwise use the copy bundled under `<resources>/bridge` (see
+    /// scripts/bundle-bridge.sh); debug builds finally fall back to the source tree.
+    pub fn resolve(resource_dir: Option<&Path>) -> Result<Self, String> {
+        let bundle = resource_dir.map(|dir| dir.join("bridge"));
+        let bundled_node = bundle
+            .as_ref()
+            .map(|dir| dir.join("bin").join(if cfg!(windows) { "node.exe" } else { "node" }))
+            .filter(|node| node.is_file());
+
+        if let Ok(path) = std::env::var("OPENQUERY_BRIDGE_PATH") {
+            return Ok(BridgeLocation {
+                script: PathBuf::from(path),
+                bundled_node,
+            });
+        }
+
+        let mut tried = Vec::new();
+        if let Some(bundle) = &bundle {
+            let script = bundle.join("bridge").join("dist").join("main.js");
+            if script.is_file() {
+                return Ok(BridgeLocation { script, bundled_node });
+            }
+            tried.push(script);
+        }
+        if cfg!(debug_assertions) {
+            let script = Path::new(env!("CARGO_MANIFEST_DIR")).join("../bridge/dist/main.js");
+            if script.is_file() {
+                return Ok(BridgeLocation { script, bundled_node });
             }
+            tried.push(script);
         }
 
-        for candidate in ["/opt/homebrew/bin/node", "/usr/local/bin/node", "/usr/bin/node"] {
-            if Path::new(candidate).exists() {
-                return candidate.to_string();
+        let tried: Vec<String> = tried.iter().map(|p| p.display().to_string()).collect();
+        Err(format!(
+            "Bridge script not found (looked in: {}). Run: pnpm --filter @openquery/desktop build:bridge, \
+             or set OPENQUERY_BRIDGE_PATH.",
+            if tried.is_empty() { "nowhere".to_string() } else { tried.join(", ") }
+        ))
+    }
+}
+
+/// A successful response as it came off the wire. The caller decodes its
+/// `result` straight into the type it expects (`protocol::decode`), so a large
+/// result is parsed once, on the caller's task rather than the reader's.
+#[derive(Debug)]
+pub struct Response {
+    pub body: Vec<u8>,
+    pub encoding: Encoding,
+}
+
+impl Response {
+    /// Deserialize the `result` member. On a mismatch, returns the path of the
+    /// offending field within the result (`.` for the result itself) and
+    /// serde's message.
+    pub fn result<T: DeserializeOwned>(&self) -> Result<T, (String, String)> {
+        let decoded: Result<Envelope<T>, _> = match self.encoding {
+            Encoding::Json => serde_path_to_error::deserialize(&mut serde_json::Deserializer::from_slice(&self.body))
+                .map_err(|e| (e.path().to_string(), e.inner().to_string())),
+            Encoding::MessagePack => {
+                serde_path_to_error::deserialize(&mut rmp_serde::Deserializer::from_read_ref(&self.body))
+                    .map_err(|e| (e.path().to_string(), e.inner().to_string()))
             }
+        };
+        decoded.map(|envelope| envelope.result).map_err(|(path, message)| {
+            let path = path.strip_prefix("result.").map_or_else(|| ".".to_string(), str::to_string);
+            (path, message)
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct Envelope<T> {
+    result: T,
+}
+
+/// What the reader needs from a message to route it. `result` is skipped
+/// here and left for the caller (see `Response`).
+#[derive(Deserialize)]
+pub struct Incoming {
+    #[serde(default)]
+    id: Value,
+    error: Option<Value>,
+    progress: Option<Value>,
+    event: Option<String>,
+    data: Option<Value>,
+}
+
+impl Incoming {
+    pub fn decode(body: &[u8], encoding: Encoding) -> Result<Self, String> {
+        match encoding {
+            Encoding::Json => serde_json::from_slice(body).map_err(|e| e.to_string()),
+            Encoding::MessagePack => rmp_serde::from_slice(body).map_err(|e| e.to_string()),
         }
+    }
+}
+
+/// What the heartbeat knows about the bridge.
+#[derive(Debug, Clone, Default)]
+pub struct Liveness {
+    /// When the bridge last sent anything.
+    pub last_seen: Option<tokio::time::Instant>,
+    /// Round trip of the most recent answered ping.
+    pub latency: Option<Duration>,
+    /// Pings in a row that went unanswered.
+    pub missed: u32,
+    /// Calls waiting on the bridge, pings aside.
+    pub in_flight: usize,
+}
 
-        "node".to_string()
+impl Liveness {
+    pub fn unresponsive(&self) -> bool {
+        self.missed >= UNRESPONSIVE_AFTER
     }
+}
 
-    /// Spawn the Node.js bridge process.
-    /// Looks for the compiled bridge script relative to the executable or via env.
-    pub fn spawn() -> Result<Self, Box<dyn std::error::Error>> {
-        let bridge_script = std::env::var("OPENQUERY_BRIDGE_PATH")
-            .unwrap_or_else(|_| env!("BRIDGE_SCRIPT_PATH").to_string());
-        let node_binary = Self::resolve_node_binary();
+/// Frames other than pings being written, and when the last one finished.
+#[derive(Default)]
+struct Writes {
+    active: usize,
+    last_done: Option<tokio::time::Instant>,
+}
 
-        eprintln!("[bridge] Resolved script path: {}", bridge_script);
-        eprintln!("[bridge] Using node binary: {}", node_binary);
+/// Marks a frame as being written for as long as it is held.
+struct Writing<'a>(&'a Mutex<Writes>);
 
-        let child = Command::new(&node_binary)
-            .arg(&bridge_script)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::inherit())
-            .spawn()
-            .map_err(|e| {
-                format!(
-                    "Failed to spawn bridge: {}. Node: {}. Script: {}",
-                    e, node_binary, bridge_script
-                )
-            })?;
+impl<'a> Writing<'a> {
+    fn start(writes: &'a Mutex<Writes>) -> Self {
+        if let Ok(mut w) = writes.lock() {
+            w.active += 1;
+        }
+        Writing(writes)
+    }
+}
 
-        eprintln!("[bridge] Node process spawned, waiting for ready signal...");
+impl Drop for Writing<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut w) = self.0.lock() {
+            w.active -= 1;
+            w.last_done = Some(tokio::time::Instant::now());
+        }
+    }
+}
+
+/// Callers waiting on a response, keyed by request id, with the method they called.
+/// `closed` is set once the reader task has stopped so new calls fail fast.
+#[derive(Default)]
+struct Pending {
+    waiters: HashMap<String, (String, oneshot::Sender<Reply>)>,
+    closed: bool,
+}
 
-        let bridge = Bridge {
-            child: Mutex::new(child),
+pub struct Bridge {
+    info: BridgeInfo,
+    transport: Arc<dyn BridgeTransport>,
+    writer: tokio::sync::Mutex<BoxWriter>,
+    writes: Mutex<Writes>,
+    /// Largest frame sent or accepted (see `framing::max_frame_from_env`).
+    max_frame: usize,
+    /// What the bridge answers in, once negotiated.
+    encoding: Encoding,
+    pending: PendingMap,
+    liveness: Arc<Mutex<Liveness>>,
+    stopping: Arc<AtomicBool>,
+    /// Where every call's latency, sizes and outcome are recorded.
+    metrics: Arc<Metrics>,
+    /// Writes every call to a session file, when recording is on.
+    recorder: Option<Arc<Recorder>>,
+}
+
+impl Bridge {
+    /// Spawn the bridge script on `node` (see `crate::node::discover`), with
+    /// the environment and sandbox in `child`, and connect to it over stdio;
+    /// its stderr goes to `stderr`. Must be called from within the tokio runtime.
+    #[allow(clippy::too_many_arguments)]
+    #[tracing::instrument(name = "bridge_spawn", skip_all, fields(script = %location.script.display(), node = %node.display()))]
+    pub async fn spawn(
+        node: &Path,
+        location: &BridgeLocation,
+        child: &ChildOptions,
+        wire: Wire,
+        on_exit: ExitHook,
+        notify: NotificationSink,
+        stderr: LineSink,
+        metrics: Arc<Metrics>,
+        recorder: Option<Arc<Recorder>>,
+    ) -> Result<Self, SpawnError> {
+        let connection = StdioTransport::spawn(node, &location.script, child, stderr)?;
+        info!("Node process spawned, waiting for the ready handshake");
+        Self::connect(connection, wire, on_exit, notify, metrics, recorder).await
+    }
+
+    /// Wait for the ready handshake on `connection`, start the reader task and
+    /// negotiate `wire.encoding`. Frames over `wire.max_frame` bytes are refused
+    /// in both directions. `on_exit` fires if the other end later goes away on
+    /// its own (not when dropped or shut down); `notify` receives progress and
+    /// event notifications; every call is recorded in `metrics`, and in
+    /// `recorder` along with the handshake and progress when there is one.
+    pub async fn connect(
+        connection: Connection,
+        wire: Wire,
+        on_exit: ExitHook,
+        notify: NotificationSink,
+        metrics: Arc<Metrics>,
+        recorder: Option<Arc<Recorder>>,
+    ) -> Result<Self, SpawnError> {
+        let Connection {
+            reader,
+            writer,
+            transport,
+        } = connection;
+        let transport: Arc<dyn BridgeTransport> = Arc::from(transport);
+        let max_frame = wire.max_frame;
+        let started = tokio::time::Instant::now();
+
+        // The same buffered reader is handed to the reader task afterwards so
+        // nothing buffered past the handshake frame is lost.
+        let mut reader = BufReader::new(reader);
+        let source = transport.describe();
+        let ready = Self::read_ready(&mut reader, &source, max_frame);
+        let info = match tokio::time::timeout(HANDSHAKE_TIMEOUT, ready).await {
+            Ok(Ok(info)) => info,
+            Ok(Err(e)) => {
+                transport.kill().await;
+                return Err(e);
+            }
+            Err(_) => {
+                transport.kill().await;
+                return Err(format!(
+                    "Bridge at {} did not send its ready handshake within {}s",
+                    source,
+                    HANDSHAKE_TIMEOUT.as_secs()
+                )
+                .into());
+            }
+        };
+        info!(
+            bridge_version = %info.bridge_version,
+            protocol_version = info.protocol_version,
+            methods = info.methods.len(),
+            duration_ms = started.elapsed().as_secs_f64() * 1000.0,
+            "Bridge ready"
+        );
+        let notify = match &recorder {
+            Some(recorder) => {
+                recorder.session(&info);
+                recorder.tap(notify)
+            }
+            None => notify,
         };
 
-        // Wait for ready signal
-        bridge.read_ready()?;
-        eprintln!("[bridge] Ready!");
+        let pending: PendingMap = Arc::new(Mutex::new(Pending::default()));
+        let liveness = Arc::new(Mutex::new(Liveness {
+            last_seen: Some(tokio::time::Instant::now()),
+            ..Liveness::default()
+        }));
+        let stopping = Arc::new(AtomicBool::new(false));
 
+        let reader_transport = Arc::clone(&transport);
+        let reader_pending = Arc::clone(&pending);
+        let reader_liveness = Arc::clone(&liveness);
+        let reader_stopping = Arc::clone(&stopping);
+        tokio::spawn(async move {
+            Self::read_loop(reader, max_frame, &reader_pending, &reader_liveness, &notify).await;
+            if reader_stopping.load(Ordering::SeqCst) {
+                Self::fail_pending(&reader_pending, "Bridge is shutting down");
+                return;
+            }
+            let reason = reader_transport.reap().await;
+            warn!(%reason, "Bridge exited unexpectedly");
+            Self::fail_pending(&reader_pending, &format!("Bridge crashed: {}", reason));
+            if !reader_stopping.load(Ordering::SeqCst) {
+                on_exit(reason);
+            }
+        });
+
+        let mut bridge = Bridge {
+            info,
+            transport,
+            writer: tokio::sync::Mutex::new(writer),
+            writes: Mutex::new(Writes::default()),
+            max_frame,
+            encoding: Encoding::Json,
+            pending,
+            liveness,
+            stopping,
+            metrics,
+            recorder,
+        };
+        bridge.negotiate(wire.encoding).await;
         Ok(bridge)
     }
 
-    fn read_ready(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let mut child = self.child.lock().map_err(|e| e.to_string())?;
-        let stdout = child.stdout.as_mut().ok_or("No stdout")?;
-        let mut reader = BufReader::new(stdout);
-        let mut line = String::new();
-        reader.read_line(&mut line)?;
-        let msg: Value = serde_json::from_str(&line)?;
-        if msg.get("result").and_then(|v| v.as_str()) == Some("bridge_ready") {
-            Ok(())
-        } else {
-            Err(format!("Unexpected bridge ready message: {}", line).into())
+    /// Ask the bridge to answer in `wanted` if it offers it. Any failure just
+    /// leaves the connection on JSON.
+    async fn negotiate(&mut self, wanted: Encoding) {
+        if wanted == Encoding::Json || !self.info.encodings.iter().any(|e| e == wanted.name()) {
+            return;
+        }
+        let params = serde_json::json!({ "encoding": wanted.name() });
+        match self.send_request("bridge.encoding", params, None, ENCODING_TIMEOUT).await {
+            Ok(_) => {
+                info!(encoding = wanted.name(), "Bridge encoding negotiated");
+                self.encoding = wanted;
+            }
+            Err(e) => warn!(error = %e, "Staying on JSON; bridge.encoding failed"),
         }
     }
 
-    /// Send a JSON-RPC request and wait for the response (synchronous).
-    pub fn call(&self, method: &str, params: Value) -> Result<Value, Box<dyn std::error::Error>> {
-        let id = Uuid::new_v4().to_string();
+    /// Read the ready handshake and refuse a bridge speaking another protocol
+    /// version, which usually means a stale `bridge/dist` from an older build.
+    async fn read_ready(
+        reader: &mut BufReader<BoxReader>,
+        source: &str,
+        max_frame: usize,
+    ) -> Result<BridgeInfo, SpawnError> {
+        let body = match framing::read_frame(reader, max_frame).await {
+            Ok(Some(Frame::Message { body, .. })) => body,
+            Ok(Some(Frame::TooLarge { size, .. })) => {
+                return Err(format!("Bridge handshake is {} bytes, over the frame limit", size).into())
+            }
+            Ok(None) => return Err("Bridge exited before sending its handshake".into()),
+            // Bridges before protocol 5 wrote bare JSON lines.
+            Err(e) => {
+                return Err(format!(
+                    "Bridge at {} did not send a framed handshake ({}); it is probably an older build. \
+                     Rebuild it with: pnpm --filter @openquery/desktop build:bridge",
+                    source, e
+                )
+                .into())
+            }
+        };
+        let raw = String::from_utf8_lossy(&body);
+        let msg: Value = serde_json::from_slice(&body)?;
+        if msg.get("result").and_then(|v| v.as_str()) != Some("bridge_ready") {
+            return Err(format!("Unexpected bridge ready message: {}", raw).into());
+        }
+
+        let info = match msg.get("handshake") {
+            Some(handshake) => serde_json::from_value::<BridgeInfo>(handshake.clone())
+                .map_err(|e| format!("Malformed bridge handshake: {}. Raw: {}", e, raw))?,
+            None => BridgeInfo {
+                protocol_version: 1,
+                bridge_version: "unknown".to_string(),
+                methods: Vec::new(),
+                encodings: Vec::new(),
+            },
+        };
+
+        if info.protocol_version != PROTOCOL_VERSION {
+            return Err(format!(
+                "Bridge at {} speaks protocol {} (version {}), but this app requires protocol {}. \
+                 Rebuild it with: pnpm --filter @openquery/desktop build:bridge",
+                source, info.protocol_version, info.bridge_version, PROTOCOL_VERSION
+            )
+            .into());
+        }
+        Ok(info)
+    }
+
+    pub fn info(&self) -> &BridgeInfo {
+        &self.info
+    }
+
+    /// The encoding the bridge answers in.
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Send `bridge.ping` and record the round trip, or the miss. A ping that
+    /// went unanswered while another frame was being written is not a miss:
+    /// the bridge may still be reading that frame.
+    pub async fn ping(&self) -> Result<Duration, BridgeError> {
+        let sent = tokio::time::Instant::now();
+        let result = self
+            .send_request("bridge.ping", Value::Object(Default::default()), None, PING_TIMEOUT)
+            .await;
+        let held_up = self
+            .writes
+            .lock()
+            .is_ok_and(|w| w.active > 0 || w.last_done.is_some_and(|done| done > sent));
+        let mut liveness = self.liveness.lock().map_err(|e| BridgeError::Transport(e.to_string()))?;
+        match result {
+            Ok(_) => {
+                let latency = sent.elapsed();
+                liveness.latency = Some(latency);
+                liveness.missed = 0;
+                Ok(latency)
+            }
+            Err(e) => {
+                if matches!(e, BridgeError::Timeout { .. }) {
+                    if held_up {
+                        debug!("Ping held up behind another frame; not counted as missed");
+                    } else {
+                        liveness.missed += 1;
+                    }
+                }
+                Err(e)
+            }
+        }
+    }
+
+    pub fn liveness(&self) -> Liveness {
+        let mut liveness = self.liveness.lock().map(|l| l.clone()).unwrap_or_default();
+        liveness.in_flight = self
+            .pending
+            .lock()
+            .map(|p| p.waiters.values().filter(|(method, _)| method != "bridge.ping").count())
+            .unwrap_or(0);
+        liveness
+    }
+
+    /// Reader task: routes each response frame to the caller waiting on its
+    /// id and hands notifications to `notify`. Returns on EOF or a read error;
+    /// a stream that is no longer correctly framed cannot be recovered.
+    async fn read_loop(
+        mut reader: BufReader<BoxReader>,
+        max_frame: usize,
+        pending: &PendingMap,
+        liveness: &Mutex<Liveness>,
+        notify: &NotificationSink,
+    ) {
+        loop {
+            let frame = framing::read_frame(&mut reader, max_frame).await;
+            if let (Ok(Some(_)), Ok(mut liveness)) = (&frame, liveness.lock()) {
+                liveness.last_seen = Some(tokio::time::Instant::now());
+            }
+            let (body, encoding) = match frame {
+                Ok(Some(Frame::Message { body, encoding })) => (body, encoding),
+                Ok(Some(Frame::TooLarge { size, id })) => {
+                    Self::reject_oversized(pending, size, max_frame, id);
+                    continue;
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!(error = %e, "Failed to read from bridge");
+                    break;
+                }
+            };
+
+            let message = match Incoming::decode(&body, encoding) {
+                Ok(message) => message,
+                Err(e) => {
+                    // The body can hold query results, so it is only traced at the most verbose level.
+                    warn!(error = %e, bytes = body.len(), encoding = encoding.name(), "Failed to parse bridge message");
+                    if encoding == Encoding::Json {
+                        trace!(raw = %String::from_utf8_lossy(&body), "Unparsed bridge message");
+                    }
+                    continue;
+                }
+            };
+
+            let Some(id) = message.id.as_str() else {
+                match message.event {
+                    Some(name) => notify(Notification::Event {
+                        name,
+                        data: message.data.unwrap_or(Value::Null),
+                    }),
+                    None => warn!("Dropping bridge message with neither id nor event"),
+                }
+                continue;
+            };
+
+            if let Some(progress) = message.progress {
+                // Progress for a call that already timed out or was cancelled is dropped.
+                let waiting = pending.lock().is_ok_and(|p| p.waiters.contains_key(id));
+                if waiting {
+                    notify(Notification::Progress {
+                        request_id: id.to_string(),
+                        data: progress,
+                    });
+                }
+                continue;
+            }
+
+            let Some((_, sender)) = pending.lock().ok().and_then(|mut p| p.waiters.remove(id)) else {
+                debug!(id, "Dropping response for unknown id");
+                continue;
+            };
+
+            let reply = match message.error {
+                Some(error) => Err(BridgeError::from_remote(&error)),
+                None => Ok(Response { body, encoding }),
+            };
+            let _ = sender.send(reply);
+        }
+    }
+
+    /// Fail the call an oversized frame was answering. The frame has already
+    /// been skipped, so the stream stays usable.
+    fn reject_oversized(pending: &PendingMap, size: usize, max: usize, id: Option<String>) {
+        let waiter = id
+            .as_deref()
+            .and_then(|id| pending.lock().ok().and_then(|mut p| p.waiters.remove(id)));
+        match waiter {
+            Some((method, sender)) => {
+                warn!(%method, size, max, "Response over the frame limit");
+                let _ = sender.send(Err(BridgeError::FrameTooLarge { method, size, max }));
+            }
+            None => warn!(size, max, id = id.as_deref().unwrap_or("unknown"), "Dropping frame over the limit"),
+        }
+    }
+
+    /// Mark the bridge closed and fail every outstanding call with `message`.
+    fn fail_pending(pending: &PendingMap, message: &str) {
+        if let Ok(mut p) = pending.lock() {
+            p.closed = true;
+            for (_, (_, sender)) in p.waiters.drain() {
+                let _ = sender.send(Err(BridgeError::Transport(message.to_string())));
+            }
+        }
+    }
+
+    /// Send a JSON-RPC request and wait for the response. Any number of calls may
+    /// be awaited concurrently; each resolves only with its own reply. A
+    /// caller-chosen `request_id` lets the call be cancelled later. On timeout the
+    /// Node side is told to drop the request.
+    pub async fn call_with(
+        &self,
+        method: &str,
+        params: Value,
+        request_id: Option<String>,
+        timeout: Duration,
+    ) -> Result<Response, BridgeError> {
+        if !self.info.supports(method) {
+            return Err(BridgeError::Protocol(format!(
+                "{} is not supported by the running bridge (version {})",
+                method, self.info.bridge_version
+            )));
+        }
+        self.send_request(method, params, request_id, timeout).await
+    }
+
+    /// `call_with` without the handshake check, for protocol-level methods
+    /// such as `bridge.shutdown` that are not in the bridge's method list.
+    /// Every call but heartbeat pings is traced and recorded in the metrics
+    /// with its duration, payload sizes and outcome; never its params. Only
+    /// a recording keeps those, redacted.
+    async fn send_request(
+        &self,
+        method: &str,
+        params: Value,
+        request_id: Option<String>,
+        timeout: Duration,
+    ) -> Result<Response, BridgeError> {
+        let id = request_id.unwrap_or_else(|| Uuid::new_v4().to_string());
         let request = serde_json::json!({
             "id": id,
             "method": method,
             "params": params,
         });
+        let body = serde_json::to_vec(&request).map_err(|e| BridgeError::Protocol(e.to_string()))?;
+
+        let span = tracing::info_span!("bridge_call", method, id = %id, request_bytes = body.len());
+        let started = tokio::time::Instant::now();
+        let result = self.exchange(method, &id, &body, timeout).instrument(span.clone()).await;
+        if method == "bridge.ping" {
+            return result;
+        }
+        let elapsed = started.elapsed();
+        let outcome = result.as_ref().map(|response| response.body.len());
+        self.metrics.record(method, elapsed, body.len(), outcome);
+        if let Some(recorder) = &self.recorder {
+            recorder.call(&id, method, &request["params"], elapsed, result.as_ref());
+        }
+        let duration_ms = elapsed.as_secs_f64() * 1000.0;
+        match &result {
+            Ok(response) => info!(
+                parent: &span,
+                duration_ms,
+                response_bytes = response.body.len(),
+                outcome = "ok",
+                "Bridge call finished"
+            ),
+            Err(e) => warn!(
+                parent: &span,
+                duration_ms,
+                outcome = e.kind(),
+                code = e.code(),
+                error = %e,
+                "Bridge call failed"
+            ),
+        }
+        result
+    }
+
+    /// Send one encoded request and wait for its reply.
+    async fn exchange(&self, method: &str, id: &str, body: &[u8], timeout: Duration) -> Result<Response, BridgeError> {
+        if body.len() > self.max_frame {
+            return Err(BridgeError::FrameTooLarge {
+                method: method.to_string(),
+                size: body.len(),
+                max: self.max_frame,
+            });
+        }
+
+        let (tx, mut rx) = oneshot::channel();
+        {
+            let mut pending = self
+                .pending
+                .lock()
+                .map_err(|e| BridgeError::Transport(e.to_string()))?;
+            if pending.closed {
+                return Err(BridgeError::Transport("Bridge process is no longer running".to_string()));
+            }
+            if pending.waiters.contains_key(id) {
+                return Err(BridgeError::Protocol(format!("Request id {} is already in flight", id)));
+            }
+            pending.waiters.insert(id.to_string(), (method.to_string(), tx));
+        }
+
+        let writing = (method != "bridge.ping").then(|| Writing::start(&self.writes));
+        let written = self.write_frame(body).await;
+        drop(writing);
+        if let Err(e) = written {
+            self.take_waiter(id);
+            return Err(BridgeError::Transport(e));
+        }
 
-        let response_line = {
-            let mut child = self.child.lock().map_err(|e| e.to_string())?;
-
-            // Write request
-            let stdin = child.stdin.as_mut().ok_or("No stdin")?;
-            let request_str = serde_json::to_string(&request)? + "\n";
-            stdin.write_all(request_str.as_bytes())?;
-            stdin.flush()?;
-
-            // Read response
-            let stdout = child.stdout.as_mut().ok_or("No stdout")?;
-            let mut reader = BufReader::new(stdout);
-            let mut line = String::new();
-            reader.read_line(&mut line)?;
-            line
+        match tokio::time::timeout(timeout, &mut rx).await {
+            Ok(Ok(reply)) => reply,
+            Ok(Err(_)) => Err(BridgeError::Transport(
+                "Bridge reader stopped before responding".to_string(),
+            )),
+            Err(_) => {
+                // The reader already claimed the waiter, so the reply is on its way.
+                if self.take_waiter(id).is_none() {
+                    return rx.await.unwrap_or_else(|_| Err(BridgeError::timeout(method, timeout)));
+                }
+                self.notify_cancel(id).await;
+                Err(BridgeError::timeout(method, timeout))
+            }
+        }
+    }
+
+    /// Cancel an in-flight call. The waiting caller gets `BridgeError::Cancelled`
+    /// right away and the Node side is asked to discard the eventual result.
+    /// Returns false if no call with that id is pending.
+    pub async fn cancel(&self, request_id: &str) -> bool {
+        let Some((method, sender)) = self.take_waiter(request_id) else {
+            return false;
         };
+        let _ = sender.send(Err(BridgeError::cancelled(&method)));
+        self.notify_cancel(request_id).await;
+        true
+    }
 
-        let response: Value = serde_json::from_str(&response_line)
-            .map_err(|e| format!("Failed to parse bridge response: {}. Raw: {}", e, response_line))?;
+    /// Stop the bridge in an orderly way: ask it to shut down, wait for it to
+    /// exit, and escalate to SIGTERM and then a kill. Returns once the other end
+    /// is gone (for a child process, reaped). The exit is not reported as a crash.
+    pub async fn shutdown(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        match self
+            .send_request("bridge.shutdown", Value::Object(Default::default()), None, SHUTDOWN_ACK_TIMEOUT)
+            .await
+        {
+            Ok(_) => info!("Shutdown acknowledged"),
+            Err(e) => warn!(error = %e, "No shutdown acknowledgement"),
+        }
+
+        if let Some(exit) = self.transport.wait(SHUTDOWN_EXIT_TIMEOUT).await {
+            info!(%exit, "Bridge stopped");
+            return;
+        }
 
-        // Check for matching ID
-        if response.get("id").and_then(|v| v.as_str()) != Some(&id) {
-            return Err("Response ID mismatch".into());
+        if self.transport.terminate() {
+            warn!("Bridge still running; sent SIGTERM");
+            if let Some(exit) = self.transport.wait(SHUTDOWN_TERM_TIMEOUT).await {
+                info!(%exit, "Bridge stopped");
+                return;
+            }
         }
 
-        // Check for error
-        if let Some(error) = response.get("error") {
-            return Err(error.as_str().unwrap_or("Unknown bridge error").to_string().into());
+        warn!("Bridge did not exit; killing it");
+        self.transport.kill().await;
+    }
+
+    fn take_waiter(&self, id: &str) -> Option<(String, oneshot::Sender<Reply>)> {
+        self.pending.lock().ok().and_then(|mut p| p.waiters.remove(id))
+    }
+
+    async fn notify_cancel(&self, id: &str) {
+        let notification = serde_json::json!({
+            "method": "bridge.cancel",
+            "params": { "id": id },
+        });
+        if let Err(e) = self.write_message(&notification).await {
+            warn!(id, error = %e, "Failed to send cancellation");
         }
+    }
+
+    async fn write_message(&self, message: &Value) -> Result<(), String> {
+        let body = serde_json::to_vec(message).map_err(|e| e.to_string())?;
+        self.write_frame(&body).await
+    }
 
-        Ok(response.get("result").cloned().unwrap_or(Value::Null))
+    async fn write_frame(&self, body: &[u8]) -> Result<(), String> {
+        let mut writer = self.writer.lock().await;
+        writer.write_all(&framing::frame(body, Encoding::Json)).await.map_err(|e| e.to_string())?;
+        writer.flush().await.map_err(|e| e.to_string())
     }
 }
 
 impl Drop for Bridge {
     fn drop(&mut self) {
-        if let Ok(mut child) = self.child.lock() {
-            let _ = child.kill();
-        }
+        self.stopping.store(true, Ordering::SeqCst);
+        self.transport.abort();
     }
 }
@@ -0,0 +1,167 @@
+// Database passwords and the OpenAI key, resolved on the Rust side.
+//
+// The webview never reads a secret back: with script running in it, anything
+// a command returns can be sent anywhere. So commands that reach a database
+// take a profile name, and the password is looked up here and written into
+// the bridge params: first what the user typed this session (held in
+// `SessionPasswords`, not in the webview's storage), then the keychain entry
+// under the profile's id (as Setup and Profiles save it). Never under its name:
+// the webview chooses names, so one could be made to match another entry. The
+// webview may store, delete and check for secrets, but not read them, and the
+// shell's own entries (`__name__`) are out of its reach except to check.
+
+use crate::error::BridgeError;
+use crate::keychain;
+use crate::protocol;
+use crate::supervisor::BridgeSupervisor;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Keychain entry holding the OpenAI API key saved in Settings.
+pub const OPENAI_KEY_ID: &str = "__openai_api_key__";
+
+/// Whether `key` is in the shell's own keychain namespace, which the webview
+/// may not write to or name a profile after.
+pub fn is_reserved(key: &str) -> bool {
+    key.len() > 4 && key.starts_with("__") && key.ends_with("__")
+}
+
+/// Passwords typed this session, by profile name. Gone when the app exits.
+#[derive(Default)]
+pub struct SessionPasswords {
+    by_profile: Mutex<HashMap<String, String>>,
+}
+
+impl SessionPasswords {
+    /// Remember `password` for `profile`; an empty one forgets it, so the
+    /// keychain is used again.
+    pub fn set(&self, profile: &str, password: &str) {
+        if let Ok(mut passwords) = self.by_profile.lock() {
+            if password.is_empty() {
+                passwords.remove(profile);
+            } else {
+                passwords.insert(profile.to_string(), password.to_string());
+            }
+        }
+    }
+
+    pub fn get(&self, profile: &str) -> Option<String> {
+        self.by_profile.lock().ok().and_then(|p| p.get(profile).cloned())
+    }
+}
+
+/// Profile ids by name, and the active profile, from the last `profiles.list`,
+/// so a database command does not wait on a second bridge call to find them.
+/// Commands that add, remove or switch profiles drop it.
+#[derive(Default)]
+pub struct ProfileIndex {
+    listed: Mutex<Option<Vec<Listed>>>,
+}
+
+#[derive(Clone)]
+struct Listed {
+    name: String,
+    id: String,
+    active: bool,
+}
+
+impl ProfileIndex {
+    pub fn update(&self, profiles: &[protocol::ListedProfile]) {
+        let listed = profiles
+            .iter()
+            .map(|listed| Listed {
+                name: listed.profile.name.clone(),
+                id: listed.profile.id.clone(),
+                active: listed.active,
+            })
+            .collect();
+        if let Ok(mut index) = self.listed.lock() {
+            *index = Some(listed);
+        }
+    }
+
+    pub fn invalidate(&self) {
+        if let Ok(mut index) = self.listed.lock() {
+            *index = None;
+        }
+    }
+
+    /// None when nothing is cached; Some(None) when the list has no match.
+    fn find(&self, name: Option<&str>) -> Option<Option<Listed>> {
+        let index = self.listed.lock().ok()?;
+        let listed = index.as_ref()?;
+        Some(
+            listed
+                .iter()
+                .find(|listed| match name {
+                    Some(name) => listed.name == name,
+                    None => listed.active,
+                })
+                .cloned(),
+        )
+    }
+}
+
+/// The profile a database command runs against, and its password.
+pub struct Credential {
+    /// None only when no profile matched; the bridge then reports why.
+    pub name: Option<String>,
+    pub password: String,
+}
+
+/// Credentials for profile `name`, or the active profile. Without a password
+/// in the session or the keychain it is empty: SQLite profiles need none, and
+/// a server that does rejects the connection itself.
+pub async fn resolve(
+    bridge: &BridgeSupervisor,
+    session: &SessionPasswords,
+    index: &ProfileIndex,
+    name: Option<String>,
+) -> Result<Credential, BridgeError> {
+    if let Some(password) = name.as_deref().and_then(|n| session.get(n)) {
+        return Ok(Credential { name, password });
+    }
+    let found = match index.find(name.as_deref()) {
+        Some(Some(listed)) => Some(listed),
+        // Not cached, or added since: ask the bridge.
+        _ => {
+            let profiles = bridge.request::<protocol::ProfilesList>(&protocol::NoParams {}, None).await?;
+            index.update(&profiles);
+            index.find(name.as_deref()).flatten()
+        }
+    };
+    let Some(listed) = found else {
+        return Ok(Credential {
+            name,
+            password: String::new(),
+        });
+    };
+    let password = match session.get(&listed.name) {
+        Some(password) => Some(password),
+        None => stored(listed.id).await,
+    };
+    Ok(Credential {
+        // Named even when it was the active profile, so the bridge uses the
+        // profile this password belongs to.
+        name: Some(listed.name),
+        password: password.unwrap_or_default(),
+    })
+}
+
+/// The OpenAI key saved in Settings; None leaves the bridge to `OPENAI_API_KEY`.
+pub async fn openai_key() -> Option<String> {
+    stored(OPENAI_KEY_ID.to_string())
+        .await
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+}
+
+/// A keychain entry, read off the async runtime since the OS store can block.
+/// A keychain that cannot be read counts as empty; the failure is traced by
+/// `keychain`.
+async fn stored(key: String) -> Option<String> {
+    tokio::task::spawn_blocking(move || keychain::get_password(&key).ok().flatten())
+        .await
+        .ok()
+        .flatten()
+}
@@ -0,0 +1,191 @@
+// Diagnostics reported to the UI for troubleshooting the shell and bridge launch.
+//
+// Everything here works with or without a running bridge: when the launch
+// failed, the details come from the recorded `LaunchFailure` instead.
+
+use crate::bridge::BridgeLocation;
+use crate::node::NodeDiscovery;
+use crate::supervisor::BridgeSupervisor;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A bridge launch that did not produce a running bridge, with whatever was
+/// resolved before it failed.
+#[derive(Debug, Clone)]
+pub struct LaunchFailure {
+    pub reason: String,
+    pub location: Option<BridgeLocation>,
+    pub node: Option<NodeDiscovery>,
+}
+
+/// Whether the bridge is up, and if not, why and with which paths.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeStatus {
+    pub running: bool,
+    /// A launch is under way; commands fail until it finishes.
+    pub starting: bool,
+    /// Why the bridge is unavailable: the launch failure, or a crash it is restarting from.
+    pub error: Option<String>,
+    pub bridge_script: Option<String>,
+    /// Node binary selected for the bridge, if discovery found one.
+    pub node_path: Option<String>,
+}
+
+/// Heartbeat view of the bridge, for the status pill in the top bar.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BridgeHealth {
+    /// healthy, unresponsive (running but not answering pings) or down.
+    pub status: String,
+    /// Milliseconds since the bridge last sent anything.
+    pub last_seen_ms: Option<u64>,
+    /// Round trip of the most recent answered ping.
+    pub latency_ms: Option<f64>,
+    /// Pings in a row that went unanswered.
+    pub missed_pings: u32,
+    /// Calls waiting on the bridge.
+    pub in_flight: u32,
+}
+
+/// Bridge call metrics for this session, and per shell version when they are
+/// kept across sessions (see `crate::metrics`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsReport {
+    pub shell_version: String,
+    /// When this session started, in milliseconds since the Unix epoch.
+    pub since_ms: u64,
+    /// This session, by method name.
+    pub methods: Vec<MethodMetrics>,
+    /// Saved totals per shell version, newest first, this session included.
+    pub releases: Vec<ReleaseMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseMetrics {
+    pub shell_version: String,
+    pub methods: Vec<MethodMetrics>,
+}
+
+/// Latency, errors and payload sizes of one bridge method. Percentiles are
+/// histogram estimates, at most 15% high; the max is exact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MethodMetrics {
+    pub method: String,
+    pub calls: u64,
+    pub errors: u64,
+    /// Failed calls by error code (TIMEOUT, AUTH_FAILED, ...).
+    pub error_codes: BTreeMap<String, u64>,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    pub request_bytes_mean: f64,
+    pub request_bytes_max: u64,
+    /// Over the calls that got a response.
+    pub response_bytes_mean: f64,
+    pub response_bytes_max: u64,
+}
+
+/// How the bridge was launched: which script, and every Node runtime considered.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupDiagnostics {
+    pub shell_version: String,
+    pub bridge_script: Option<String>,
+    /// Node runtime shipped in the app resources, if this build has one.
+    pub bundled_node: Option<String>,
+    /// Node discovery from the most recent bridge spawn.
+    pub node: Option<NodeDiscovery>,
+    /// Names (never values) of the environment variables the running bridge
+    /// was spawned with; None when the shell did not spawn it.
+    pub bridge_env: Option<Vec<String>>,
+    /// How the running bridge is confined; None when the shell did not spawn it.
+    pub sandbox: Option<SandboxStatus>,
+    /// Why the last launch failed, if it did.
+    pub error: Option<String>,
+}
+
+/// The bridge process's confinement (see `crate::sandbox`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SandboxStatus {
+    /// Whether the Landlock filesystem ruleset is enforced.
+    pub active: bool,
+    /// Why it is not: turned off in settings, not Linux, or no kernel support.
+    pub reason: Option<String>,
+    /// Landlock ABI version of the running kernel, if it has Landlock.
+    pub landlock_abi: Option<u32>,
+    pub read_only: Vec<String>,
+    pub read_write: Vec<String>,
+    pub memory_limit_mb: Option<u64>,
+    pub open_files_limit: Option<u64>,
+    /// Whether the bridge leads its own process group.
+    pub process_group: bool,
+}
+
+fn launch_details(
+    bridge: Option<&BridgeSupervisor>,
+    failure: Option<&LaunchFailure>,
+) -> (Option<BridgeLocation>, Option<NodeDiscovery>) {
+    match (bridge, failure) {
+        (Some(bridge), _) => (bridge.location().cloned(), bridge.node_discovery()),
+        (None, Some(failure)) => (failure.location.clone(), failure.node.clone()),
+        (None, None) => (None, None),
+    }
+}
+
+pub fn status(bridge: Option<&BridgeSupervisor>, failure: Option<&LaunchFailure>, starting: bool) -> BridgeStatus {
+    let (location, node) = launch_details(bridge, failure);
+    let starting = starting && bridge.is_none();
+    let error = match bridge {
+        Some(bridge) => bridge.info().err().map(|e| e.to_string()),
+        None if starting => None,
+        None => Some(failure.map_or_else(|| "Bridge has not been started.".to_string(), |f| f.reason.clone())),
+    };
+    BridgeStatus {
+        running: !starting && error.is_none(),
+        starting,
+        error,
+        bridge_script: location.map(|l| l.script.display().to_string()),
+        node_path: node.and_then(|n| n.selected),
+    }
+}
+
+pub fn startup(bridge: Option<&BridgeSupervisor>, failure: Option<&LaunchFailure>) -> StartupDiagnostics {
+    let (location, node) = launch_details(bridge, failure);
+    StartupDiagnostics {
+        shell_version: env!("CARGO_PKG_VERSION").to_string(),
+        bridge_script: location.as_ref().map(|l| l.script.display().to_string()),
+        bundled_node: location
+            .as_ref()
+            .and_then(|l| l.bundled_node.as_ref())
+            .map(|p| p.display().to_string()),
+        node,
+        bridge_env: bridge.and_then(BridgeSupervisor::env_names),
+        sandbox: bridge.and_then(BridgeSupervisor::sandbox_status),
+        error: failure.filter(|_| bridge.is_none()).map(|f| f.reason.clone()),
+    }
+}
+
+pub fn health(bridge: Option<&BridgeSupervisor>) -> BridgeHealth {
+    let Some(liveness) = bridge.and_then(|b| b.liveness().ok()) else {
+        return BridgeHealth {
+            status: "down".to_string(),
+            last_seen_ms: None,
+            latency_ms: None,
+            missed_pings: 0,
+            in_flight: 0,
+        };
+    };
+    BridgeHealth {
+        status: if liveness.unresponsive() { "unresponsive" } else { "healthy" }.to_string(),
+        last_seen_ms: liveness.last_seen.map(|t| t.elapsed().as_millis() as u64),
+        latency_ms: liveness.latency.map(|d| d.as_secs_f64() * 1000.0),
+        missed_pings: liveness.missed,
+        in_flight: liveness.in_flight as u32,
+    }
+}
@@ -0,0 +1,97 @@
+// The environment the Node bridge is spawned with.
+//
+// A desktop session's environment carries credentials the bridge has no use
+// for (AWS_*, GITHUB_TOKEN, ...), and anything in it would be readable by every
+// package the bridge loads. So the bridge starts from an empty environment and
+// gets only the variables allowed here: what Node and the OS need, the
+// OPENAI_API_KEY fallback and OPENQUERY_* settings, plus whatever the user adds
+// with the `bridgeEnv` shell setting (e.g. NODE_EXTRA_CA_CERTS behind a TLS
+// proxy). Diagnostics report the names passed, never the values.
+
+use std::ffi::OsString;
+
+/// Always passed through. An entry ending in `*` matches a prefix.
+pub const BASE_ALLOW: &[&str] = &[
+    "PATH",
+    "HOME",
+    "OPENAI_API_KEY",
+    "OPENQUERY_*",
+    "LANG",
+    "LC_*",
+    "TZ",
+    "TMPDIR",
+    "TEMP",
+    "TMP",
+    // Windows: Node and `os.homedir()` need these.
+    "SYSTEMROOT",
+    "WINDIR",
+    "COMSPEC",
+    "PATHEXT",
+    "USERPROFILE",
+    "APPDATA",
+    "LOCALAPPDATA",
+];
+
+/// Variables for the bridge process, sorted by name.
+#[derive(Debug, Clone, Default)]
+pub struct ChildEnv {
+    vars: Vec<(OsString, OsString)>,
+}
+
+impl ChildEnv {
+    /// The allowed part of this process's environment; `extra` adds to
+    /// `BASE_ALLOW`.
+    pub fn from_current(extra: &[String]) -> Self {
+        Self::filter(std::env::vars_os(), extra)
+    }
+
+    pub fn filter(vars: impl IntoIterator<Item = (OsString, OsString)>, extra: &[String]) -> Self {
+        let mut vars: Vec<(OsString, OsString)> = vars
+            .into_iter()
+            .filter(|(name, _)| {
+                let name = name.to_string_lossy();
+                let mut patterns = BASE_ALLOW.iter().copied().chain(extra.iter().map(String::as_str));
+                patterns.any(|pattern| allows(pattern, &name))
+            })
+            .collect();
+        vars.sort();
+        ChildEnv { vars }
+    }
+
+    pub fn vars(&self) -> &[(OsString, OsString)] {
+        &self.vars
+    }
+
+    /// Names of the variables passed, for diagnostics.
+    pub fn names(&self) -> Vec<String> {
+        self.vars.iter().map(|(name, _)| name.to_string_lossy().into_owned()).collect()
+    }
+}
+
+/// Whether allow-list entry `pattern` covers variable `name`. Windows
+/// variable names are case-insensitive.
+fn allows(pattern: &str, name: &str) -> bool {
+    let (pattern, name) = if cfg!(windows) {
+        (pattern.to_ascii_uppercase(), name.to_ascii_uppercase())
+    } else {
+        (pattern.to_string(), name.to_string())
+    };
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+/// Check a `bridgeEnv` entry: a variable name, or a prefix ending in `*`.
+pub fn validate_pattern(pattern: &str) -> Result<(), String> {
+    let name = pattern.strip_suffix('*').unwrap_or(pattern);
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid bridge environment entry {:?}: expected a variable name such as NODE_EXTRA_CA_CERTS, or a prefix such as MY_APP_*",
+            pattern
+        ))
+    }
+}
@@ -0,0 +1,169 @@
+// Error type shared by the bridge client and the Tauri command layer.
+//
+// Serialized to the webview as `{kind, code, message, details}`. `code` is
+// stable: for `remote` errors it comes from the bridge (AUTH_FAILED,
+// CONNECTION_REFUSED, POLICY_BLOCKED, LLM_QUOTA, ...); every other kind has a
+// fixed code of its own, so the UI can branch without matching message text.
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json::Value;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub enum BridgeError {
+    /// The pipe to the bridge failed or the process went away mid-call.
+    Transport(String),
+    /// No reply within the method's deadline.
+    Timeout { method: String, timeout_ms: u64 },
+    /// The call was cancelled via `bridge_cancel`.
+    Cancelled { method: String },
+    /// The bridge sent something we cannot use (bad handshake, wrong shape, unsupported method).
+    Protocol(String),
+    /// A result did not match the method's typed shape; `field` is the path to the mismatch.
+    Decode { method: String, field: String, message: String },
+    /// A request or response was larger than the bridge frame limit.
+    FrameTooLarge { method: String, size: usize, max: usize },
+    /// The bridge ran the method and it failed.
+    Remote { code: String, message: String, data: Option<Value> },
+    /// The OS credential store refused or failed.
+    Keychain(String),
+    /// No bridge is running (not spawned yet, restarting, or given up).
+    NotStarted(String),
+    /// Shell settings could not be saved.
+    Settings(String),
+    /// Captured logs could not be read or exported.
+    Logs(String),
+}
+
+impl BridgeError {
+    pub fn timeout(method: &str, after: Duration) -> Self {
+        BridgeError::Timeout {
+            method: method.to_string(),
+            timeout_ms: after.as_millis() as u64,
+        }
+    }
+
+    pub fn cancelled(method: &str) -> Self {
+        BridgeError::Cancelled {
+            method: method.to_string(),
+        }
+    }
+
+    /// Decode the `error` member of a bridge response. Older bridges sent a
+    /// bare string; those map to the generic `INTERNAL` code.
+    pub fn from_remote(error: &Value) -> Self {
+        match error {
+            Value::String(message) => BridgeError::Remote {
+                code: "INTERNAL".to_string(),
+                message: message.clone(),
+                data: None,
+            },
+            Value::Object(obj) => BridgeError::Remote {
+                code: obj
+                    .get("code")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("INTERNAL")
+                    .to_string(),
+                message: obj
+                    .get("message")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or("Unknown bridge error")
+                    .to_string(),
+                data: obj.get("data").cloned().filter(|d| !d.is_null()),
+            },
+            other => BridgeError::Protocol(format!("Malformed bridge error: {}", other)),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            BridgeError::Transport(_) => "transport",
+            BridgeError::Timeout { .. } => "timeout",
+            BridgeError::Cancelled { .. } => "cancelled",
+            BridgeError::Protocol(_) | BridgeError::Decode { .. } | BridgeError::FrameTooLarge { .. } => "protocol",
+            BridgeError::Remote { .. } => "remote",
+            BridgeError::Keychain(_) => "keychain",
+            BridgeError::NotStarted(_) => "notStarted",
+            BridgeError::Settings(_) => "settings",
+            BridgeError::Logs(_) => "logs",
+        }
+    }
+
+    pub fn code(&self) -> &str {
+        match self {
+            BridgeError::Transport(_) => "BRIDGE_TRANSPORT",
+            BridgeError::Timeout { .. } => "TIMEOUT",
+            BridgeError::Cancelled { .. } => "CANCELLED",
+            BridgeError::Protocol(_) | BridgeError::Decode { .. } => "BRIDGE_PROTOCOL",
+            BridgeError::FrameTooLarge { .. } => "FRAME_TOO_LARGE",
+            BridgeError::Remote { code, .. } => code,
+            BridgeError::Keychain(_) => "KEYCHAIN",
+            BridgeError::NotStarted(_) => "BRIDGE_NOT_STARTED",
+            BridgeError::Settings(_) => "SETTINGS",
+            BridgeError::Logs(_) => "LOGS",
+        }
+    }
+
+    fn details(&self) -> Option<Value> {
+        match self {
+            BridgeError::Timeout { method, timeout_ms } => {
+                Some(serde_json::json!({ "method": method, "timeoutMs": timeout_ms }))
+            }
+            BridgeError::Cancelled { method } => Some(serde_json::json!({ "method": method })),
+            BridgeError::Decode { method, field, .. } => {
+                Some(serde_json::json!({ "method": method, "field": field }))
+            }
+            BridgeError::FrameTooLarge { method, size, max } => {
+                Some(serde_json::json!({ "method": method, "size": size, "maxSize": max }))
+            }
+            BridgeError::Remote { data, .. } => data.clone(),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BridgeError::Transport(message)
+            | BridgeError::Protocol(message)
+            | BridgeError::Keychain(message)
+            | BridgeError::NotStarted(message)
+            | BridgeError::Settings(message)
+            | BridgeError::Logs(message)
+            | BridgeError::Remote { message, .. } => f.write_str(message),
+            BridgeError::Timeout { method, timeout_ms } => {
+                write!(f, "{} timed out after {}s", method, timeout_ms / 1000)
+            }
+            BridgeError::Cancelled { method } => write!(f, "{} was cancelled", method),
+            BridgeError::Decode { method, field, message } => {
+                write!(f, "{}: unexpected `{}` in bridge result: {}", method, field, message)
+            }
+            BridgeError::FrameTooLarge { method, size, max } => write!(
+                f,
+                "{}: message of {} exceeds the {} bridge frame limit (OPENQUERY_BRIDGE_MAX_FRAME_MB)",
+                method,
+                megabytes(*size),
+                megabytes(*max)
+            ),
+        }
+    }
+}
+
+fn megabytes(bytes: usize) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+impl std::error::Error for BridgeError {}
+
+impl Serialize for BridgeError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("BridgeError", 4)?;
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("code", self.code())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.serialize_field("details", &self.details())?;
+        s.end()
+    }
+}
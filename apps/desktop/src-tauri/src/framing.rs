@@ -0,0 +1,231 @@
+// Content-Length framing for the bridge protocol, as in LSP:
+//
+//     Content-Length: 27\r\n
+//     \r\n
+//     {"id":"1","result":{"ok":true}}
+//
+// Each frame carries one JSON message of any size or layout (pretty-printed,
+// multi-line strings), and the reader learns its size before reading it, so a
+// message over the limit is skipped without ever being buffered.
+//
+// Once the shell has asked for it (`bridge.encoding`, see `crate::bridge`), the
+// bridge sends MessagePack instead, marked per frame:
+//
+//     Content-Length: 1843\r\n
+//     Content-Type: application/msgpack\r\n
+//     \r\n
+//     <binary>
+//
+// A frame without a Content-Type is JSON. The shell's own messages always are.
+
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt};
+
+/// Largest message accepted in either direction unless overridden.
+pub const DEFAULT_MAX_FRAME: usize = 64 * 1024 * 1024;
+
+/// Header lines are short; anything longer means the stream is not framed.
+const MAX_HEADER_LINE: usize = 1024;
+
+/// How much of an oversized frame is read to find the request id it answers.
+const ID_PREFIX: usize = 256;
+
+/// `OPENQUERY_BRIDGE_MAX_FRAME_MB` overrides the frame size limit. The bridge
+/// reads the same variable, so both sides agree.
+pub fn max_frame_from_env() -> usize {
+    std::env::var("OPENQUERY_BRIDGE_MAX_FRAME_MB")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .filter(|mb| *mb > 0)
+        .map_or(DEFAULT_MAX_FRAME, |mb| mb.saturating_mul(1024 * 1024))
+}
+
+/// How a message body is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    MessagePack,
+}
+
+impl Encoding {
+    /// Name used in the handshake and in `bridge.encoding`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Json => "json",
+            Encoding::MessagePack => "msgpack",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "json" => Some(Encoding::Json),
+            "msgpack" => Some(Encoding::MessagePack),
+            _ => None,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Encoding::Json => "application/json",
+            Encoding::MessagePack => "application/msgpack",
+        }
+    }
+
+    fn from_content_type(value: &str) -> Option<Self> {
+        // Parameters such as `; charset=utf-8` do not change the encoding.
+        match value.split(';').next().unwrap_or_default().trim() {
+            "application/json" | "application/vscode-jsonrpc" => Some(Encoding::Json),
+            "application/msgpack" | "application/x-msgpack" => Some(Encoding::MessagePack),
+            _ => None,
+        }
+    }
+}
+
+/// The limit and encoding a connection starts with.
+#[derive(Debug, Clone, Copy)]
+pub struct Wire {
+    /// Largest frame sent or accepted, in bytes.
+    pub max_frame: usize,
+    /// Encoding to ask the bridge to answer in, if it offers it.
+    pub encoding: Encoding,
+}
+
+impl Default for Wire {
+    fn default() -> Self {
+        Wire {
+            max_frame: DEFAULT_MAX_FRAME,
+            encoding: Encoding::MessagePack,
+        }
+    }
+}
+
+impl Wire {
+    /// `OPENQUERY_BRIDGE_MAX_FRAME_MB` (see `max_frame_from_env`), and
+    /// `OPENQUERY_BRIDGE_ENCODING=json` to keep the bridge on JSON, e.g. to read
+    /// its output while debugging.
+    pub fn from_env() -> Self {
+        let encoding = std::env::var("OPENQUERY_BRIDGE_ENCODING")
+            .ok()
+            .and_then(|v| Encoding::from_name(v.trim()))
+            .unwrap_or(Wire::default().encoding);
+        Wire {
+            max_frame: max_frame_from_env(),
+            encoding,
+        }
+    }
+}
+
+pub enum Frame {
+    Message { body: Vec<u8>, encoding: Encoding },
+    /// A frame over the limit, already skipped. `id` is the request it
+    /// answers, if that could be read from the start of the body.
+    TooLarge { size: usize, id: Option<String> },
+}
+
+/// Prefix `body` with its headers. JSON frames carry no Content-Type.
+pub fn frame(body: &[u8], encoding: Encoding) -> Vec<u8> {
+    let mut framed = match encoding {
+        Encoding::Json => format!("Content-Length: {}\r\n\r\n", body.len()),
+        other => format!("Content-Length: {}\r\nContent-Type: {}\r\n\r\n", body.len(), other.content_type()),
+    }
+    .into_bytes();
+    framed.extend_from_slice(body);
+    framed
+}
+
+/// Read the next frame. `Ok(None)` on a clean EOF between frames; an error if
+/// the stream is not framed or ends mid-frame.
+pub async fn read_frame<R: AsyncBufRead + Unpin>(reader: &mut R, max: usize) -> io::Result<Option<Frame>> {
+    let mut length = None;
+    let mut encoding = Encoding::Json;
+    let mut line = Vec::new();
+    let mut first = true;
+    loop {
+        line.clear();
+        let read = (&mut *reader).take(MAX_HEADER_LINE as u64).read_until(b'\n', &mut line).await?;
+        if read == 0 {
+            return if first {
+                Ok(None)
+            } else {
+                Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Bridge output ended inside a frame header"))
+            };
+        }
+        first = false;
+        if line.last() != Some(&b'\n') {
+            return Err(malformed(&line));
+        }
+        let text = std::str::from_utf8(&line).map_err(|_| malformed(&line))?.trim_end();
+        if text.is_empty() {
+            break;
+        }
+        let (name, value) = text.split_once(':').ok_or_else(|| malformed(&line))?;
+        if name.trim().eq_ignore_ascii_case("content-length") {
+            length = Some(value.trim().parse::<usize>().map_err(|_| malformed(&line))?);
+        } else if name.trim().eq_ignore_ascii_case("content-type") {
+            encoding = Encoding::from_content_type(value).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Unsupported bridge frame content type: {}", value.trim()),
+                )
+            })?;
+        }
+    }
+    let size = length.ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "Bridge frame has no Content-Length header")
+    })?;
+
+    if size > max {
+        let mut prefix = vec![0; size.min(ID_PREFIX)];
+        reader.read_exact(&mut prefix).await?;
+        let rest = (size - prefix.len()) as u64;
+        let skipped = tokio::io::copy(&mut (&mut *reader).take(rest), &mut tokio::io::sink()).await?;
+        if skipped < rest {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Bridge output ended inside a frame"));
+        }
+        return Ok(Some(Frame::TooLarge {
+            size,
+            id: leading_id(&prefix, encoding),
+        }));
+    }
+
+    let mut body = vec![0; size];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(Frame::Message { body, encoding }))
+}
+
+fn malformed(line: &[u8]) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Malformed bridge frame header: {}", String::from_utf8_lossy(line).trim_end()),
+    )
+}
+
+/// The bridge writes `id` first in every message, so a string id can be read
+/// off the start of a body too large to parse.
+fn leading_id(prefix: &[u8], encoding: Encoding) -> Option<String> {
+    match encoding {
+        Encoding::Json => {
+            let text = String::from_utf8_lossy(prefix);
+            let rest = text.trim_start().strip_prefix('{')?.trim_start().strip_prefix("\"id\"")?;
+            let rest = rest.trim_start().strip_prefix(':')?.trim_start().strip_prefix('"')?;
+            rest.split_once('"').map(|(id, _)| id.to_string())
+        }
+        Encoding::MessagePack => {
+            // A map header, the key "id" as a fixstr, then a string value.
+            let rest = match prefix.first()? {
+                0x80..=0x8f => &prefix[1..],
+                0xde => prefix.get(3..)?,
+                0xdf => prefix.get(5..)?,
+                _ => return None,
+            };
+            let rest = rest.strip_prefix(&[0xa2, b'i', b'd'])?;
+            let (len, rest) = match *rest.first()? {
+                tag @ 0xa0..=0xbf => (usize::from(tag & 0x1f), &rest[1..]),
+                0xd9 => (usize::from(*rest.get(1)?), rest.get(2..)?),
+                0xda => (usize::from(u16::from_be_bytes([*rest.get(1)?, *rest.get(2)?])), rest.get(3..)?),
+                _ => return None,
+            };
+            String::from_utf8(rest.get(..len)?.to_vec()).ok()
+        }
+    }
+}
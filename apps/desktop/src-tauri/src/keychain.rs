@@ -3,29 +3,42 @@
 // - macOS: Keychain
 // - Windows: Credential Manager
 // - Linux: Secret Service (GNOME Keyring / KWallet)
+//
+// Operations are traced by profile and outcome; secrets never are.
+
+use tracing::{debug, warn};
 
 const SERVICE_NAME: &str = "com.openquery.app";
 
 pub fn set_password(profile_id: &str, password: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let entry = keyring::Entry::new(SERVICE_NAME, profile_id)?;
-    entry.set_password(password)?;
-    Ok(())
+    let result = keyring::Entry::new(SERVICE_NAME, profile_id).and_then(|entry| entry.set_password(password));
+    traced("set", profile_id, result.as_ref().map(|_| "stored"));
+    Ok(result?)
 }
 
 pub fn get_password(profile_id: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
-    let entry = keyring::Entry::new(SERVICE_NAME, profile_id)?;
-    match entry.get_password() {
+    let result = match keyring::Entry::new(SERVICE_NAME, profile_id).and_then(|entry| entry.get_password()) {
         Ok(pw) => Ok(Some(pw)),
         Err(keyring::Error::NoEntry) => Ok(None),
-        Err(e) => Err(e.into()),
-    }
+        Err(e) => Err(e),
+    };
+    traced("get", profile_id, result.as_ref().map(|pw| if pw.is_some() { "found" } else { "missing" }));
+    Ok(result?)
 }
 
 pub fn delete_password(profile_id: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let entry = keyring::Entry::new(SERVICE_NAME, profile_id)?;
-    match entry.delete_credential() {
-        Ok(()) => Ok(()),
-        Err(keyring::Error::NoEntry) => Ok(()), // already gone
-        Err(e) => Err(e.into()),
+    let result = match keyring::Entry::new(SERVICE_NAME, profile_id).and_then(|entry| entry.delete_credential()) {
+        Ok(()) => Ok("deleted"),
+        Err(keyring::Error::NoEntry) => Ok("missing"), // already gone
+        Err(e) => Err(e),
+    };
+    traced("delete", profile_id, result.as_ref().copied());
+    result.map(|_| ()).map_err(Into::into)
+}
+
+fn traced(operation: &str, profile: &str, outcome: Result<&str, &keyring::Error>) {
+    match outcome {
+        Ok(outcome) => debug!(operation, profile, outcome, "Keychain"),
+        Err(e) => warn!(operation, profile, error = %e, "Keychain operation failed"),
     }
 }
@@ -0,0 +1,21 @@
+// OpenQuery Desktop — bridge library
+// The Node bridge and what runs it: framing, the typed protocol, supervision
+// and diagnostics. The Tauri app (src/main.rs) and the benchmarks build on it.
+
+pub mod bridge;
+pub mod diagnostics;
+pub mod environment;
+pub mod error;
+pub mod framing;
+pub mod logs;
+pub mod metrics;
+pub mod node;
+pub mod protocol;
+pub mod recording;
+#[cfg(debug_assertions)]
+pub mod reload;
+pub mod sandbox;
+pub mod settings;
+pub mod supervisor;
+pub mod telemetry;
+pub mod transport;
@@ -0,0 +1,286 @@
+// Logs captured by the shell, so users can attach them to a bug report without
+// launching the app from a terminal.
+//
+// Two sources: the bridge's stderr (Node warnings, stack traces,
+// `console.error`), piped by `StdioTransport` and handed here one line at a
+// time, and the shell's own JSON-lines trace (see `crate::telemetry`). Each
+// line is kept in a per-source ring buffer for `logs_tail` and appended to a
+// file in the app log directory: `bridge.log`, each line prefixed with when it
+// was read and echoed to the shell's stderr for development, and
+// `shell.jsonl` as is. Files rotate at 5 MB into `<file>.1` .. `.3` (`.3`
+// oldest); `logs_export` joins them, oldest first, into one file.
+//
+// A bridge attached over `OPENQUERY_BRIDGE_SOCKET` keeps its own stderr, so
+// nothing is captured for it.
+//
+// Problems writing the files go straight to stderr, not through tracing: the
+// trace itself is written here.
+
+use crate::transport::LineSink;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Lines kept in memory per source.
+const RING_LINES: usize = 2000;
+/// Size at which `<source>.log` is rotated.
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept besides the current one.
+const KEEP_ROTATED: u32 = 3;
+
+/// Where a captured line came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogSource {
+    /// The bridge process's stderr.
+    Bridge,
+    /// The shell's structured trace, one JSON object per line.
+    Shell,
+}
+
+impl LogSource {
+    pub const ALL: &'static [LogSource] = &[LogSource::Bridge, LogSource::Shell];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogSource::Bridge => "bridge",
+            LogSource::Shell => "shell",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|source| source.name() == name)
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            LogSource::Bridge => "bridge.log",
+            LogSource::Shell => "shell.jsonl",
+        }
+    }
+
+}
+
+/// One captured line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogLine {
+    /// When the shell read the line, as UTC ISO 8601.
+    pub timestamp: String,
+    pub line: String,
+}
+
+/// Where `logs_export` wrote the logs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogExport {
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Default)]
+struct Channel {
+    lines: VecDeque<LogLine>,
+    /// Open `<source>.log` and its size; None until the first line.
+    file: Option<(File, u64)>,
+    /// The file could not be opened; stop trying until the next launch.
+    unwritable: bool,
+}
+
+pub struct LogStore {
+    dir: Option<PathBuf>,
+    channels: Mutex<BTreeMap<LogSource, Channel>>,
+}
+
+impl LogStore {
+    /// Keep logs in memory and, when there is one, in `dir`.
+    pub fn new(dir: Option<&Path>) -> Self {
+        LogStore {
+            dir: dir.map(Path::to_path_buf),
+            channels: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// A sink that records every line it is given under `source`.
+    pub fn sink(self: &Arc<Self>, source: LogSource) -> LineSink {
+        let store = Arc::clone(self);
+        Arc::new(move |line| store.push(source, line))
+    }
+
+    pub fn push(&self, source: LogSource, line: &str) {
+        if source == LogSource::Bridge {
+            eprintln!("{}", line);
+        }
+        let entry = LogLine {
+            timestamp: format_timestamp(SystemTime::now()),
+            line: line.to_string(),
+        };
+        let Ok(mut channels) = self.channels.lock() else {
+            return;
+        };
+        let channel = channels.entry(source).or_default();
+        if let Some(dir) = &self.dir {
+            append(dir, source, channel, &entry);
+        }
+        if channel.lines.len() == RING_LINES {
+            channel.lines.pop_front();
+        }
+        channel.lines.push_back(entry);
+    }
+
+    /// The last `count` lines from `source`, oldest first.
+    pub fn tail(&self, source: LogSource, count: usize) -> Vec<LogLine> {
+        let Ok(channels) = self.channels.lock() else {
+            return Vec::new();
+        };
+        let Some(channel) = channels.get(&source) else {
+            return Vec::new();
+        };
+        let skip = channel.lines.len().saturating_sub(count);
+        channel.lines.iter().skip(skip).cloned().collect()
+    }
+
+    /// Write every source's logs, oldest first, to one text file in the log
+    /// directory (the temp directory if there is none) and return its path.
+    pub fn export(&self) -> Result<LogExport, String> {
+        let dir = self.dir.clone().unwrap_or_else(std::env::temp_dir);
+        fs::create_dir_all(&dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+        let stamp = format_timestamp(SystemTime::now())[..19].replace([':', '-'], "");
+        let path = dir.join(format!("openquery-logs-{}.txt", stamp));
+
+        // Open the files and note their sizes under the lock, so no rotation
+        // happens halfway through, then read them without holding up pushes.
+        // Rotation renames; an open file keeps reading what it was.
+        let mut parts = Vec::new();
+        let channels = self.channels.lock().map_err(|e| e.to_string())?;
+        for &source in LogSource::ALL {
+            parts.push(Part::Text(format!("===== {} =====\n", source.file_name())));
+            match &self.dir {
+                Some(dir) => {
+                    for n in (0..=KEEP_ROTATED).rev() {
+                        let opened = File::open(rotated(dir, source, n))
+                            .and_then(|file| Ok((file.metadata()?.len(), file)));
+                        if let Ok((len, file)) = opened {
+                            parts.push(Part::File(file, len));
+                        }
+                    }
+                }
+                None => {
+                    let lines = channels.get(&source).into_iter().flat_map(|c| &c.lines);
+                    let text: String = lines.map(|entry| format!("{}\n", file_line(source, entry))).collect();
+                    parts.push(Part::Text(text));
+                }
+            }
+        }
+        drop(channels);
+
+        let mut out = Vec::new();
+        for part in parts {
+            match part {
+                Part::Text(text) => out.extend_from_slice(text.as_bytes()),
+                Part::File(file, len) => {
+                    let _ = file.take(len).read_to_end(&mut out);
+                }
+            }
+        }
+
+        fs::write(&path, &out).map_err(|e| format!("write {}: {}", path.display(), e))?;
+        Ok(LogExport {
+            path: path.display().to_string(),
+            bytes: out.len() as u64,
+        })
+    }
+}
+
+/// A piece of an export: text taken from memory, or the first `len` bytes of a
+/// log file opened while the channels were locked.
+enum Part {
+    Text(String),
+    File(File, u64),
+}
+
+/// A line as written to the source's file. Bridge lines carry no time of their
+/// own, so they are prefixed with when they were read; trace records have one.
+fn file_line(source: LogSource, entry: &LogLine) -> String {
+    match source {
+        LogSource::Bridge => format!("{} {}", entry.timestamp, entry.line),
+        LogSource::Shell => entry.line.clone(),
+    }
+}
+
+/// The source's file for 0, else its n-th rotation.
+fn rotated(dir: &Path, source: LogSource, n: u32) -> PathBuf {
+    match n {
+        0 => dir.join(source.file_name()),
+        n => dir.join(format!("{}.{}", source.file_name(), n)),
+    }
+}
+
+/// Append `entry` to the channel's file, rotating first if it is full. A file
+/// that cannot be opened is given up on; the ring buffer still has every line.
+fn append(dir: &Path, source: LogSource, channel: &mut Channel, entry: &LogLine) {
+    if channel.unwritable {
+        return;
+    }
+    let text = format!("{}\n", file_line(source, entry));
+    if channel.file.as_ref().is_some_and(|(_, size)| *size + text.len() as u64 > MAX_FILE_BYTES) {
+        channel.file = None;
+        for n in (1..=KEEP_ROTATED).rev() {
+            let _ = fs::rename(rotated(dir, source, n - 1), rotated(dir, source, n));
+        }
+    }
+    if channel.file.is_none() {
+        let path = rotated(dir, source, 0);
+        let opened = fs::create_dir_all(dir)
+            .and_then(|_| OpenOptions::new().create(true).append(true).open(&path))
+            .and_then(|file| Ok((file.metadata()?.len(), file)));
+        match opened {
+            Ok((size, file)) => channel.file = Some((file, size)),
+            Err(e) => {
+                eprintln!("[logs] Cannot write {}: {}", path.display(), e);
+                channel.unwritable = true;
+                return;
+            }
+        }
+    }
+    if let Some((file, size)) = &mut channel.file {
+        if file.write_all(text.as_bytes()).is_ok() {
+            *size += text.len() as u64;
+        }
+    }
+}
+
+/// `2026-01-31T12:34:56.789Z`, without pulling in a date crate.
+fn format_timestamp(at: SystemTime) -> String {
+    let since = at.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let time = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        since.subsec_millis()
+    )
+}
+
+/// Gregorian date of a day count since 1970-01-01 (Howard Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
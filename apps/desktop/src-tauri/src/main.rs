@@ -3,385 +3,821 @@
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod bridge;
+mod credentials;
 mod keychain;
 
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+use openquery_desktop::{
+    bridge, diagnostics, environment, error, framing, logs, metrics, protocol, recording, settings, supervisor,
+    telemetry, transport,
+};
+use error::BridgeError;
+use protocol::Method;
 use serde_json::Value;
-use tauri::State;
-use std::sync::Mutex;
+use tauri::{Emitter, Manager, State};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 struct AppState {
-    bridge: Mutex<Option<bridge::Bridge>>,
+    /// None when the bridge could not be launched. The window still opens and
+    /// bridge commands fail with `notStarted` until `bridge_retry` succeeds.
+    bridge: Mutex<Option<Arc<supervisor::BridgeSupervisor>>>,
+    /// Why the last launch failed, if it did.
+    bridge_error: Mutex<Option<diagnostics::LaunchFailure>>,
+    /// Set while a launch is under way, from before the window opens.
+    starting: AtomicBool,
+    launcher: Launcher,
+    settings: Arc<settings::SettingsStore>,
+    /// Bridge stderr and the shell trace, kept for `logs_tail` and `logs_export`.
+    logs: Arc<logs::LogStore>,
+    /// Level control for the installed trace subscriber; None in tests.
+    telemetry: Option<telemetry::Telemetry>,
+    /// Bridge call metrics since startup, and from earlier sessions if kept.
+    metrics: Arc<metrics::Metrics>,
+    /// Database passwords typed this session; never sent back to the webview.
+    passwords: credentials::SessionPasswords,
+    /// Profile ids and the active profile, for resolving passwords.
+    profiles: credentials::ProfileIndex,
+}
+
+/// What every bridge launch needs, kept so the UI can retry a failed one.
+struct Launcher {
+    resource_dir: Option<PathBuf>,
+    /// Attach to this bridge instead of spawning one (`OPENQUERY_BRIDGE_SOCKET`,
+    /// `OPENQUERY_BRIDGE_REPLAY`, tests).
+    connector: Option<transport::Connector>,
+    /// Frame size limit and preferred encoding for every bridge launched.
+    wire: framing::Wire,
+    sink: supervisor::EventSink,
+    notify: bridge::NotificationSink,
+    /// Set by `OPENQUERY_BRIDGE_RECORD`.
+    recorder: Option<Arc<recording::Recorder>>,
+    /// Serializes launches so two retries never race.
+    busy: tokio::sync::Mutex<()>,
+}
+
+impl AppState {
+    fn bridge(&self) -> Result<Arc<supervisor::BridgeSupervisor>, BridgeError> {
+        if let Some(bridge) = self.bridge.lock().ok().and_then(|b| b.clone()) {
+            return Ok(bridge);
+        }
+        let reason = match self.bridge_error.lock().ok().and_then(|f| f.clone()) {
+            _ if self.starting.load(Ordering::SeqCst) => "Bridge is still starting.".to_string(),
+            Some(failure) => format!("Bridge is not running: {}", failure.reason),
+            None => "Bridge is not running yet.".to_string(),
+        };
+        Err(BridgeError::NotStarted(reason))
+    }
+
+    fn bridge_error(&self) -> Option<diagnostics::LaunchFailure> {
+        self.bridge_error.lock().ok().and_then(|f| f.clone())
+    }
+
+    /// Stop the running bridge, if any, letting it close its store first.
+    async fn shutdown_bridge(&self) {
+        let current = self.bridge.lock().ok().and_then(|mut b| b.take());
+        if let Some(bridge) = current {
+            bridge.shutdown().await;
+        }
+    }
+
+    /// Stop the bridge for good, after any launch under way has finished.
+    async fn stop_bridge(&self) {
+        let _busy = self.launcher.busy.lock().await;
+        self.shutdown_bridge().await;
+    }
+
+    /// The launch at startup, run after the window is up: Node discovery and the
+    /// handshake can take seconds. `Launched` tells the frontend it is done.
+    async fn launch_at_startup(&self) {
+        tracing::info!(version = env!("CARGO_PKG_VERSION"), "Starting bridge");
+        let error = match self.launch_bridge().await {
+            Ok(()) => {
+                tracing::info!("Bridge started");
+                None
+            }
+            Err(reason) => {
+                // Without a bridge the app still works; Setup shows the reason and offers a retry.
+                tracing::warn!(%reason, "Bridge unavailable, continuing without it");
+                Some(reason)
+            }
+        };
+        (self.launcher.sink)(supervisor::BridgeEvent::Launched {
+            running: error.is_none(),
+            error,
+        });
+    }
+
+    /// (Re)launch the bridge, replacing any running one. A failure is recorded
+    /// for `bridge_status` instead of aborting the app.
+    async fn launch_bridge(&self) -> Result<(), String> {
+        self.starting.store(true, Ordering::SeqCst);
+        let _busy = self.launcher.busy.lock().await;
+        self.shutdown_bridge().await;
+        self.profiles.invalidate();
+
+        let source = match &self.launcher.connector {
+            Some(connect) => Ok(supervisor::BridgeSource::Connect(Arc::clone(connect))),
+            None => bridge::BridgeLocation::resolve(self.launcher.resource_dir.as_deref())
+                .map(supervisor::BridgeSource::Node),
+        };
+        let result = match source {
+            Err(reason) => Err(diagnostics::LaunchFailure {
+                reason,
+                location: None,
+                node: None,
+            }),
+            Ok(source) => {
+                let location = match &source {
+                    supervisor::BridgeSource::Node(location) => Some(location.clone()),
+                    supervisor::BridgeSource::Connect(_) => None,
+                };
+                supervisor::BridgeSupervisor::start(
+                    source,
+                    self.settings.get(),
+                    supervisor::RestartPolicy::from_env(),
+                    self.launcher.wire,
+                    Arc::clone(&self.launcher.sink),
+                    Arc::clone(&self.launcher.notify),
+                    self.logs.sink(logs::LogSource::Bridge),
+                    Arc::clone(&self.metrics),
+                    self.launcher.recorder.clone(),
+                )
+                .await
+                .map_err(|e| diagnostics::LaunchFailure {
+                    reason: e.reason,
+                    location,
+                    node: e.node,
+                })
+            }
+        };
+
+        let failure = match result {
+            Ok(supervisor) => {
+                if let Ok(mut bridge) = self.bridge.lock() {
+                    *bridge = Some(Arc::new(supervisor));
+                }
+                None
+            }
+            Err(failure) => Some(failure),
+        };
+        let outcome = failure.as_ref().map_or(Ok(()), |f| Err(f.reason.clone()));
+        if let Ok(mut error) = self.bridge_error.lock() {
+            *error = failure;
+        }
+        self.starting.store(false, Ordering::SeqCst);
+        outcome
+    }
+
+    /// Save this session's call metrics if the user keeps them across sessions.
+    fn save_metrics(&self) {
+        if !self.settings.get().persist_metrics() {
+            return;
+        }
+        if let Err(e) = self.metrics.save() {
+            tracing::warn!(error = %e, "Failed to save bridge call metrics");
+        }
+    }
+}
+
+// ── Bridge helpers (async — bridge I/O never runs on the main thread) ────
+
+async fn call_bridge<M: Method>(state: &State<'_, AppState>, params: M::Params) -> Result<M::Result, BridgeError> {
+    state.bridge()?.request::<M>(&params, None).await
+}
+
+/// Like `call_bridge`, for long-running methods the UI may want to stop.
+/// The frontend picks `request_id` so it can pass the same id to `bridge_cancel`.
+async fn call_bridge_cancellable<M: Method>(
+    state: &State<'_, AppState>,
+    params: M::Params,
+    request_id: Option<String>,
+) -> Result<M::Result, BridgeError> {
+    state.bridge()?.request::<M>(&params, request_id).await
+}
+
+/// Run `work` on the blocking pool: keychain and file I/O can stall, and must
+/// not hold up the main thread or the async runtime. `error` wraps its failure.
+async fn blocking<T: Send + 'static>(
+    error: fn(String) -> BridgeError,
+    work: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, BridgeError> {
+    match tokio::task::spawn_blocking(work).await {
+        Ok(result) => result.map_err(error),
+        Err(e) => Err(error(e.to_string())),
+    }
+}
+
+/// Profile `name` (or the active one) and its password, for a command that
+/// reaches the database; see `credentials`.
+async fn credential(state: &State<'_, AppState>, name: Option<String>) -> Result<credentials::Credential, BridgeError> {
+    let bridge = state.bridge()?;
+    credentials::resolve(&bridge, &state.passwords, &state.profiles, name).await
+}
+
+/// Versions of the shell and the running bridge, plus the bridge methods it
+/// supports, so the UI can hide features an older bridge lacks.
+#[tauri::command]
+fn bridge_info(state: State<'_, AppState>) -> Result<protocol::ShellInfo, BridgeError> {
+    let bridge = state.bridge()?;
+    let info = bridge.info()?;
+    Ok(protocol::ShellInfo {
+        shell_version: env!("CARGO_PKG_VERSION").to_string(),
+        protocol_version: info.protocol_version,
+        bridge_version: info.bridge_version,
+        methods: info.methods,
+        encoding: bridge.encoding()?.name().to_string(),
+    })
+}
+
+#[tauri::command]
+async fn bridge_cancel(state: State<'_, AppState>, request_id: String) -> Result<bool, BridgeError> {
+    state.bridge()?.cancel(&request_id).await
+}
+
+/// Whether the bridge is running; if not, why, and the script and Node it resolved.
+#[tauri::command]
+fn bridge_status(state: State<'_, AppState>) -> diagnostics::BridgeStatus {
+    diagnostics::status(
+        state.bridge().ok().as_deref(),
+        state.bridge_error().as_ref(),
+        state.starting.load(Ordering::SeqCst),
+    )
+}
+
+/// Whether the running bridge answers its heartbeat, with the latest round trip.
+#[tauri::command]
+fn bridge_health(state: State<'_, AppState>) -> diagnostics::BridgeHealth {
+    diagnostics::health(state.bridge().ok().as_deref())
+}
+
+/// Relaunch the bridge (e.g. after installing Node or setting its path) and
+/// report the outcome. Any running bridge is replaced.
+#[tauri::command]
+async fn bridge_retry(state: State<'_, AppState>) -> Result<diagnostics::BridgeStatus, BridgeError> {
+    if let Err(reason) = state.launch_bridge().await {
+        tracing::warn!(%reason, "Bridge retry failed");
+    }
+    Ok(bridge_status(state))
+}
+
+/// Bridge script and Node runtime candidates, for the troubleshooting view.
+#[tauri::command]
+fn diagnostics_startup(state: State<'_, AppState>) -> diagnostics::StartupDiagnostics {
+    diagnostics::startup(state.bridge().ok().as_deref(), state.bridge_error().as_ref())
+}
+
+/// The last `lines` (default 200) captured lines from `source` ("bridge" or
+/// "shell"), oldest first.
+#[tauri::command]
+fn logs_tail(state: State<'_, AppState>, source: String, lines: Option<u32>) -> Result<Vec<logs::LogLine>, BridgeError> {
+    let source = logs::LogSource::from_name(&source)
+        .ok_or_else(|| BridgeError::Logs(format!("Unknown log source: {}", source)))?;
+    Ok(state.logs.tail(source, lines.unwrap_or(200) as usize))
 }
 
-// ── Bridge helper (synchronous — no await while holding the lock) ────
+/// Write all captured logs to one file for a bug report and return where it is.
+#[tauri::command]
+async fn logs_export(state: State<'_, AppState>) -> Result<logs::LogExport, BridgeError> {
+    let logs = Arc::clone(&state.logs);
+    blocking(BridgeError::Logs, move || logs.export()).await
+}
 
-fn call_bridge_sync(state: &State<'_, AppState>, method: &str, params: Value) -> Result<Value, String> {
-    let bridge_guard = state.bridge.lock().map_err(|e| e.to_string())?;
-    let bridge = bridge_guard.as_ref().ok_or("Bridge not started")?;
-    bridge.call(method, params).map_err(|e| e.to_string())
+/// Per-method latency, error and payload-size metrics for bridge calls, for
+/// this session and (when kept) earlier OpenQuery versions.
+#[tauri::command]
+fn diagnostics_metrics(state: State<'_, AppState>) -> diagnostics::MetricsReport {
+    state.metrics.report()
 }
 
+// ── Shell settings ──────────────────────────────────────────────
+
 #[tauri::command]
-fn profiles_list(state: State<'_, AppState>) -> Result<Value, String> {
-    call_bridge_sync(&state, "profiles.list", Value::Object(Default::default()))
+fn shell_settings_get(state: State<'_, AppState>) -> settings::ShellSettings {
+    state.settings.get()
 }
 
+/// Save shell settings. A changed log level applies at once, a changed Node
+/// path or bridge environment the next time the bridge starts.
 #[tauri::command]
-fn profiles_add(state: State<'_, AppState>, params: Value) -> Result<Value, String> {
-    call_bridge_sync(&state, "profiles.add", params)
+async fn shell_settings_set(state: State<'_, AppState>, settings: settings::ShellSettings) -> Result<(), BridgeError> {
+    let log_level = settings.log_level().map(str::to_string);
+    if let Some(level) = &log_level {
+        telemetry::parse_level(level).map_err(BridgeError::Settings)?;
+    }
+    for entry in settings.bridge_env() {
+        environment::validate_pattern(&entry).map_err(BridgeError::Settings)?;
+    }
+    let (store, next) = (Arc::clone(&state.settings), settings.clone());
+    blocking(BridgeError::Settings, move || store.set(next)).await?;
+    if let Some(telemetry) = &state.telemetry {
+        telemetry.set_level(log_level.as_deref()).map_err(BridgeError::Settings)?;
+    }
+    if let Ok(bridge) = state.bridge() {
+        bridge.set_settings(settings);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn profiles_list(state: State<'_, AppState>) -> Result<Vec<protocol::ListedProfile>, BridgeError> {
+    let profiles = call_bridge::<protocol::ProfilesList>(&state, protocol::NoParams {}).await?;
+    state.profiles.update(&profiles);
+    Ok(profiles)
 }
 
 #[tauri::command]
-fn profiles_remove(state: State<'_, AppState>, name: String) -> Result<Value, String> {
-    let _ = keychain::delete_password(&name);
-    let mut params = serde_json::Map::new();
-    params.insert("name".to_string(), Value::String(name));
-    call_bridge_sync(&state, "profiles.remove", Value::Object(params))
+async fn profiles_add(state: State<'_, AppState>, params: protocol::NewProfile) -> Result<protocol::Profile, BridgeError> {
+    reject_reserved(&params.name)?;
+    state.profiles.invalidate();
+    call_bridge::<protocol::ProfilesAdd>(&state, params).await
 }
 
 #[tauri::command]
-fn profiles_use(state: State<'_, AppState>, name: String) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("name".to_string(), Value::String(name));
-    call_bridge_sync(&state, "profiles.use", Value::Object(params))
+async fn profiles_remove(state: State<'_, AppState>, name: String) -> Result<protocol::Removed, BridgeError> {
+    state.passwords.set(&name, "");
+    state.profiles.invalidate();
+    let removed = call_bridge::<protocol::ProfilesRemove>(&state, protocol::ByName { name }).await?;
+    // Passwords are saved under the profile id, which the bridge reports.
+    let profile_id = removed.profile_id.clone();
+    let _ = blocking(BridgeError::Keychain, move || {
+        keychain::delete_password(&profile_id).map_err(|e| e.to_string())
+    })
+    .await;
+    Ok(removed)
 }
 
 #[tauri::command]
-fn profiles_test(state: State<'_, AppState>, name: String, password: String) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("name".to_string(), Value::String(name));
-    params.insert("password".to_string(), Value::String(password));
-    call_bridge_sync(&state, "profiles.test", Value::Object(params))
+async fn profiles_use(state: State<'_, AppState>, name: String) -> Result<protocol::Ack, BridgeError> {
+    state.profiles.invalidate();
+    call_bridge::<protocol::ProfilesUse>(&state, protocol::ByName { name }).await
 }
 
 #[tauri::command]
-fn profiles_get_active(state: State<'_, AppState>) -> Result<Value, String> {
-    call_bridge_sync(&state, "profiles.getActive", Value::Object(Default::default()))
+async fn profiles_test(state: State<'_, AppState>, name: String) -> Result<protocol::ConnectionTest, BridgeError> {
+    let password = credential(&state, Some(name.clone())).await?.password;
+    call_bridge::<protocol::ProfilesTest>(&state, protocol::ProfileTest { name, password }).await
+}
+
+#[tauri::command]
+async fn profiles_get_active(state: State<'_, AppState>) -> Result<protocol::ActiveProfile, BridgeError> {
+    call_bridge::<protocol::ProfilesGetActive>(&state, protocol::NoParams {}).await
 }
 
 // ── Keychain commands ───────────────────────────────────────────
 
+/// Refuse a key in the shell's keychain namespace (see `credentials`).
+fn reject_reserved(key: &str) -> Result<(), BridgeError> {
+    if credentials::is_reserved(key) {
+        return Err(BridgeError::Keychain(format!("\"{}\" is reserved for the app's own keychain entries.", key)));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+async fn keychain_set(profile_id: String, password: String) -> Result<(), BridgeError> {
+    reject_reserved(&profile_id)?;
+    blocking(BridgeError::Keychain, move || {
+        keychain::set_password(&profile_id, &password).map_err(|e| e.to_string())
+    })
+    .await
+}
+
+/// Whether a secret is stored under `profile_id`. Stored secrets are never
+/// read back into the webview; commands resolve them in `credentials`.
 #[tauri::command]
-fn keychain_set(profile_id: String, password: String) -> Result<(), String> {
-    keychain::set_password(&profile_id, &password).map_err(|e| e.to_string())
+async fn keychain_has(profile_id: String) -> Result<bool, BridgeError> {
+    blocking(BridgeError::Keychain, move || {
+        keychain::get_password(&profile_id)
+            .map(|password| password.is_some())
+            .map_err(|e| e.to_string())
+    })
+    .await
 }
 
 #[tauri::command]
-fn keychain_get(profile_id: String) -> Result<Option<String>, String> {
-    keychain::get_password(&profile_id).map_err(|e| e.to_string())
+async fn keychain_delete(profile_id: String) -> Result<(), BridgeError> {
+    reject_reserved(&profile_id)?;
+    blocking(BridgeError::Keychain, move || {
+        keychain::delete_password(&profile_id).map_err(|e| e.to_string())
+    })
+    .await
 }
 
+/// Use `password` for profile `name` until the app exits, ahead of the
+/// keychain; an empty one forgets it.
 #[tauri::command]
-fn keychain_delete(profile_id: String) -> Result<(), String> {
-    keychain::delete_password(&profile_id).map_err(|e| e.to_string())
+async fn session_password_set(state: State<'_, AppState>, name: String, password: String) -> Result<(), BridgeError> {
+    state.passwords.set(&name, &password);
+    Ok(())
 }
 
 // ── Schema commands ─────────────────────────────────────────────
 
 #[tauri::command]
-fn schema_refresh(state: State<'_, AppState>, password: String, name: Option<String>) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("password".to_string(), Value::String(password));
-    if let Some(n) = name {
-        params.insert("name".to_string(), Value::String(n));
-    }
-    call_bridge_sync(&state, "schema.refresh", Value::Object(params))
+async fn schema_refresh(
+    state: State<'_, AppState>,
+    name: Option<String>,
+    request_id: Option<String>,
+) -> Result<protocol::SchemaRefreshSummary, BridgeError> {
+    let credentials::Credential { name, password } = credential(&state, name).await?;
+    let params = protocol::SchemaRefreshParams { password, name };
+    call_bridge_cancellable::<protocol::SchemaRefresh>(&state, params, request_id).await
 }
 
 #[tauri::command]
-fn schema_search(state: State<'_, AppState>, query: String) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("query".to_string(), Value::String(query));
-    call_bridge_sync(&state, "schema.search", Value::Object(params))
+async fn schema_search(state: State<'_, AppState>, query: String) -> Result<Vec<protocol::SchemaMatch>, BridgeError> {
+    call_bridge::<protocol::SchemaSearch>(&state, protocol::SchemaSearchParams { query }).await
 }
 
 #[tauri::command]
-fn schema_table_detail(state: State<'_, AppState>, table: String, schema: Option<String>) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("table".to_string(), Value::String(table));
-    if let Some(s) = schema {
-        params.insert("schema".to_string(), Value::String(s));
-    }
-    call_bridge_sync(&state, "schema.tableDetail", Value::Object(params))
+async fn schema_table_detail(
+    state: State<'_, AppState>,
+    table: String,
+    schema: Option<String>,
+) -> Result<protocol::TableInfo, BridgeError> {
+    call_bridge::<protocol::SchemaTableDetail>(&state, protocol::TableDetailParams { table, schema }).await
 }
 
 #[tauri::command]
-fn schema_get_snapshot(state: State<'_, AppState>) -> Result<Value, String> {
-    call_bridge_sync(&state, "schema.getSnapshot", Value::Object(Default::default()))
+async fn schema_get_snapshot(state: State<'_, AppState>) -> Result<Option<protocol::SchemaSnapshot>, BridgeError> {
+    call_bridge::<protocol::SchemaGetSnapshot>(&state, protocol::NoParams {}).await
 }
 
 // ── Ask commands ────────────────────────────────────────────────
 
 #[tauri::command]
-fn ask_dry_run(
+async fn ask_dry_run(
     state: State<'_, AppState>,
     question: String,
     mode: String,
-    password: String,
-    open_ai_api_key: Option<String>,
-) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("question".to_string(), Value::String(question));
-    params.insert("mode".to_string(), Value::String(mode));
-    params.insert("password".to_string(), Value::String(password));
-    if let Some(key) = open_ai_api_key {
-        params.insert("openAiApiKey".to_string(), Value::String(key));
-    }
-    call_bridge_sync(&state, "ask.dryRun", Value::Object(params))
+    name: Option<String>,
+    request_id: Option<String>,
+) -> Result<protocol::AskResult, BridgeError> {
+    let credentials::Credential { name, password } = credential(&state, name).await?;
+    let params = protocol::AskParams {
+        question,
+        mode,
+        password,
+        name,
+        open_ai_api_key: credentials::openai_key().await,
+    };
+    call_bridge_cancellable::<protocol::AskDryRun>(&state, params, request_id).await
 }
 
 #[tauri::command]
-fn ask_run(
+async fn ask_run(
     state: State<'_, AppState>,
     question: String,
     mode: String,
-    password: String,
-    open_ai_api_key: Option<String>,
-) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("question".to_string(), Value::String(question));
-    params.insert("mode".to_string(), Value::String(mode));
-    params.insert("password".to_string(), Value::String(password));
-    if let Some(key) = open_ai_api_key {
-        params.insert("openAiApiKey".to_string(), Value::String(key));
-    }
-    call_bridge_sync(&state, "ask.run", Value::Object(params))
-}
-
-#[tauri::command]
-fn workspace_sql(
+    name: Option<String>,
+    request_id: Option<String>,
+) -> Result<protocol::AskResult, BridgeError> {
+    let credentials::Credential { name, password } = credential(&state, name).await?;
+    let params = protocol::AskParams {
+        question,
+        mode,
+        password,
+        name,
+        open_ai_api_key: credentials::openai_key().await,
+    };
+    call_bridge_cancellable::<protocol::AskRun>(&state, params, request_id).await
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn workspace_sql(
     state: State<'_, AppState>,
     sql: String,
     mode: String,
     action: Option<String>,
-    policy: Option<Value>,
-    password: String,
+    policy: Option<protocol::SqlPolicy>,
     name: Option<String>,
-) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("sql".to_string(), Value::String(sql));
-    params.insert("mode".to_string(), Value::String(mode));
-    params.insert("password".to_string(), Value::String(password));
-    if let Some(a) = action {
-        params.insert("action".to_string(), Value::String(a));
-    }
-    if let Some(p) = policy {
-        params.insert("policy".to_string(), p);
-    }
-    if let Some(n) = name {
-        params.insert("name".to_string(), Value::String(n));
-    }
-    call_bridge_sync(&state, "workspace.sql", Value::Object(params))
+    stream_rows: Option<bool>,
+    request_id: Option<String>,
+) -> Result<protocol::WorkspaceSqlResult, BridgeError> {
+    let credentials::Credential { name, password } = credential(&state, name).await?;
+    let params = protocol::WorkspaceSqlParams {
+        sql,
+        mode,
+        password,
+        action,
+        policy,
+        name,
+        stream_rows,
+    };
+    call_bridge_cancellable::<protocol::WorkspaceSql>(&state, params, request_id).await
 }
 
 // ── History commands ────────────────────────────────────────────
 
 #[tauri::command]
-fn history_list(state: State<'_, AppState>, limit: Option<u32>) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    if let Some(l) = limit {
-        params.insert("limit".to_string(), Value::Number(l.into()));
-    }
-    call_bridge_sync(&state, "history.list", Value::Object(params))
+async fn history_list(state: State<'_, AppState>, limit: Option<u32>) -> Result<Vec<protocol::HistoryListItem>, BridgeError> {
+    call_bridge::<protocol::HistoryList>(&state, protocol::HistoryListParams { limit }).await
 }
 
 #[tauri::command]
-fn history_show(state: State<'_, AppState>, id: String) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("id".to_string(), Value::String(id));
-    call_bridge_sync(&state, "history.show", Value::Object(params))
+async fn history_show(state: State<'_, AppState>, id: String) -> Result<protocol::HistoryDetail, BridgeError> {
+    call_bridge::<protocol::HistoryShow>(&state, protocol::ById { id }).await
 }
 
 #[tauri::command]
-fn history_export_md(state: State<'_, AppState>, id: String) -> Result<String, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("id".to_string(), Value::String(id));
-    let result = call_bridge_sync(&state, "history.exportMd", Value::Object(params))?;
-    result.as_str().map(|s| s.to_string()).ok_or("Expected string result".to_string())
+async fn history_export_md(state: State<'_, AppState>, id: String) -> Result<String, BridgeError> {
+    call_bridge::<protocol::HistoryExportMd>(&state, protocol::ById { id }).await
 }
 
 // ── Settings commands ───────────────────────────────────────────
 
 #[tauri::command]
-fn settings_status(state: State<'_, AppState>) -> Result<Value, String> {
-    call_bridge_sync(&state, "settings.status", Value::Object(Default::default()))
+async fn settings_status(state: State<'_, AppState>) -> Result<protocol::SettingsStatusResult, BridgeError> {
+    call_bridge::<protocol::SettingsStatus>(&state, protocol::NoParams {}).await
+}
+
+#[tauri::command]
+async fn settings_test_openai_key(state: State<'_, AppState>, api_key: Option<String>) -> Result<protocol::KeyCheck, BridgeError> {
+    // Without a candidate, test the saved key (the bridge falls back to the environment).
+    let api_key = match api_key.filter(|key| !key.trim().is_empty()) {
+        Some(key) => Some(key),
+        None => credentials::openai_key().await,
+    };
+    call_bridge::<protocol::SettingsTestOpenAiKey>(&state, protocol::OpenAiKeyParams { api_key }).await
 }
 
+/// Save the OpenAI key in the keychain, where only the shell reads it.
 #[tauri::command]
-fn settings_test_openai_key(state: State<'_, AppState>, api_key: Option<String>) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    if let Some(key) = api_key {
-        params.insert("apiKey".to_string(), Value::String(key));
+async fn settings_set_openai_key(api_key: String) -> Result<(), BridgeError> {
+    let api_key = api_key.trim().to_string();
+    if api_key.is_empty() {
+        return Err(BridgeError::Settings("OpenAI API key cannot be empty.".to_string()));
     }
-    call_bridge_sync(&state, "settings.testOpenAiKey", Value::Object(params))
+    blocking(BridgeError::Keychain, move || {
+        keychain::set_password(credentials::OPENAI_KEY_ID, &api_key).map_err(|e| e.to_string())
+    })
+    .await
 }
 
 #[tauri::command]
-fn demo_no_docker_status(state: State<'_, AppState>) -> Result<Value, String> {
-    call_bridge_sync(&state, "demo.noDockerStatus", Value::Object(Default::default()))
+async fn settings_clear_openai_key() -> Result<(), BridgeError> {
+    blocking(BridgeError::Keychain, || {
+        keychain::delete_password(credentials::OPENAI_KEY_ID).map_err(|e| e.to_string())
+    })
+    .await
 }
 
 #[tauri::command]
-fn demo_no_docker_prepare(state: State<'_, AppState>, reset: Option<bool>) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("reset".to_string(), Value::Bool(reset.unwrap_or(false)));
-    call_bridge_sync(&state, "demo.noDockerPrepare", Value::Object(params))
+async fn demo_no_docker_status(state: State<'_, AppState>) -> Result<protocol::DemoStatus, BridgeError> {
+    call_bridge::<protocol::DemoNoDockerStatus>(&state, protocol::NoParams {}).await
 }
 
 #[tauri::command]
-fn demo_no_docker_reset(state: State<'_, AppState>) -> Result<Value, String> {
-    call_bridge_sync(&state, "demo.noDockerReset", Value::Object(Default::default()))
+async fn demo_no_docker_prepare(state: State<'_, AppState>, reset: Option<bool>) -> Result<protocol::DemoReady, BridgeError> {
+    let params = protocol::DemoPrepareParams {
+        reset: reset.unwrap_or(false),
+    };
+    // Creates the demo profile and makes it active.
+    state.profiles.invalidate();
+    call_bridge::<protocol::DemoNoDockerPrepare>(&state, params).await
 }
 
 #[tauri::command]
-fn fixture_check_docker(state: State<'_, AppState>) -> Result<Value, String> {
-    call_bridge_sync(&state, "fixture.checkDocker", Value::Object(Default::default()))
+async fn demo_no_docker_reset(state: State<'_, AppState>) -> Result<protocol::DemoReady, BridgeError> {
+    state.profiles.invalidate();
+    call_bridge::<protocol::DemoNoDockerReset>(&state, protocol::NoParams {}).await
 }
 
 #[tauri::command]
-fn fixture_pick_port(state: State<'_, AppState>, preferred_ports: Option<Vec<u16>>) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    if let Some(ports) = preferred_ports {
-        params.insert(
-            "preferredPorts".to_string(),
-            Value::Array(ports.into_iter().map(|p| Value::Number((p as u64).into())).collect()),
-        );
-    }
-    call_bridge_sync(&state, "fixture.pickPort", Value::Object(params))
+async fn fixture_check_docker(state: State<'_, AppState>) -> Result<protocol::DockerCheck, BridgeError> {
+    call_bridge::<protocol::FixtureCheckDocker>(&state, protocol::NoParams {}).await
 }
 
 #[tauri::command]
-fn fixture_up(state: State<'_, AppState>, port: u16) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("port".to_string(), Value::Number((port as u64).into()));
-    call_bridge_sync(&state, "fixture.up", Value::Object(params))
+async fn fixture_pick_port(state: State<'_, AppState>, preferred_ports: Option<Vec<u16>>) -> Result<protocol::PickedPort, BridgeError> {
+    call_bridge::<protocol::FixturePickPort>(&state, protocol::PickPortParams { preferred_ports }).await
 }
 
 #[tauri::command]
-fn fixture_down(state: State<'_, AppState>) -> Result<Value, String> {
-    call_bridge_sync(&state, "fixture.down", Value::Object(Default::default()))
+async fn fixture_up(state: State<'_, AppState>, port: u16) -> Result<protocol::FixtureUpResult, BridgeError> {
+    state.profiles.invalidate();
+    call_bridge::<protocol::FixtureUp>(&state, protocol::FixtureUpParams { port }).await
 }
 
 #[tauri::command]
-fn fixture_status(state: State<'_, AppState>) -> Result<Value, String> {
-    call_bridge_sync(&state, "fixture.status", Value::Object(Default::default()))
+async fn fixture_down(state: State<'_, AppState>) -> Result<protocol::Ack, BridgeError> {
+    call_bridge::<protocol::FixtureDown>(&state, protocol::NoParams {}).await
 }
 
 #[tauri::command]
-fn fixture_logs(state: State<'_, AppState>, tail: Option<u32>) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    if let Some(t) = tail {
-        params.insert("tail".to_string(), Value::Number(t.into()));
-    }
-    call_bridge_sync(&state, "fixture.logs", Value::Object(params))
+async fn fixture_status(state: State<'_, AppState>) -> Result<protocol::FixtureStatusResult, BridgeError> {
+    call_bridge::<protocol::FixtureStatus>(&state, protocol::NoParams {}).await
+}
+
+#[tauri::command]
+async fn fixture_logs(state: State<'_, AppState>, tail: Option<u32>) -> Result<protocol::FixtureLogLines, BridgeError> {
+    call_bridge::<protocol::FixtureLogs>(&state, protocol::FixtureLogsParams { tail }).await
 }
 
 // ── POWER mode commands ─────────────────────────────────────────
 
 #[tauri::command]
-fn profile_update_power(state: State<'_, AppState>, name: String, settings: Value) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("name".to_string(), Value::String(name));
-    params.insert("settings".to_string(), settings);
-    call_bridge_sync(&state, "profile.updatePower", Value::Object(params))
+async fn profile_update_power(
+    state: State<'_, AppState>,
+    name: String,
+    settings: protocol::PowerSettingsUpdate,
+) -> Result<protocol::Ack, BridgeError> {
+    call_bridge::<protocol::ProfileUpdatePower>(&state, protocol::UpdatePowerParams { name, settings }).await
 }
 
 #[tauri::command]
-fn profile_get_power(state: State<'_, AppState>, name: String) -> Result<Value, String> {
-    let mut params = serde_json::Map::new();
-    params.insert("name".to_string(), Value::String(name));
-    call_bridge_sync(&state, "profile.getPower", Value::Object(params))
+async fn profile_get_power(state: State<'_, AppState>, name: String) -> Result<protocol::PowerSettings, BridgeError> {
+    call_bridge::<protocol::ProfileGetPower>(&state, protocol::ByName { name }).await
 }
 
 #[tauri::command]
-fn write_preview(
+async fn write_preview(
     state: State<'_, AppState>,
     sql: String,
-    params: Value,
-    password: String,
+    params: Vec<Value>,
     name: Option<String>,
-) -> Result<Value, String> {
-    let mut payload = serde_json::Map::new();
-    payload.insert("sql".to_string(), Value::String(sql));
-    payload.insert("params".to_string(), params);
-    payload.insert("password".to_string(), Value::String(password));
-    if let Some(n) = name {
-        payload.insert("name".to_string(), Value::String(n));
-    }
-    call_bridge_sync(&state, "write.preview", Value::Object(payload))
+    request_id: Option<String>,
+) -> Result<protocol::WritePreviewResult, BridgeError> {
+    let credentials::Credential { name, password } = credential(&state, name).await?;
+    let payload = protocol::WriteParams {
+        sql,
+        params,
+        password,
+        name,
+    };
+    call_bridge_cancellable::<protocol::WritePreview>(&state, payload, request_id).await
 }
 
 #[tauri::command]
-fn write_execute(
+async fn write_execute(
     state: State<'_, AppState>,
     sql: String,
-    params: Value,
-    password: String,
+    params: Vec<Value>,
     name: Option<String>,
-) -> Result<Value, String> {
-    let mut payload = serde_json::Map::new();
-    payload.insert("sql".to_string(), Value::String(sql));
-    payload.insert("params".to_string(), params);
-    payload.insert("password".to_string(), Value::String(password));
-    if let Some(n) = name {
-        payload.insert("name".to_string(), Value::String(n));
-    }
-    call_bridge_sync(&state, "write.execute", Value::Object(payload))
+    request_id: Option<String>,
+) -> Result<protocol::WriteExecution, BridgeError> {
+    let credentials::Credential { name, password } = credential(&state, name).await?;
+    let payload = protocol::WriteParams {
+        sql,
+        params,
+        password,
+        name,
+    };
+    call_bridge_cancellable::<protocol::WriteExecute>(&state, payload, request_id).await
 }
 
 // ── Main ────────────────────────────────────────────────────────
 
 fn main() {
-    eprintln!("[openquery] Starting bridge...");
-    let bridge_instance = bridge::Bridge::spawn().expect("Failed to start bridge process");
-    eprintln!("[openquery] Bridge started, launching Tauri window...");
+    let commands = tauri::generate_handler![
+        bridge_info,
+        bridge_cancel,
+        bridge_status,
+        bridge_health,
+        bridge_retry,
+        diagnostics_startup,
+        diagnostics_metrics,
+        logs_tail,
+        logs_export,
+        shell_settings_get,
+        shell_settings_set,
+        profiles_list,
+        profiles_add,
+        profiles_remove,
+        profiles_use,
+        profiles_test,
+        profiles_get_active,
+        keychain_set,
+        keychain_has,
+        keychain_delete,
+        session_password_set,
+        schema_refresh,
+        schema_search,
+        schema_table_detail,
+        schema_get_snapshot,
+        ask_dry_run,
+        ask_run,
+        workspace_sql,
+        history_list,
+        history_show,
+        history_export_md,
+        settings_status,
+        settings_test_openai_key,
+        settings_set_openai_key,
+        settings_clear_openai_key,
+        demo_no_docker_status,
+        demo_no_docker_prepare,
+        demo_no_docker_reset,
+        fixture_check_docker,
+        fixture_pick_port,
+        fixture_up,
+        fixture_down,
+        fixture_status,
+        fixture_logs,
+        profile_update_power,
+        profile_get_power,
+        write_preview,
+        write_execute,
+    ];
 
     tauri::Builder::default()
-        .manage(AppState {
-            bridge: Mutex::new(Some(bridge_instance)),
+        .setup(|app| {
+            // Tracing first, so everything below is recorded.
+            let settings = Arc::new(settings::SettingsStore::load(app.path().app_config_dir().ok().as_deref()));
+            let logs = Arc::new(logs::LogStore::new(app.path().app_log_dir().ok().as_deref()));
+            let telemetry = telemetry::Telemetry::init(&logs, settings.get().log_level());
+
+            let handle = app.handle().clone();
+            let sink: supervisor::EventSink = Arc::new(move |event| {
+                let channel = match event {
+                    supervisor::BridgeEvent::Reloaded { .. } => "bridge://reloaded",
+                    _ => "bridge://status",
+                };
+                let _ = handle.emit(channel, event);
+            });
+
+            let handle = app.handle().clone();
+            let notify: bridge::NotificationSink = Arc::new(move |notification| match notification {
+                bridge::Notification::Progress { request_id, data } => {
+                    let _ = handle.emit(&format!("bridge://progress/{}", request_id), data);
+                }
+                bridge::Notification::Event { name, data } => {
+                    let _ = handle.emit(&format!("bridge://event/{}", name), data);
+                }
+            });
+
+            #[cfg(unix)]
+            let connector = std::env::var_os("OPENQUERY_BRIDGE_SOCKET")
+                .map(|path| transport::UnixSocketTransport::connector(PathBuf::from(path)));
+            #[cfg(not(unix))]
+            let connector = None;
+            let connector = match std::env::var_os(recording::REPLAY_ENV) {
+                Some(path) => Some(recording::replay_connector(Path::new(&path)).unwrap_or_else(|e| {
+                    // Fail every launch with the reason rather than quietly starting a real bridge.
+                    let reason = format!("Cannot replay bridge recording: {}", e);
+                    Arc::new(move || {
+                        let reason = reason.clone();
+                        Box::pin(async move { Err(reason.into()) })
+                    })
+                })),
+                None => connector,
+            };
+
+            let state = AppState {
+                bridge: Mutex::new(None),
+                bridge_error: Mutex::new(None),
+                // Until the launch below has run, `bridge_status` says it is starting.
+                starting: AtomicBool::new(true),
+                launcher: Launcher {
+                    resource_dir: app.path().resource_dir().ok(),
+                    connector,
+                    wire: framing::Wire::from_env(),
+                    sink,
+                    notify,
+                    recorder: recording::Recorder::from_env(),
+                    busy: tokio::sync::Mutex::new(()),
+                },
+                settings,
+                logs,
+                telemetry: Some(telemetry),
+                metrics: Arc::new(metrics::Metrics::new(
+                    app.path().app_data_dir().ok().as_deref(),
+                    env!("CARGO_PKG_VERSION"),
+                )),
+                passwords: credentials::SessionPasswords::default(),
+                profiles: credentials::ProfileIndex::default(),
+            };
+
+            app.manage(state);
+            // In the background, so a slow or hung bridge never holds up the window.
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                handle.state::<AppState>().launch_at_startup().await;
+            });
+            Ok(())
         })
-        .invoke_handler(tauri::generate_handler![
-            profiles_list,
-            profiles_add,
-            profiles_remove,
-            profiles_use,
-            profiles_test,
-            profiles_get_active,
-            keychain_set,
-            keychain_get,
-            keychain_delete,
-            schema_refresh,
-            schema_search,
-            schema_table_detail,
-            schema_get_snapshot,
-            ask_dry_run,
-            ask_run,
-            workspace_sql,
-            history_list,
-            history_show,
-            history_export_md,
-            settings_status,
-            settings_test_openai_key,
-            demo_no_docker_status,
-            demo_no_docker_prepare,
-            demo_no_docker_reset,
-            fixture_check_docker,
-            fixture_pick_port,
-            fixture_up,
-            fixture_down,
-            fixture_status,
-            fixture_logs,
-            profile_update_power,
-            profile_get_power,
-            write_preview,
-            write_execute,
-        ])
-        .on_window_event(|_window, event| {
-            if let tauri::WindowEvent::Destroyed = event {
-                // Bridge cleanup happens on drop
-            }
+        .invoke_handler(move |invoke| {
+            // The command name only: arguments can carry passwords.
+            tracing::debug!(command = invoke.message.command(), "Command invoked");
+            commands(invoke)
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // Closing the last window ends up here too. Block until the bridge
+            // has flushed its store and exited, so nothing is left half-written.
+            if let tauri::RunEvent::Exit = event {
+                if let Some(state) = app.try_state::<AppState>() {
+                    tauri::async_runtime::block_on(state.stop_bridge());
+                    state.save_metrics();
+                }
+            }
+        });
 }
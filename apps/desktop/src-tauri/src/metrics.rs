@@ -0,0 +1,235 @@
+// Per-method latency, error and payload metrics for bridge calls.
+//
+// `Bridge` records every call except heartbeat pings: how long it took, the
+// request and response sizes, and the error code if it failed. Durations go
+// into a fixed log-scale histogram (buckets 15% apart), so p50 and p95 are
+// upper-bound estimates and memory stays fixed however many calls are made;
+// the max is exact.
+//
+// With the `persistMetrics` shell setting on, each session's totals are
+// merged into metrics.json under the shell version on exit, so timings on the
+// same databases can be compared across OpenQuery releases.
+
+use crate::diagnostics::{MethodMetrics, MetricsReport, ReleaseMetrics};
+use crate::error::BridgeError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const FILE_NAME: &str = "metrics.json";
+
+/// Bucket `i` holds durations up to GROWTH^i ms; the last one everything longer
+/// (1.15^103 ms is about 4 hours).
+const GROWTH: f64 = 1.15;
+const BUCKETS: usize = 104;
+
+/// Running totals for one method.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Stats {
+    calls: u64,
+    errors: u64,
+    error_codes: BTreeMap<String, u64>,
+    buckets: Vec<u64>,
+    total_ms: f64,
+    max_ms: f64,
+    request_bytes: u64,
+    max_request_bytes: u64,
+    /// Calls that got a response, which `response_bytes` is summed over.
+    responses: u64,
+    response_bytes: u64,
+    max_response_bytes: u64,
+}
+
+impl Stats {
+    fn record(&mut self, ms: f64, request_bytes: u64, outcome: Result<u64, &BridgeError>) {
+        self.calls += 1;
+        self.buckets.resize(BUCKETS, 0);
+        self.buckets[bucket(ms)] += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        self.request_bytes += request_bytes;
+        self.max_request_bytes = self.max_request_bytes.max(request_bytes);
+        match outcome {
+            Ok(bytes) => {
+                self.responses += 1;
+                self.response_bytes += bytes;
+                self.max_response_bytes = self.max_response_bytes.max(bytes);
+            }
+            Err(e) => {
+                self.errors += 1;
+                *self.error_codes.entry(e.code().to_string()).or_default() += 1;
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &Stats) {
+        self.calls += other.calls;
+        self.errors += other.errors;
+        for (code, count) in &other.error_codes {
+            *self.error_codes.entry(code.clone()).or_default() += count;
+        }
+        self.buckets.resize(BUCKETS, 0);
+        for (mine, theirs) in self.buckets.iter_mut().zip(&other.buckets) {
+            *mine += theirs;
+        }
+        self.total_ms += other.total_ms;
+        self.max_ms = self.max_ms.max(other.max_ms);
+        self.request_bytes += other.request_bytes;
+        self.max_request_bytes = self.max_request_bytes.max(other.max_request_bytes);
+        self.responses += other.responses;
+        self.response_bytes += other.response_bytes;
+        self.max_response_bytes = self.max_response_bytes.max(other.max_response_bytes);
+    }
+
+    /// Upper bound of the bucket holding the `q` quantile, capped at the max.
+    fn quantile(&self, q: f64) -> f64 {
+        let rank = ((self.calls as f64) * q).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return GROWTH.powi(i as i32).min(self.max_ms);
+            }
+        }
+        self.max_ms
+    }
+
+    fn report(&self, method: &str) -> MethodMetrics {
+        let mean = |total: u64, n: u64| if n == 0 { 0.0 } else { total as f64 / n as f64 };
+        MethodMetrics {
+            method: method.to_string(),
+            calls: self.calls,
+            errors: self.errors,
+            error_codes: self.error_codes.clone(),
+            p50_ms: self.quantile(0.5),
+            p95_ms: self.quantile(0.95),
+            max_ms: self.max_ms,
+            mean_ms: if self.calls == 0 { 0.0 } else { self.total_ms / self.calls as f64 },
+            request_bytes_mean: mean(self.request_bytes, self.calls),
+            request_bytes_max: self.max_request_bytes,
+            response_bytes_mean: mean(self.response_bytes, self.responses),
+            response_bytes_max: self.max_response_bytes,
+        }
+    }
+}
+
+fn bucket(ms: f64) -> usize {
+    if ms <= 1.0 {
+        return 0;
+    }
+    ((ms.ln() / GROWTH.ln()).ceil() as usize).min(BUCKETS - 1)
+}
+
+type MethodStats = BTreeMap<String, Stats>;
+
+/// metrics.json: totals per shell version, then per method.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct History {
+    #[serde(default)]
+    releases: BTreeMap<String, MethodStats>,
+}
+
+pub struct Metrics {
+    path: Option<PathBuf>,
+    version: String,
+    started: SystemTime,
+    session: Mutex<MethodStats>,
+    /// As loaded at startup; this session is merged in when saving.
+    history: History,
+}
+
+impl Metrics {
+    /// Start a session for shell `version`, with the history in
+    /// `<data_dir>/metrics.json` if there is one.
+    pub fn new(data_dir: Option<&Path>, version: &str) -> Self {
+        let path = data_dir.map(|dir| dir.join(FILE_NAME));
+        let history = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|text| match serde_json::from_str(&text) {
+                Ok(history) => Some(history),
+                Err(e) => {
+                    tracing::warn!(file = FILE_NAME, error = %e, "Ignoring unreadable metrics");
+                    None
+                }
+            })
+            .unwrap_or_default();
+        Metrics {
+            path,
+            version: version.to_string(),
+            started: SystemTime::now(),
+            session: Mutex::new(BTreeMap::new()),
+            history,
+        }
+    }
+
+    /// Record one call. `outcome` is the response size, or the error.
+    pub fn record(&self, method: &str, elapsed: Duration, request_bytes: usize, outcome: Result<usize, &BridgeError>) {
+        let Ok(mut session) = self.session.lock() else {
+            return;
+        };
+        session.entry(method.to_string()).or_default().record(
+            elapsed.as_secs_f64() * 1000.0,
+            request_bytes as u64,
+            outcome.map(|bytes| bytes as u64),
+        );
+    }
+
+    pub fn report(&self) -> MetricsReport {
+        let session = self.session.lock().map(|s| s.clone()).unwrap_or_default();
+        let mut releases: Vec<ReleaseMetrics> = self
+            .releases(&session)
+            .iter()
+            .map(|(version, methods)| ReleaseMetrics {
+                shell_version: version.clone(),
+                methods: report_all(methods),
+            })
+            .collect();
+        // Newest release first, comparing versions numerically.
+        releases.sort_by_cached_key(|r| {
+            let parts: Vec<u64> = r.shell_version.split('.').map(|p| p.parse().unwrap_or(0)).collect();
+            std::cmp::Reverse(parts)
+        });
+        MetricsReport {
+            shell_version: self.version.clone(),
+            since_ms: self.started.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            methods: report_all(&session),
+            releases,
+        }
+    }
+
+    /// Write the history with this session merged in. Saving again later
+    /// replaces what the earlier save added, so nothing is counted twice.
+    pub fn save(&self) -> Result<(), String> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let session = self.session.lock().map_err(|e| e.to_string())?.clone();
+        let history = History {
+            releases: self.releases(&session),
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+        }
+        let text = serde_json::to_string(&history).map_err(|e| e.to_string())?;
+        std::fs::write(path, text).map_err(|e| format!("write {}: {}", path.display(), e))
+    }
+
+    /// The saved history with `session` added to this version's totals.
+    fn releases(&self, session: &MethodStats) -> BTreeMap<String, MethodStats> {
+        let mut releases = self.history.releases.clone();
+        let current = releases.entry(self.version.clone()).or_default();
+        for (method, stats) in session {
+            current.entry(method.clone()).or_default().merge(stats);
+        }
+        releases.retain(|_, methods| !methods.is_empty());
+        releases
+    }
+}
+
+fn report_all(methods: &MethodStats) -> Vec<MethodMetrics> {
+    methods.iter().map(|(method, stats)| stats.report(method)).collect()
+}
@@ -0,0 +1,375 @@
+// Scripted stand-in for the Node bridge, for the command tests.
+//
+// `MockBridge` speaks the real wire protocol over an in-memory transport: it
+// sends the ready handshake (announcing every method in `protocol::ALL`),
+// answers each request with the next canned reply scripted for its method,
+// and records what the shell sent. Each request is answered from its own
+// task, so a delayed reply is overtaken by later ones just as it can be with
+// the Node bridge. JSON replies are pretty-printed, so every frame spans
+// lines; like the Node bridge, the mock switches to MessagePack when the shell
+// asks for it with `bridge.encoding`.
+//
+// Also here: an in-process keychain, since keyring's own mock credential does
+// not share secrets between `Entry` instances.
+
+use crate::bridge::PROTOCOL_VERSION;
+use crate::framing::{self, Encoding, Frame};
+use crate::protocol;
+use crate::transport::{Connector, MemoryTransport};
+use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+use serde_json::{json, Value};
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex, Once};
+use std::time::Duration;
+use tokio::io::{AsyncWriteExt, BufReader, DuplexStream, WriteHalf};
+
+type Writer = Arc<tokio::sync::Mutex<Output>>;
+
+/// One session's write half and the encoding it currently answers in.
+struct Output {
+    writer: WriteHalf<DuplexStream>,
+    encoding: Encoding,
+}
+
+/// How the mock answers one request.
+#[derive(Debug, Clone)]
+pub enum Reply {
+    Ok(Value),
+    Err { code: String, message: String },
+    /// Send these progress notifications, then the reply.
+    Progress(Vec<Value>, Box<Reply>),
+    /// Answer after a delay, letting later requests overtake this one.
+    After(Duration, Box<Reply>),
+    /// Never answer: for timeouts and cancellation.
+    Never,
+    /// Close the connection instead of answering, as if the bridge died.
+    Crash,
+}
+
+impl Reply {
+    pub fn ok(result: Value) -> Self {
+        Reply::Ok(result)
+    }
+
+    pub fn err(code: &str, message: &str) -> Self {
+        Reply::Err {
+            code: code.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn after(ms: u64, reply: Reply) -> Self {
+        Reply::After(Duration::from_millis(ms), Box::new(reply))
+    }
+}
+
+/// A request as the mock received it.
+#[derive(Debug, Clone)]
+pub struct Received {
+    pub id: String,
+    pub method: String,
+    pub params: Value,
+}
+
+#[derive(Default)]
+struct Script {
+    /// Canned replies per method, used in order; the last one repeats.
+    replies: HashMap<String, VecDeque<Reply>>,
+    received: Vec<Received>,
+    /// Request ids named by `bridge.cancel` notifications.
+    cancelled: Vec<String>,
+    connections: u32,
+    /// Refuse connections, as if the bridge could not be started.
+    unavailable: bool,
+    /// Offer only JSON in the handshake, like bridges before `bridge.encoding`.
+    json_only: bool,
+    /// Encoding the latest session was switched to, if any.
+    negotiated: Option<Encoding>,
+    /// Read requests but answer none, pings included, as if the event loop hung.
+    wedged: bool,
+    /// Accept connections but never send the handshake, like a bridge that
+    /// hangs while starting.
+    silent: bool,
+    pings: u32,
+}
+
+#[derive(Clone, Default)]
+pub struct MockBridge {
+    script: Arc<Mutex<Script>>,
+}
+
+impl MockBridge {
+    pub fn new() -> Self {
+        MockBridge::default()
+    }
+
+    /// Script the next reply to `method`.
+    pub fn on(&self, method: &str, reply: Reply) -> &Self {
+        self.lock().replies.entry(method.to_string()).or_default().push_back(reply);
+        self
+    }
+
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.lock().unavailable = unavailable;
+    }
+
+    pub fn set_json_only(&self, json_only: bool) {
+        self.lock().json_only = json_only;
+    }
+
+    pub fn set_wedged(&self, wedged: bool) {
+        self.lock().wedged = wedged;
+    }
+
+    pub fn set_silent(&self, silent: bool) {
+        self.lock().silent = silent;
+    }
+
+    /// How many `bridge.ping` requests have been answered.
+    pub fn pings(&self) -> u32 {
+        self.lock().pings
+    }
+
+    pub fn negotiated(&self) -> Option<Encoding> {
+        self.lock().negotiated
+    }
+
+    pub fn received(&self) -> Vec<Received> {
+        self.lock().received.clone()
+    }
+
+    /// Params of the most recent call to `method`.
+    pub fn params(&self, method: &str) -> Option<Value> {
+        self.lock()
+            .received
+            .iter()
+            .rev()
+            .find(|r| r.method == method)
+            .map(|r| r.params.clone())
+    }
+
+    pub fn cancelled(&self) -> Vec<String> {
+        self.lock().cancelled.clone()
+    }
+
+    /// How many times the shell has connected (i.e. started a bridge).
+    pub fn connections(&self) -> u32 {
+        self.lock().connections
+    }
+
+    /// Wait until a request with this id has arrived.
+    pub async fn wait_for(&self, id: &str) {
+        while !self.lock().received.iter().any(|r| r.id == id) {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    }
+
+    /// A connector that starts a fresh mock bridge session on every call.
+    pub fn connector(&self) -> Connector {
+        let mock = self.clone();
+        Arc::new(move || {
+            let mock = mock.clone();
+            Box::pin(async move {
+                if mock.lock().unavailable {
+                    return Err("Mock bridge is unavailable".into());
+                }
+                let (connection, peer) = MemoryTransport::pair();
+                tokio::spawn(mock.serve(peer));
+                Ok(connection)
+            })
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Script> {
+        self.script.lock().expect("mock script poisoned")
+    }
+
+    fn next_reply(&self, method: &str) -> Reply {
+        let mut script = self.lock();
+        match script.replies.get_mut(method) {
+            Some(queue) if queue.len() > 1 => queue.pop_front().expect("non-empty queue"),
+            Some(queue) if !queue.is_empty() => queue[0].clone(),
+            _ => Reply::err("UNKNOWN_METHOD", &format!("No reply scripted for {}", method)),
+        }
+    }
+
+    async fn serve(self, mut peer: DuplexStream) {
+        if self.lock().silent {
+            self.lock().connections += 1;
+            // Hold the connection open until the shell gives up on it.
+            let _ = tokio::io::copy(&mut peer, &mut tokio::io::sink()).await;
+            return;
+        }
+        let encodings = {
+            let mut script = self.lock();
+            script.connections += 1;
+            script.negotiated = None;
+            if script.json_only {
+                vec!["json"]
+            } else {
+                vec!["json", "msgpack"]
+            }
+        };
+        let (reader, writer) = tokio::io::split(peer);
+        let writer: Writer = Arc::new(tokio::sync::Mutex::new(Output {
+            writer,
+            encoding: Encoding::Json,
+        }));
+        let ready = json!({
+            "id": null,
+            "result": "bridge_ready",
+            "handshake": {
+                "protocolVersion": PROTOCOL_VERSION,
+                "bridgeVersion": "mock",
+                "methods": protocol::ALL,
+                "encodings": encodings,
+            },
+        });
+        send(&writer, ready).await;
+
+        let mut reader = BufReader::new(reader);
+        while let Ok(Some(frame)) = framing::read_frame(&mut reader, framing::DEFAULT_MAX_FRAME).await {
+            let Frame::Message { body, encoding } = frame else {
+                panic!("shell sent an oversized frame");
+            };
+            assert_eq!(encoding, Encoding::Json, "shell requests are always JSON");
+            let msg: Value = serde_json::from_slice(&body).expect("shell sent invalid JSON");
+            let method = msg["method"].as_str().unwrap_or_default().to_string();
+            let params = msg["params"].clone();
+            let Some(id) = msg["id"].as_str().map(str::to_string) else {
+                assert_eq!(method, "bridge.cancel", "unexpected notification: {}", msg);
+                if let Some(target) = params["id"].as_str() {
+                    self.lock().cancelled.push(target.to_string());
+                }
+                continue;
+            };
+
+            if self.lock().wedged {
+                continue;
+            }
+            if method == "bridge.ping" {
+                self.lock().pings += 1;
+                send(&writer, json!({ "id": id, "result": {} })).await;
+                continue;
+            }
+            if method == "bridge.encoding" {
+                let name = params["encoding"].as_str().unwrap_or_default();
+                let encoding = Encoding::from_name(name).expect("shell asked for a known encoding");
+                send(&writer, json!({ "id": id, "result": { "encoding": name } })).await;
+                writer.lock().await.encoding = encoding;
+                self.lock().negotiated = Some(encoding);
+                continue;
+            }
+            self.lock().received.push(Received {
+                id: id.clone(),
+                method: method.clone(),
+                params,
+            });
+            if method == "bridge.shutdown" {
+                send(&writer, json!({ "id": id, "result": { "drained": true } })).await;
+                let _ = writer.lock().await.writer.shutdown().await;
+                return;
+            }
+            match self.next_reply(&method) {
+                Reply::Crash => {
+                    let _ = writer.lock().await.writer.shutdown().await;
+                    return;
+                }
+                reply => {
+                    tokio::spawn(answer(Arc::clone(&writer), id, reply));
+                }
+            }
+        }
+    }
+}
+
+async fn answer(writer: Writer, id: String, mut reply: Reply) {
+    loop {
+        match reply {
+            Reply::Ok(result) => return send(&writer, json!({ "id": id, "result": result })).await,
+            Reply::Err { code, message } => {
+                let error = json!({ "code": code, "message": message, "data": null });
+                return send(&writer, json!({ "id": id, "error": error })).await;
+            }
+            Reply::Progress(updates, next) => {
+                for progress in updates {
+                    send(&writer, json!({ "id": id, "progress": progress })).await;
+                }
+                reply = *next;
+            }
+            Reply::After(delay, next) => {
+                tokio::time::sleep(delay).await;
+                reply = *next;
+            }
+            Reply::Never | Reply::Crash => return,
+        }
+    }
+}
+
+async fn send(writer: &Writer, message: Value) {
+    let mut output = writer.lock().await;
+    let body = match output.encoding {
+        Encoding::Json => serde_json::to_vec_pretty(&message).expect("serializable message"),
+        Encoding::MessagePack => rmp_serde::to_vec(&message).expect("serializable message"),
+    };
+    let frame = framing::frame(&body, output.encoding);
+    let _ = output.writer.write_all(&frame).await;
+}
+
+// ── Keychain ────────────────────────────────────────────────────
+
+static SECRETS: Mutex<BTreeMap<(String, String), Vec<u8>>> = Mutex::new(BTreeMap::new());
+
+/// Route `keyring::Entry` to an in-process store shared by every entry.
+pub fn use_memory_keychain() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| keyring::set_default_credential_builder(Box::new(MemoryKeychain)));
+}
+
+struct MemoryKeychain;
+
+struct MemoryCredential {
+    key: (String, String),
+}
+
+impl CredentialBuilderApi for MemoryKeychain {
+    fn build(&self, _target: Option<&str>, service: &str, user: &str) -> keyring::Result<Box<Credential>> {
+        Ok(Box::new(MemoryCredential {
+            key: (service.to_string(), user.to_string()),
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl CredentialApi for MemoryCredential {
+    fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+        SECRETS.lock().expect("keychain poisoned").insert(self.key.clone(), secret.to_vec());
+        Ok(())
+    }
+
+    fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+        SECRETS
+            .lock()
+            .expect("keychain poisoned")
+            .get(&self.key)
+            .cloned()
+            .ok_or(keyring::Error::NoEntry)
+    }
+
+    fn delete_credential(&self) -> keyring::Result<()> {
+        SECRETS
+            .lock()
+            .expect("keychain poisoned")
+            .remove(&self.key)
+            .map(|_| ())
+            .ok_or(keyring::Error::NoEntry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
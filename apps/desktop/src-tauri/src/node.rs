@@ -0,0 +1,251 @@
+// Node.js runtime discovery for the bridge.
+//
+// GUI apps rarely inherit the user's shell PATH (macOS launches them with a
+// minimal one), and most developers install Node through a version manager,
+// so a fixed list of system paths misses it. Candidates are collected in
+// priority order, each one is asked for `--version`, and the first that meets
+// `MIN_NODE_MAJOR` wins. Every candidate tried is kept, with the reason it was
+// rejected, so a failed launch can say exactly what was looked at.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Oldest Node major the bridge runs on (matches `engines.node` in package.json).
+pub const MIN_NODE_MAJOR: u32 = 18;
+
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+const NODE_EXE: &str = if cfg!(windows) { "node.exe" } else { "node" };
+
+/// One Node binary considered during discovery.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeCandidate {
+    pub path: String,
+    /// Where the candidate came from: env, setting, bundled, path, volta, asdf, mise, fnm, nvm or system.
+    pub source: String,
+    /// Output of `node --version`, when it ran.
+    pub version: Option<String>,
+    pub accepted: bool,
+    /// Why the candidate was not used; null for the accepted one.
+    pub rejected: Option<String>,
+}
+
+/// Outcome of a discovery run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeDiscovery {
+    /// The binary the bridge runs on, if any candidate qualified.
+    pub selected: Option<String>,
+    pub min_major: u32,
+    pub candidates: Vec<NodeCandidate>,
+}
+
+impl NodeDiscovery {
+    /// Multi-line explanation of why no usable Node was found.
+    pub fn failure_message(&self) -> String {
+        let mut message = format!(
+            "No usable Node.js runtime found (need v{} or newer). Install Node {} LTS or later, \
+             or set its path in Settings or OPENQUERY_NODE_PATH.",
+            self.min_major, self.min_major
+        );
+        if self.candidates.is_empty() {
+            message.push_str("\nNo node binary was found on PATH or in any known install location.");
+        }
+        for candidate in &self.candidates {
+            message.push_str(&format!(
+                "\n  {} ({}): {}",
+                candidate.path,
+                candidate.source,
+                candidate.rejected.as_deref().unwrap_or("ok")
+            ));
+        }
+        message
+    }
+}
+
+/// Find a Node runtime for the bridge. `setting` is the user's configured path;
+/// `bundled` the runtime shipped in the app resources, whose ABI matches the
+/// bundled native modules.
+pub async fn discover(setting: Option<&Path>, bundled: Option<&Path>) -> NodeDiscovery {
+    let mut discovery = NodeDiscovery {
+        selected: None,
+        min_major: MIN_NODE_MAJOR,
+        candidates: Vec::new(),
+    };
+
+    for (source, path) in candidates(setting, bundled) {
+        let mut candidate = NodeCandidate {
+            path: path.display().to_string(),
+            source: source.to_string(),
+            version: None,
+            accepted: false,
+            rejected: None,
+        };
+        match probe(&path).await {
+            Ok(version) => {
+                match parse_major(&version) {
+                    Some(major) if major >= MIN_NODE_MAJOR => candidate.accepted = true,
+                    Some(_) => {
+                        candidate.rejected = Some(format!("{} is older than v{}", version, MIN_NODE_MAJOR))
+                    }
+                    None => candidate.rejected = Some(format!("unrecognized version output {:?}", version)),
+                }
+                candidate.version = Some(version);
+            }
+            Err(reason) => candidate.rejected = Some(reason),
+        }
+
+        let accepted = candidate.accepted;
+        discovery.candidates.push(candidate);
+        if accepted {
+            discovery.selected = Some(path.display().to_string());
+            break;
+        }
+    }
+    discovery
+}
+
+/// Candidate binaries in priority order, without duplicates. Explicit choices
+/// (env var, setting) are listed even when missing so the diagnostics show the
+/// typo; everything else only when the file exists.
+fn candidates(setting: Option<&Path>, bundled: Option<&Path>) -> Vec<(&'static str, PathBuf)> {
+    let mut list: Vec<(&'static str, PathBuf)> = Vec::new();
+    if let Some(path) = std::env::var_os("OPENQUERY_NODE_PATH").filter(|p| !p.is_empty()) {
+        list.push(("env", PathBuf::from(path)));
+    }
+    if let Some(path) = setting {
+        list.push(("setting", path.to_path_buf()));
+    }
+    if let Some(path) = bundled {
+        list.push(("bundled", path.to_path_buf()));
+    }
+
+    let mut found: Vec<(&'static str, PathBuf)> = Vec::new();
+    if let Some(path) = std::env::var_os("PATH") {
+        for dir in std::env::split_paths(&path) {
+            found.push(("path", dir.join(NODE_EXE)));
+        }
+    }
+    found.extend(version_manager_candidates());
+    found.extend(system_candidates());
+    list.extend(found.into_iter().filter(|(_, path)| path.is_file()));
+
+    let mut seen = HashSet::new();
+    list.retain(|(_, path)| seen.insert(std::fs::canonicalize(path).unwrap_or_else(|_| path.clone())));
+    list
+}
+
+fn home_dir() -> Option<PathBuf> {
+    let var = if cfg!(windows) { "USERPROFILE" } else { "HOME" };
+    std::env::var_os(var).filter(|h| !h.is_empty()).map(PathBuf::from)
+}
+
+fn env_dir(var: &str) -> Option<PathBuf> {
+    std::env::var_os(var).filter(|v| !v.is_empty()).map(PathBuf::from)
+}
+
+/// Shims and default installs of the common version managers.
+fn version_manager_candidates() -> Vec<(&'static str, PathBuf)> {
+    let mut list = Vec::new();
+    let home = home_dir();
+    let under_home = |rel: &str| home.as_ref().map(|h| h.join(rel));
+
+    // Volta and asdf put a shim on a fixed path that dispatches per project.
+    if let Some(volta) = env_dir("VOLTA_HOME").or_else(|| under_home(".volta")) {
+        list.push(("volta", volta.join("bin").join(NODE_EXE)));
+    }
+    if let Some(asdf) = env_dir("ASDF_DATA_DIR").or_else(|| under_home(".asdf")) {
+        list.push(("asdf", asdf.join("shims").join(NODE_EXE)));
+    }
+    if let Some(mise) = env_dir("MISE_DATA_DIR").or_else(|| under_home(".local/share/mise")) {
+        list.push(("mise", mise.join("shims").join(NODE_EXE)));
+    }
+
+    // fnm keeps its default alias as a symlink to an install.
+    let fnm_dir = env_dir("FNM_DIR").or_else(|| {
+        if cfg!(windows) {
+            env_dir("APPDATA").map(|d| d.join("fnm"))
+        } else if cfg!(target_os = "macos") {
+            under_home("Library/Application Support/fnm")
+        } else {
+            under_home(".local/share/fnm")
+        }
+    });
+    if let Some(fnm) = fnm_dir {
+        let default = fnm.join("aliases").join("default");
+        list.push(("fnm", if cfg!(windows) { default.join(NODE_EXE) } else { default.join("bin").join(NODE_EXE) }));
+    }
+
+    // nvm has no shim; try every installed version, newest first.
+    if cfg!(windows) {
+        if let Some(link) = env_dir("NVM_SYMLINK") {
+            list.push(("nvm", link.join(NODE_EXE)));
+        }
+    } else if let Some(nvm) = env_dir("NVM_DIR").or_else(|| under_home(".nvm")) {
+        let mut versions: Vec<(Vec<u32>, PathBuf)> = std::fs::read_dir(nvm.join("versions").join("node"))
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let key = name
+                    .trim_start_matches('v')
+                    .split('.')
+                    .map(|part| part.parse().ok())
+                    .collect::<Option<Vec<u32>>>()?;
+                Some((key, entry.path().join("bin").join(NODE_EXE)))
+            })
+            .collect();
+        versions.sort_by(|a, b| b.0.cmp(&a.0));
+        list.extend(versions.into_iter().map(|(_, path)| ("nvm", path)));
+    }
+    list
+}
+
+/// Installer and package-manager locations outside any version manager.
+fn system_candidates() -> Vec<(&'static str, PathBuf)> {
+    if cfg!(windows) {
+        ["ProgramFiles", "ProgramFiles(x86)"]
+            .iter()
+            .filter_map(|var| env_dir(var))
+            .map(|dir| ("system", dir.join("nodejs").join(NODE_EXE)))
+            .collect()
+    } else {
+        ["/opt/homebrew/bin/node", "/usr/local/bin/node", "/usr/bin/node"]
+            .iter()
+            .map(|path| ("system", PathBuf::from(path)))
+            .collect()
+    }
+}
+
+/// Run `<path> --version` and return its trimmed output.
+async fn probe(path: &Path) -> Result<String, String> {
+    if !path.exists() {
+        return Err("does not exist".to_string());
+    }
+    let output = Command::new(path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(VERSION_TIMEOUT, output).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return Err(format!("failed to run: {}", e)),
+        Err(_) => return Err(format!("`--version` did not answer within {}s", VERSION_TIMEOUT.as_secs())),
+    };
+    if !output.status.success() {
+        return Err(format!("`--version` exited with {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Major version from `node --version` output such as `v20.11.1`.
+fn parse_major(version: &str) -> Option<u32> {
+    version.strip_prefix('v')?.split('.').next()?.parse().ok()
+}
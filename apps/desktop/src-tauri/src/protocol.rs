@@ -0,0 +1,607 @@
+// Typed params and results for every bridge method.
+//
+// Field names mirror the Node handlers in `bridge/handlers.ts` (camelCase,
+// except stored profiles, which keep their SQLite column names). Results are
+// decoded strictly: a field renamed or retyped on the Node side becomes a
+// protocol error naming the method and field rather than an `undefined` deep in
+// the UI. Values the shell only passes through (policy verdicts, EXPLAIN plans)
+// stay as `Value`.
+
+use crate::bridge::Response;
+use crate::error::BridgeError;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+
+/// A bridge method: its wire name and the shapes it sends and receives.
+pub trait Method {
+    const NAME: &'static str;
+    type Params: Serialize;
+    type Result: DeserializeOwned;
+}
+
+macro_rules! methods {
+    ($($ty:ident => $name:literal ($params:ty) -> $result:ty;)*) => {
+        $(
+            pub struct $ty;
+
+            impl Method for $ty {
+                const NAME: &'static str = $name;
+                type Params = $params;
+                type Result = $result;
+            }
+        )*
+
+        /// Every method's wire name, in declaration order.
+        pub const ALL: &[&str] = &[$($name),*];
+    };
+}
+
+methods! {
+    ProfilesList => "profiles.list" (NoParams) -> Vec<ListedProfile>;
+    ProfilesAdd => "profiles.add" (NewProfile) -> Profile;
+    ProfilesRemove => "profiles.remove" (ByName) -> Removed;
+    ProfilesUse => "profiles.use" (ByName) -> Ack;
+    ProfilesTest => "profiles.test" (ProfileTest) -> ConnectionTest;
+    ProfilesGetActive => "profiles.getActive" (NoParams) -> ActiveProfile;
+    SchemaRefresh => "schema.refresh" (SchemaRefreshParams) -> SchemaRefreshSummary;
+    SchemaSearch => "schema.search" (SchemaSearchParams) -> Vec<SchemaMatch>;
+    SchemaTableDetail => "schema.tableDetail" (TableDetailParams) -> TableInfo;
+    SchemaGetSnapshot => "schema.getSnapshot" (NoParams) -> Option<SchemaSnapshot>;
+    AskDryRun => "ask.dryRun" (AskParams) -> AskResult;
+    AskRun => "ask.run" (AskParams) -> AskResult;
+    WorkspaceSql => "workspace.sql" (WorkspaceSqlParams) -> WorkspaceSqlResult;
+    HistoryList => "history.list" (HistoryListParams) -> Vec<HistoryListItem>;
+    HistoryShow => "history.show" (ById) -> HistoryDetail;
+    HistoryExportMd => "history.exportMd" (ById) -> String;
+    SettingsStatus => "settings.status" (NoParams) -> SettingsStatusResult;
+    SettingsTestOpenAiKey => "settings.testOpenAiKey" (OpenAiKeyParams) -> KeyCheck;
+    DemoNoDockerStatus => "demo.noDockerStatus" (NoParams) -> DemoStatus;
+    DemoNoDockerPrepare => "demo.noDockerPrepare" (DemoPrepareParams) -> DemoReady;
+    DemoNoDockerReset => "demo.noDockerReset" (NoParams) -> DemoReady;
+    FixtureCheckDocker => "fixture.checkDocker" (NoParams) -> DockerCheck;
+    FixturePickPort => "fixture.pickPort" (PickPortParams) -> PickedPort;
+    FixtureUp => "fixture.up" (FixtureUpParams) -> FixtureUpResult;
+    FixtureDown => "fixture.down" (NoParams) -> Ack;
+    FixtureStatus => "fixture.status" (NoParams) -> FixtureStatusResult;
+    FixtureLogs => "fixture.logs" (FixtureLogsParams) -> FixtureLogLines;
+    ProfileUpdatePower => "profile.updatePower" (UpdatePowerParams) -> Ack;
+    ProfileGetPower => "profile.getPower" (ByName) -> PowerSettings;
+    WritePreview => "write.preview" (WriteParams) -> WritePreviewResult;
+    WriteExecute => "write.execute" (WriteParams) -> WriteExecution;
+}
+
+pub fn encode<M: Method>(params: &M::Params) -> Result<Value, BridgeError> {
+    serde_json::to_value(params)
+        .map_err(|e| BridgeError::Protocol(format!("{}: could not encode params: {}", M::NAME, e)))
+}
+
+/// Decode a bridge result from the response frame, reporting the path of the
+/// first field that does not match.
+pub fn decode<M: Method>(response: &Response) -> Result<M::Result, BridgeError> {
+    response.result::<M::Result>().map_err(|(path, message)| {
+        // serde reports a missing field at its parent; name the field itself.
+        let field = match message.strip_prefix("missing field `").and_then(|m| m.split('`').next()) {
+            Some(missing) if path == "." => missing.to_string(),
+            Some(missing) => format!("{}.{}", path, missing),
+            None => path,
+        };
+        BridgeError::Decode {
+            method: M::NAME.to_string(),
+            field,
+            message,
+        }
+    })
+}
+
+/// Returned by the `bridge_info` command: versions of both halves of the app.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellInfo {
+    pub shell_version: String,
+    pub protocol_version: u32,
+    pub bridge_version: String,
+    /// Bridge methods the running bridge supports.
+    pub methods: Vec<String>,
+    /// How the bridge encodes its responses: json or msgpack.
+    pub encoding: String,
+}
+
+/// `{}` for methods that take no params.
+#[derive(Debug, Default, Serialize)]
+pub struct NoParams {}
+
+// ── Profiles ────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct ByName {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ById {
+    pub id: String,
+}
+
+/// Sent by the UI as-is, so it uses the same snake_case keys as the stored profile.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewProfile {
+    pub name: String,
+    pub db_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub database: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default)]
+    pub ssl: bool,
+}
+
+/// A row of the `profiles` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    pub db_type: String,
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub database: Option<String>,
+    pub user: Option<String>,
+    pub ssl: u8,
+    pub created_at: String,
+    pub allow_write: u8,
+    pub allow_dangerous: u8,
+    pub power_confirm_phrase: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListedProfile {
+    #[serde(flatten)]
+    pub profile: Profile,
+    #[serde(rename = "_active")]
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Ack {
+    pub ok: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Removed {
+    pub ok: bool,
+    pub profile_id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ProfileTest {
+    pub name: String,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTest {
+    pub ok: bool,
+    pub error: Option<String>,
+    pub server_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveProfile {
+    pub name: Option<String>,
+}
+
+// ── Schema ──────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct SchemaRefreshParams {
+    pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchemaRefreshSummary {
+    pub tables: u32,
+    pub columns: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchemaSearchParams {
+    pub query: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaMatch {
+    pub schema: Option<String>,
+    pub table: String,
+    pub column: Option<String>,
+    pub data_type: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableDetailParams {
+    pub table: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaSnapshot {
+    pub tables: Vec<TableInfo>,
+    pub captured_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableInfo {
+    pub name: String,
+    pub schema: Option<String>,
+    pub columns: Vec<ColumnInfo>,
+    pub row_count_estimate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnInfo {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+    pub is_primary_key: bool,
+    pub default_value: Option<String>,
+}
+
+// ── Ask + workspace SQL ─────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AskParams {
+    pub question: String,
+    pub mode: String,
+    pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub open_ai_api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AskResult {
+    pub query_id: String,
+    pub plan: SqlPlan,
+    pub model: String,
+    pub retried: bool,
+    pub validation: Value,
+    pub explain_summary: Value,
+    pub explain_allowed: bool,
+    pub explain_warnings: Vec<String>,
+    pub explain_blockers: Vec<String>,
+    pub execution_result: Option<QueryResult>,
+    /// ok, blocked, error or dry-run.
+    pub status: String,
+    pub error: Option<String>,
+}
+
+/// The statement the model generated for a question.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqlPlan {
+    pub sql: String,
+    pub params: Vec<PlanParam>,
+    pub assumptions: Vec<String>,
+    pub safety_notes: Vec<String>,
+    pub confidence: f64,
+    pub referenced_entities: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanParam {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: Value,
+}
+
+/// Safe-mode thresholds the UI can override per run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SqlPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_rows_threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_cost_threshold: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub enforce_limit: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSqlParams {
+    pub sql: String,
+    pub mode: String,
+    pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<SqlPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_rows: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceSqlResult {
+    /// ok, blocked, error, explained, dry-run or requires-power.
+    pub status: String,
+    pub classification: Value,
+    pub validation: Value,
+    pub rewritten_sql: String,
+    pub explain_summary: Value,
+    pub explain_warnings: Vec<String>,
+    pub explain_blockers: Vec<String>,
+    pub error: Option<String>,
+    pub execution_result: Option<QueryResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Map<String, Value>>,
+    pub row_count: u64,
+    pub truncated: bool,
+    pub exec_ms: f64,
+    /// Set when rows were sent as progress batches and `rows` is empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows_streamed: Option<bool>,
+}
+
+// ── History ─────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct HistoryListParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryListItem {
+    pub id: String,
+    pub question: String,
+    pub asked_at: String,
+    pub status: Option<String>,
+    pub exec_ms: Option<f64>,
+    pub row_count: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryDetail {
+    pub query: HistoryQuery,
+    pub generation: Option<HistoryGeneration>,
+    pub run: Option<HistoryRun>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryQuery {
+    pub id: String,
+    pub profile_id: String,
+    pub question: String,
+    pub mode: String,
+    pub dialect: String,
+    pub asked_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryGeneration {
+    pub id: String,
+    pub model: String,
+    pub generated_sql: String,
+    pub params: Vec<Value>,
+    pub confidence: f64,
+    pub assumptions: Vec<String>,
+    pub safety_notes: Vec<String>,
+    pub generated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryRun {
+    pub id: String,
+    pub rewritten_sql: String,
+    pub params: Vec<Value>,
+    pub explain_summary: Value,
+    pub exec_ms: f64,
+    pub row_count: u64,
+    pub truncated: bool,
+    pub status: String,
+    pub error_text: Option<String>,
+    pub ran_at: String,
+}
+
+// ── Settings ────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsStatusResult {
+    pub open_ai_key_set: bool,
+    pub model: String,
+    pub app_version: String,
+    pub defaults: SafeModeDefaults,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeModeDefaults {
+    pub max_rows_threshold: f64,
+    pub max_cost_threshold: f64,
+    pub enforce_limit: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenAiKeyParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyCheck {
+    pub ok: bool,
+    pub message: String,
+}
+
+// ── Setup + fixture ─────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemoStatus {
+    pub ready: bool,
+    pub db_path: String,
+    pub active: bool,
+    pub profile_name: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DemoPrepareParams {
+    pub reset: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DemoReady {
+    pub ready: bool,
+    pub db_path: String,
+    pub profile_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DockerCheck {
+    pub installed: bool,
+    pub daemon_running: bool,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PickPortParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferred_ports: Option<Vec<u16>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickedPort {
+    pub port: u16,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FixtureUpParams {
+    pub port: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FixtureUpResult {
+    pub running: bool,
+    pub port: u16,
+    pub profile_name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureStatusResult {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FixtureLogsParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tail: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FixtureLogLines {
+    pub lines: Vec<String>,
+}
+
+// ── POWER mode ──────────────────────────────────────────────────
+
+#[derive(Debug, Serialize)]
+pub struct UpdatePowerParams {
+    pub name: String,
+    pub settings: PowerSettingsUpdate,
+}
+
+/// Partial update: absent fields are left alone, `confirmPhrase: null` clears the phrase.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerSettingsUpdate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_write: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow_dangerous: Option<bool>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub confirm_phrase: Option<Option<String>>,
+}
+
+/// Keeps an explicit `null` distinct from a missing field.
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(d: D) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(d).map(Some)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerSettings {
+    pub allow_write: bool,
+    pub allow_dangerous: bool,
+    pub confirm_phrase: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WriteParams {
+    pub sql: String,
+    pub params: Vec<Value>,
+    pub password: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WritePreviewResult {
+    pub classification: Value,
+    pub kind: String,
+    pub impacted_tables: Vec<String>,
+    pub has_where_clause: bool,
+    pub summary: String,
+    pub estimated_rows_affected: Option<f64>,
+    pub explain_plan: Value,
+    pub warnings: Vec<String>,
+    pub requires_confirmation: bool,
+    pub confirmation_phrase: String,
+    pub requires_dangerous_confirmation: bool,
+    pub dangerous_confirmation_phrase: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WriteExecution {
+    pub success: bool,
+    pub rows_affected: u64,
+    pub exec_ms: f64,
+    pub error: Option<String>,
+}
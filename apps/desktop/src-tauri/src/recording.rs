@@ -0,0 +1,361 @@
+// Record-and-replay of bridge traffic, so a bug seen against someone's database
+// can be reproduced without it.
+//
+// With OPENQUERY_BRIDGE_RECORD=<file>, `Bridge` writes every handshake, call
+// and progress notification to that file as JSON lines. Secrets never reach
+// it: `password`, `openAiApiKey` and `apiKey` anywhere in the params become
+// "[redacted]", and every cell of every `rows` array (results and streamed row
+// batches alike) is replaced by a short SHA-256 of its value, so equal values
+// stay equal and NULLs stay NULL. Heartbeats and the other `bridge.*` protocol
+// calls are left out.
+//
+// With OPENQUERY_BRIDGE_REPLAY=<file>, no bridge is started: an in-process
+// stand-in announces the recorded handshake and answers each method with its
+// recorded replies in order, progress included. A call that failed on the
+// shell's side (timeout, cancel) is never answered, so it fails the same way;
+// a method with no replies left gets a NOT_RECORDED error.
+
+use crate::bridge::{BridgeInfo, Notification, NotificationSink, Response};
+use crate::error::BridgeError;
+use crate::framing::{self, Encoding, Frame};
+use crate::transport::{Connector, MemoryTransport};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncWriteExt, DuplexStream, WriteHalf};
+
+/// Record bridge traffic to this file.
+pub const RECORD_ENV: &str = "OPENQUERY_BRIDGE_RECORD";
+/// Replay a recorded file instead of starting a bridge.
+pub const REPLAY_ENV: &str = "OPENQUERY_BRIDGE_REPLAY";
+
+/// Param names whose values are never written.
+const SECRET_KEYS: &[&str] = &["password", "openAiApiKey", "apiKey"];
+const REDACTED: &str = "[redacted]";
+
+/// One line of a recording.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Entry {
+    /// A bridge (re)started and sent this handshake.
+    #[serde(rename_all = "camelCase")]
+    Session { shell_version: String, bridge: BridgeInfo },
+    /// Progress for the call with this id, before its reply.
+    Progress { id: String, data: Value },
+    /// A finished call. `error` holds a bridge error as the bridge sent it,
+    /// or the shell-side failure (`local`) the call ended with instead.
+    #[serde(rename_all = "camelCase")]
+    Call {
+        id: String,
+        method: String,
+        params: Value,
+        duration_ms: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result: Option<Value>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<Value>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        local: bool,
+    },
+}
+
+// ── Recording ──────────────────────────────────────────────────
+
+pub struct Recorder {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    /// Start recording if OPENQUERY_BRIDGE_RECORD names a file. A file that
+    /// cannot be created is reported and recording stays off.
+    pub fn from_env() -> Option<Arc<Self>> {
+        let path = std::env::var_os(RECORD_ENV).filter(|p| !p.is_empty())?;
+        match Self::create(Path::new(&path)) {
+            Ok(recorder) => {
+                tracing::warn!(path = %recorder.path.display(), "Recording bridge traffic; secrets and cell values are redacted");
+                Some(Arc::new(recorder))
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Bridge traffic is not being recorded");
+                None
+            }
+        }
+    }
+
+    /// Record to `path`, replacing whatever is there.
+    pub fn create(path: &Path) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| format!("create {}: {}", path.display(), e))?;
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn session(&self, info: &BridgeInfo) {
+        self.write(&Entry::Session {
+            shell_version: env!("CARGO_PKG_VERSION").to_string(),
+            bridge: info.clone(),
+        });
+    }
+
+    /// Record a finished call. `params` are redacted here.
+    pub fn call(
+        &self,
+        id: &str,
+        method: &str,
+        params: &Value,
+        elapsed: Duration,
+        outcome: Result<&Response, &BridgeError>,
+    ) {
+        if method.starts_with("bridge.") {
+            return;
+        }
+        let mut params = params.clone();
+        redact_secrets(&mut params);
+        let (result, error, local) = match outcome {
+            Ok(response) => {
+                let mut result = response.result::<Value>().unwrap_or(Value::Null);
+                hash_rows(&mut result);
+                (Some(result), None, false)
+            }
+            Err(BridgeError::Remote { code, message, data }) => {
+                let error = json!({ "code": code, "message": message, "data": data });
+                (None, Some(error), false)
+            }
+            Err(e) => (None, Some(json!({ "code": e.code(), "message": e.to_string() })), true),
+        };
+        self.write(&Entry::Call {
+            id: id.to_string(),
+            method: method.to_string(),
+            params,
+            duration_ms: elapsed.as_secs_f64() * 1000.0,
+            result,
+            error,
+            local,
+        });
+    }
+
+    /// `notify`, with every progress notification recorded on the way through.
+    pub fn tap(self: &Arc<Self>, notify: NotificationSink) -> NotificationSink {
+        let recorder = Arc::clone(self);
+        Arc::new(move |notification| {
+            if let Notification::Progress { request_id, data } = &notification {
+                let mut data = data.clone();
+                hash_rows(&mut data);
+                recorder.write(&Entry::Progress {
+                    id: request_id.clone(),
+                    data,
+                });
+            }
+            notify(notification)
+        })
+    }
+
+    fn write(&self, entry: &Entry) {
+        let Ok(mut line) = serde_json::to_vec(entry) else {
+            return;
+        };
+        line.push(b'\n');
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+        if let Err(e) = file.write_all(&line) {
+            tracing::warn!(path = %self.path.display(), error = %e, "Failed to write bridge recording");
+        }
+    }
+}
+
+/// Replace the value of every secret-named key, at any depth.
+pub fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if SECRET_KEYS.contains(&key.as_str()) && !v.is_null() {
+                    *v = Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(v);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// Hash every cell of every `rows` array, at any depth. Rows may be objects
+/// keyed by column or plain arrays.
+pub fn hash_rows(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                match v {
+                    Value::Array(rows) if key == "rows" => {
+                        for row in rows {
+                            match row {
+                                Value::Object(cells) => cells.values_mut().for_each(hash_cell),
+                                Value::Array(cells) => cells.iter_mut().for_each(hash_cell),
+                                cell => hash_cell(cell),
+                            }
+                        }
+                    }
+                    v => hash_rows(v),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(hash_rows),
+        _ => {}
+    }
+}
+
+fn hash_cell(cell: &mut Value) {
+    if cell.is_null() {
+        return;
+    }
+    let digest = Sha256::digest(cell.to_string().as_bytes());
+    let hex: String = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+    *cell = Value::String(format!("sha256:{}", hex));
+}
+
+// ── Replay ─────────────────────────────────────────────────────
+
+/// A recorded reply, with the progress sent before it.
+struct Recorded {
+    progress: Vec<Value>,
+    result: Option<Value>,
+    error: Option<Value>,
+    local: bool,
+}
+
+struct Replay {
+    info: BridgeInfo,
+    /// Replies per method, in recorded order, shared by every connection.
+    calls: Mutex<HashMap<String, VecDeque<Recorded>>>,
+}
+
+/// A connector that answers from the recording at `path`. Restarts carry on
+/// where the previous connection left off.
+pub fn replay_connector(path: &Path) -> Result<Connector, String> {
+    let replay = Arc::new(load(path)?);
+    tracing::warn!(
+        path = %path.display(),
+        bridge_version = %replay.info.bridge_version,
+        "Replaying recorded bridge traffic; no bridge is started"
+    );
+    Ok(Arc::new(move || {
+        let replay = Arc::clone(&replay);
+        Box::pin(async move {
+            let (connection, peer) = MemoryTransport::pair();
+            tokio::spawn(replay.serve(peer));
+            Ok(connection)
+        })
+    }))
+}
+
+fn load(path: &Path) -> Result<Replay, String> {
+    let file = File::open(path).map_err(|e| format!("open {}: {}", path.display(), e))?;
+    let mut info = None;
+    let mut progress: HashMap<String, Vec<Value>> = HashMap::new();
+    let mut calls: HashMap<String, VecDeque<Recorded>> = HashMap::new();
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("read {}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry =
+            serde_json::from_str(&line).map_err(|e| format!("{} line {}: {}", path.display(), n + 1, e))?;
+        match entry {
+            Entry::Session { bridge, .. } => {
+                info.get_or_insert(bridge);
+            }
+            Entry::Progress { id, data } => progress.entry(id).or_default().push(data),
+            Entry::Call {
+                id,
+                method,
+                result,
+                error,
+                local,
+                ..
+            } => calls.entry(method).or_default().push_back(Recorded {
+                progress: progress.remove(&id).unwrap_or_default(),
+                result,
+                error,
+                local,
+            }),
+        }
+    }
+    let Some(mut info) = info else {
+        return Err(format!("{} has no recorded bridge session", path.display()));
+    };
+    // Recordings are JSON; there is nothing to gain from switching encodings.
+    info.encodings = vec![Encoding::Json.name().to_string()];
+    Ok(Replay {
+        info,
+        calls: Mutex::new(calls),
+    })
+}
+
+type Writer = Arc<tokio::sync::Mutex<WriteHalf<DuplexStream>>>;
+
+impl Replay {
+    async fn serve(self: Arc<Self>, peer: DuplexStream) {
+        let (reader, writer) = tokio::io::split(peer);
+        let writer: Writer = Arc::new(tokio::sync::Mutex::new(writer));
+        let ready = json!({ "id": null, "result": "bridge_ready", "handshake": self.info });
+        send(&writer, &ready).await;
+
+        let mut reader = tokio::io::BufReader::new(reader);
+        while let Ok(Some(Frame::Message { body, .. })) =
+            framing::read_frame(&mut reader, framing::DEFAULT_MAX_FRAME).await
+        {
+            let Ok(msg) = serde_json::from_slice::<Value>(&body) else {
+                continue;
+            };
+            // Notifications (`bridge.cancel`) need no answer.
+            let Some(id) = msg["id"].as_str() else {
+                continue;
+            };
+            let method = msg["method"].as_str().unwrap_or_default();
+            match method {
+                "bridge.ping" => send(&writer, &json!({ "id": id, "result": {} })).await,
+                "bridge.shutdown" => {
+                    send(&writer, &json!({ "id": id, "result": { "drained": true } })).await;
+                    let _ = writer.lock().await.shutdown().await;
+                    return;
+                }
+                _ => self.answer(&writer, id, method).await,
+            }
+        }
+    }
+
+    async fn answer(&self, writer: &Writer, id: &str, method: &str) {
+        let next = self.calls.lock().ok().and_then(|mut calls| calls.get_mut(method)?.pop_front());
+        let Some(recorded) = next else {
+            let error = json!({
+                "code": "NOT_RECORDED",
+                "message": format!("The recording has no reply left for {}", method),
+                "data": null,
+            });
+            return send(writer, &json!({ "id": id, "error": error })).await;
+        };
+        for progress in recorded.progress {
+            send(writer, &json!({ "id": id, "progress": progress })).await;
+        }
+        match (recorded.result, recorded.error) {
+            _ if recorded.local => {}
+            (_, Some(error)) => send(writer, &json!({ "id": id, "error": error })).await,
+            (result, None) => send(writer, &json!({ "id": id, "result": result })).await,
+        }
+    }
+}
+
+async fn send(writer: &Writer, message: &Value) {
+    let body = serde_json::to_vec(message).expect("serializable message");
+    let _ = writer.lock().await.write_all(&framing::frame(&body, Encoding::Json)).await;
+}
@@ -0,0 +1,103 @@
+// Development hot reload: watches the bridge's build output for changes.
+//
+// Debug builds only. `tsc --watch` (or `pnpm build:bridge`) rewrites files in
+// `apps/desktop/bridge/dist`; the supervisor restarts the bridge once they
+// settle, so the webview and its state survive an edit to a handler. Polling
+// the modification times keeps this free of a watcher dependency, and a dist
+// directory of a few dozen files costs nothing to scan twice a second.
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::debug;
+
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Path, modification time and size of every file under the watched directory.
+type Snapshot = Vec<(PathBuf, Option<SystemTime>, u64)>;
+
+pub struct DistWatcher {
+    dir: PathBuf,
+    last: Snapshot,
+}
+
+impl DistWatcher {
+    /// Watch `dir`, taking its current contents as the baseline.
+    pub fn new(dir: &Path) -> Self {
+        DistWatcher {
+            dir: dir.to_path_buf(),
+            last: snapshot(dir),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Wait until files under the directory change and then stay unchanged
+    /// for a whole poll, so a build still writing files is not caught halfway.
+    /// Returns the paths that differ from the previous baseline.
+    pub async fn changed(&mut self) -> Vec<PathBuf> {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let mut current = snapshot(&self.dir);
+            if current == self.last {
+                continue;
+            }
+            loop {
+                tokio::time::sleep(POLL_INTERVAL).await;
+                let next = snapshot(&self.dir);
+                if next == current {
+                    break;
+                }
+                current = next;
+            }
+            let changed = diff(&self.last, &current);
+            debug!(dir = %self.dir.display(), files = changed.len(), "Bridge build output changed");
+            self.last = current;
+            if !changed.is_empty() {
+                return changed;
+            }
+        }
+    }
+}
+
+/// Files added, removed or modified between two snapshots.
+fn diff(before: &Snapshot, after: &Snapshot) -> Vec<PathBuf> {
+    let mut changed: Vec<PathBuf> = after
+        .iter()
+        .filter(|entry| !before.contains(entry))
+        .map(|(path, ..)| path.clone())
+        .collect();
+    for (path, ..) in before {
+        if !after.iter().any(|(p, ..)| p == path) {
+            changed.push(path.clone());
+        }
+    }
+    changed.sort();
+    changed.dedup();
+    changed
+}
+
+fn snapshot(dir: &Path) -> Snapshot {
+    let mut files = Vec::new();
+    collect(dir, &mut files);
+    files.sort();
+    files
+}
+
+fn collect(dir: &Path, files: &mut Snapshot) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else { continue };
+        if meta.is_dir() {
+            if entry.file_name() != "node_modules" {
+                collect(&path, files);
+            }
+        } else {
+            files.push((path, meta.modified().ok(), meta.len()));
+        }
+    }
+}
@@ -0,0 +1,356 @@
+// Confinement of the spawned Node bridge on Linux.
+//
+// The bridge needs far less than the user's full privileges: its own files,
+// Node and the system libraries read-only, and read-write only the OpenQuery
+// data directory (`~/.openquery`, which holds the local store and the demo
+// SQLite files) and the temp directory. So before exec the child:
+//
+// - gets a Landlock ruleset allowing just that, and nothing else on the
+//   filesystem (kernels without Landlock skip this step);
+// - has its data segment capped at 4 GiB, its open files at 4096, and core
+//   dumps off, since a dump would hold passwords;
+// - goes into its own process group, so stopping the bridge also stops what it
+//   started (e.g. the docker CLI) and a Ctrl-C in `tauri dev` does not reach it
+//   before the shell has shut it down.
+//
+// Network access is left alone: Landlock can only filter TCP by port, and the
+// database hosts and ports are the user's to choose. The `sandboxBridge` shell
+// setting turns all of this off, e.g. for a Node installed somewhere unusual.
+
+use crate::diagnostics::SandboxStatus;
+use std::path::{Path, PathBuf};
+#[cfg(target_os = "linux")]
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::fd::{AsRawFd, OwnedFd};
+
+/// Cap on the bridge's data segment. V8 reserves address space far beyond its
+/// heap, so the address-space limit would stop Node from starting at all.
+const MEMORY_LIMIT_BYTES: u64 = 4 * 1024 * 1024 * 1024;
+const OPEN_FILES_LIMIT: u64 = 4096;
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(all(target_os = "linux", not(target_env = "gnu")))]
+type Resource = libc::c_int;
+
+/// What the bridge may touch.
+#[derive(Debug, Clone, Default)]
+pub struct SandboxPolicy {
+    pub read_only: Vec<PathBuf>,
+    pub read_write: Vec<PathBuf>,
+}
+
+impl SandboxPolicy {
+    /// The policy for running `script` on `node`.
+    pub fn for_bridge(script: &Path, node: &Path) -> Self {
+        let mut read_only: Vec<PathBuf> = ["/usr", "/lib", "/lib64", "/bin", "/etc", "/proc", "/sys", "/dev"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        // Node's install, both as found (a version manager shim such as
+        // ~/.volta/bin/node) and as resolved; each prefix is the dir above bin.
+        let resolved = std::fs::canonicalize(node).ok();
+        for binary in [Some(node), resolved.as_deref()].into_iter().flatten() {
+            if let Some(prefix) = binary.parent().and_then(Path::parent) {
+                read_only.push(prefix.to_path_buf());
+            }
+        }
+        // The bridge, and any node_modules it resolves packages from up the
+        // tree (in development, the workspace root).
+        let script_dir = script.parent().unwrap_or(script);
+        let modules_root = script_dir.ancestors().filter(|dir| dir.join("node_modules").is_dir()).last();
+        read_only.push(modules_root.unwrap_or(script_dir).to_path_buf());
+
+        let mut read_write = vec![std::env::temp_dir(), PathBuf::from("/dev/null")];
+        if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+            // Created up front: a rule can only be added for a path that exists,
+            // and the bridge creates this on first run.
+            let data_dir = home.join(".openquery");
+            let _ = std::fs::create_dir_all(&data_dir);
+            read_write.push(data_dir);
+            // The Docker fixture runs the docker CLI, which keeps its config here.
+            read_write.push(home.join(".docker"));
+        }
+        SandboxPolicy { read_only, read_write }
+    }
+}
+
+/// A prepared sandbox, applied to the bridge's `Command` at spawn.
+pub struct Sandbox {
+    status: SandboxStatus,
+    /// Landlock ruleset to enforce in the child; None without Landlock.
+    #[cfg(target_os = "linux")]
+    ruleset: Option<OwnedFd>,
+    /// Resource limits to set in the child; None when sandboxing is off.
+    #[cfg(target_os = "linux")]
+    limits: Option<Limits>,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Clone, Copy)]
+struct Limits {
+    data: libc::rlim_t,
+    open_files: libc::rlim_t,
+}
+
+impl Sandbox {
+    /// No confinement, and why.
+    pub fn off(reason: &str) -> Self {
+        Sandbox {
+            status: SandboxStatus {
+                active: false,
+                reason: Some(reason.to_string()),
+                landlock_abi: None,
+                read_only: Vec::new(),
+                read_write: Vec::new(),
+                memory_limit_mb: None,
+                open_files_limit: None,
+                process_group: false,
+            },
+            #[cfg(target_os = "linux")]
+            ruleset: None,
+            #[cfg(target_os = "linux")]
+            limits: None,
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn prepare(_policy: &SandboxPolicy) -> Self {
+        Self::off("Sandboxing is only available on Linux")
+    }
+
+    /// Build the Landlock ruleset for `policy` and work out the limits. Paths
+    /// that do not exist are skipped; a kernel without Landlock leaves only the
+    /// limits and the process group.
+    #[cfg(target_os = "linux")]
+    pub fn prepare(policy: &SandboxPolicy) -> Self {
+        let (ruleset, landlock_abi, reason) = match landlock::abi() {
+            Some(abi) => match landlock::ruleset(abi, policy) {
+                Ok(fd) => (Some(fd), Some(abi), None),
+                Err(e) => (None, Some(abi), Some(format!("Landlock ruleset could not be built: {}", e))),
+            },
+            None => (None, None, Some("This kernel does not support Landlock; only resource limits apply".to_string())),
+        };
+        let limits = Limits {
+            data: capped(libc::RLIMIT_DATA, MEMORY_LIMIT_BYTES),
+            open_files: capped(libc::RLIMIT_NOFILE, OPEN_FILES_LIMIT),
+        };
+        let existing = |paths: &[PathBuf]| -> Vec<String> {
+            paths.iter().filter(|p| p.exists()).map(|p| p.display().to_string()).collect()
+        };
+        Sandbox {
+            status: SandboxStatus {
+                active: ruleset.is_some(),
+                reason,
+                landlock_abi,
+                read_only: existing(&policy.read_only),
+                read_write: existing(&policy.read_write),
+                memory_limit_mb: Some(limits.data / (1024 * 1024)),
+                open_files_limit: Some(limits.open_files),
+                process_group: true,
+            },
+            ruleset,
+            limits: Some(limits),
+        }
+    }
+
+    pub fn status(&self) -> &SandboxStatus {
+        &self.status
+    }
+
+    /// Whether the child leads its own process group, so signals go to the group.
+    pub fn process_group(&self) -> bool {
+        self.status.process_group
+    }
+
+    /// Have `command` confine its child before exec.
+    #[cfg(target_os = "linux")]
+    pub fn apply(&self, command: &mut tokio::process::Command) {
+        let Some(limits) = self.limits else {
+            return;
+        };
+        let ruleset = self.ruleset.as_ref().map(AsRawFd::as_raw_fd);
+        command.process_group(0);
+        // SAFETY: the hook runs in the forked child before exec and makes only
+        // async-signal-safe system calls, with no allocation.
+        unsafe {
+            command.pre_exec(move || {
+                set_limit(libc::RLIMIT_DATA, limits.data)?;
+                set_limit(libc::RLIMIT_NOFILE, limits.open_files)?;
+                set_limit(libc::RLIMIT_CORE, 0)?;
+                if let Some(fd) = ruleset {
+                    landlock::restrict_self(fd)?;
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply(&self, _command: &mut tokio::process::Command) {}
+}
+
+/// `wanted`, or the current hard limit if that is lower: only root may raise it.
+#[cfg(target_os = "linux")]
+fn capped(resource: Resource, wanted: u64) -> libc::rlim_t {
+    let mut current = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit writes into the struct we pass.
+    let ok = unsafe { libc::getrlimit(resource, &mut current) } == 0;
+    if ok && current.rlim_max != libc::RLIM_INFINITY {
+        wanted.min(current.rlim_max)
+    } else {
+        wanted
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn set_limit(resource: Resource, value: libc::rlim_t) -> io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: value,
+        rlim_max: value,
+    };
+    // SAFETY: setrlimit only reads the struct we pass.
+    match unsafe { libc::setrlimit(resource, &limit) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// The Landlock system calls, which libc has numbers for but no wrappers.
+#[cfg(target_os = "linux")]
+mod landlock {
+    use super::SandboxPolicy;
+    use std::ffi::CString;
+    use std::io;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    const CREATE_RULESET_VERSION: u32 = 1;
+    const RULE_PATH_BENEATH: libc::c_int = 1;
+
+    const EXECUTE: u64 = 1 << 0;
+    const WRITE_FILE: u64 = 1 << 1;
+    const READ_FILE: u64 = 1 << 2;
+    const READ_DIR: u64 = 1 << 3;
+    /// Everything up to MAKE_SYM: what ABI 1 handles.
+    const ABI_1: u64 = (1 << 13) - 1;
+    const REFER: u64 = 1 << 13;
+    const TRUNCATE: u64 = 1 << 14;
+    /// The only rights a rule on a file (not a directory) may grant.
+    const FILE_RIGHTS: u64 = EXECUTE | WRITE_FILE | READ_FILE | TRUNCATE;
+
+    #[repr(C)]
+    struct RulesetAttr {
+        handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    struct PathBeneathAttr {
+        allowed_access: u64,
+        parent_fd: i32,
+    }
+
+    /// The Landlock ABI version, or None if the kernel lacks it or it is off.
+    pub fn abi() -> Option<u32> {
+        // SAFETY: the version query takes no attribute.
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                CREATE_RULESET_VERSION,
+            )
+        };
+        (version > 0).then_some(version as u32)
+    }
+
+    /// A ruleset handling every filesystem right `abi` knows (bar device
+    /// ioctls), granting read-only and read-write access beneath the policy's
+    /// paths.
+    pub fn ruleset(abi: u32, policy: &SandboxPolicy) -> io::Result<OwnedFd> {
+        let handled = match abi {
+            1 => ABI_1,
+            2 => ABI_1 | REFER,
+            _ => ABI_1 | REFER | TRUNCATE,
+        };
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        // SAFETY: `attr` outlives the call and its size is passed along.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0u32,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: the kernel just handed us this descriptor (close-on-exec).
+        let ruleset = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+        let read_only = EXECUTE | READ_FILE | READ_DIR;
+        for path in &policy.read_only {
+            allow(&ruleset, path, read_only & handled)?;
+        }
+        for path in &policy.read_write {
+            allow(&ruleset, path, handled)?;
+        }
+        Ok(ruleset)
+    }
+
+    /// Grant `access` beneath `path`; paths that do not exist are skipped.
+    fn allow(ruleset: &OwnedFd, path: &Path, access: u64) -> io::Result<()> {
+        let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+            return Ok(());
+        };
+        // SAFETY: `c_path` is a valid C string for the duration of the call.
+        let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+        if fd < 0 {
+            return Ok(());
+        }
+        // SAFETY: `open` just returned this descriptor.
+        let parent = unsafe { OwnedFd::from_raw_fd(fd) };
+        let access = if path.is_dir() { access } else { access & FILE_RIGHTS };
+        let rule = PathBeneathAttr {
+            allowed_access: access,
+            parent_fd: parent.as_raw_fd(),
+        };
+        // SAFETY: `rule` and both descriptors outlive the call.
+        let added = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_add_rule,
+                ruleset.as_raw_fd(),
+                RULE_PATH_BENEATH,
+                &rule as *const PathBeneathAttr,
+                0u32,
+            )
+        };
+        if added != 0 {
+            let e = io::Error::last_os_error();
+            return Err(io::Error::new(e.kind(), format!("{}: {}", path.display(), e)));
+        }
+        Ok(())
+    }
+
+    /// Enforce `ruleset` on the calling process. Runs in the child between
+    /// fork and exec, so it only makes system calls.
+    pub fn restrict_self(ruleset: i32) -> io::Result<()> {
+        // SAFETY: plain prctl and syscall with integer arguments.
+        unsafe {
+            if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0u32) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+}
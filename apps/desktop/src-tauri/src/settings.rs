@@ -0,0 +1,111 @@
+// Settings owned by the desktop shell rather than the bridge.
+//
+// These govern how the bridge itself is launched, so they cannot live in the
+// bridge's own store. Kept as JSON in the app config directory; a missing or
+// unreadable file means defaults.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const FILE_NAME: &str = "shell-settings.json";
+
+/// Shell-owned settings, stored in shell-settings.json.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ShellSettings {
+    /// Node binary to run the bridge with, tried before PATH and version managers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_path: Option<String>,
+    /// Trace level for the shell log: error, warn, info, debug or trace.
+    /// OPENQUERY_LOG overrides it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_level: Option<String>,
+    /// Keep bridge call metrics across sessions, per OpenQuery version.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persist_metrics: Option<bool>,
+    /// Environment variables passed to the bridge besides the built-in list:
+    /// names, or prefixes ending in `*`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bridge_env: Option<Vec<String>>,
+    /// Confine the bridge on Linux (Landlock, resource limits, own process
+    /// group). On unless set to false.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sandbox_bridge: Option<bool>,
+}
+
+impl ShellSettings {
+    pub fn node_path(&self) -> Option<PathBuf> {
+        self.node_path
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    }
+
+    pub fn log_level(&self) -> Option<&str> {
+        self.log_level.as_deref().map(str::trim).filter(|l| !l.is_empty())
+    }
+
+    pub fn persist_metrics(&self) -> bool {
+        self.persist_metrics.unwrap_or(false)
+    }
+
+    pub fn sandbox_bridge(&self) -> bool {
+        self.sandbox_bridge.unwrap_or(true)
+    }
+
+    pub fn bridge_env(&self) -> Vec<String> {
+        self.bridge_env
+            .iter()
+            .flatten()
+            .map(|entry| entry.trim())
+            .filter(|entry| !entry.is_empty())
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+pub struct SettingsStore {
+    path: Option<PathBuf>,
+    current: Mutex<ShellSettings>,
+}
+
+impl SettingsStore {
+    /// Load from `<config_dir>/shell-settings.json`. Without a config dir the
+    /// settings still work for this session but are not persisted.
+    pub fn load(config_dir: Option<&Path>) -> Self {
+        let path = config_dir.map(|dir| dir.join(FILE_NAME));
+        let current = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|text| match serde_json::from_str(&text) {
+                Ok(settings) => Some(settings),
+                Err(e) => {
+                    tracing::warn!(file = FILE_NAME, error = %e, "Ignoring unreadable shell settings");
+                    None
+                }
+            })
+            .unwrap_or_default();
+        SettingsStore {
+            path,
+            current: Mutex::new(current),
+        }
+    }
+
+    pub fn get(&self) -> ShellSettings {
+        self.current.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    pub fn set(&self, next: ShellSettings) -> Result<(), String> {
+        if let Some(path) = &self.path {
+            if let Some(dir) = path.parent() {
+                std::fs::create_dir_all(dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+            }
+            let text = serde_json::to_string_pretty(&next).map_err(|e| e.to_string())?;
+            std::fs::write(path, text).map_err(|e| format!("write {}: {}", path.display(), e))?;
+        }
+        *self.current.lock().map_err(|e| e.to_string())? = next;
+        Ok(())
+    }
+}
@@ -0,0 +1,608 @@
+// Bridge supervisor — owns the running bridge and restarts it when it crashes.
+//
+// Calls made while a restart is in progress fail fast instead of blocking.
+// Too many crashes inside the restart window and the supervisor gives up until
+// the app is relaunched. `shutdown` stops the bridge for good.
+//
+// Each running bridge also gets a heartbeat task that pings it every
+// `bridge::HEARTBEAT_INTERVAL` and reports when it stops (and starts again)
+// answering. An unresponsive bridge is reported, not restarted: it may only be
+// stuck on one long synchronous job.
+//
+// Debug builds also watch the spawned bridge's build output and reload it when
+// it changes (see `reload.rs`): calls in flight are allowed to finish, new
+// ones fail fast as during a restart, and the fresh bridge is handshaken
+// before `Reloaded` is reported.
+
+use crate::bridge::{self, Bridge, BridgeInfo, BridgeLocation, Liveness, NotificationSink, Response, SpawnError};
+use crate::error::BridgeError;
+use crate::diagnostics::SandboxStatus;
+use crate::environment::ChildEnv;
+use crate::framing::{Encoding, Wire};
+use crate::metrics::Metrics;
+use crate::recording::Recorder;
+use crate::sandbox::{Sandbox, SandboxPolicy};
+use crate::settings::ShellSettings;
+use crate::node::{self, NodeDiscovery};
+use crate::protocol::{self, Method};
+#[cfg(debug_assertions)]
+use crate::reload::DistWatcher;
+use crate::transport::{ChildOptions, Connector, LineSink};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::path::Path;
+#[cfg(debug_assertions)]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+
+/// Lifecycle notifications forwarded to the frontend as `bridge://status` events.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum BridgeEvent {
+    Crashed { reason: String },
+    Restarting { attempt: u32, delay_ms: u64 },
+    Restarted { attempt: u32 },
+    GaveUp { reason: String, failures: u32 },
+    /// Pings have gone unanswered `missed` times in a row.
+    Unresponsive { missed: u32, last_seen_ms: Option<u64> },
+    /// An unresponsive bridge answered a ping again.
+    Responsive { latency_ms: f64 },
+    /// The launch started with the app finished, with the bridge running or
+    /// with why it is not.
+    Launched { running: bool, error: Option<String> },
+    /// The bridge was restarted on a change to its build output (debug
+    /// builds); sent on `bridge://reloaded` rather than `bridge://status`.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    Reloaded { changed: Vec<String>, duration_ms: f64 },
+}
+
+pub type EventSink = Arc<dyn Fn(BridgeEvent) + Send + Sync>;
+
+/// How long a reload waits for calls in flight before stopping the old bridge.
+#[cfg_attr(not(debug_assertions), allow(dead_code))]
+const RELOAD_DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How aggressively a crashed bridge is respawned.
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// Crashes tolerated within `window` before giving up.
+    pub max_restarts: u32,
+    pub window: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        RestartPolicy {
+            max_restarts: 5,
+            window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// Defaults, with `OPENQUERY_BRIDGE_MAX_RESTARTS` overriding the crash budget.
+    pub fn from_env() -> Self {
+        let mut policy = RestartPolicy::default();
+        if let Some(max) = std::env::var("OPENQUERY_BRIDGE_MAX_RESTARTS")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+        {
+            policy.max_restarts = max;
+        }
+        policy
+    }
+
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |d| d.min(self.max_backoff))
+    }
+}
+
+/// How each bridge instance is obtained.
+pub enum BridgeSource {
+    /// Spawn the bridge script on a discovered Node runtime (the normal case).
+    Node(BridgeLocation),
+    /// Connect to a bridge started elsewhere: a socket, or the test mock.
+    Connect(Connector),
+}
+
+/// Why the first bridge could not be started, with the Node candidates tried.
+#[derive(Debug)]
+pub struct StartError {
+    pub reason: String,
+    pub node: Option<NodeDiscovery>,
+}
+
+impl std::fmt::Display for StartError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.reason)
+    }
+}
+
+impl std::error::Error for StartError {}
+
+struct Inner {
+    current: Mutex<Option<Arc<Bridge>>>,
+    /// Each bridge's exit hook reports its crash reason here; one task handles restarts.
+    crash_tx: mpsc::UnboundedSender<String>,
+    crashes: Mutex<VecDeque<Instant>>,
+    gave_up: Mutex<Option<String>>,
+    /// Set by `shutdown`; no restarts after this.
+    shutting_down: AtomicBool,
+    /// Set while a reload swaps the bridge out.
+    reloading: AtomicBool,
+    source: BridgeSource,
+    /// Read on every (re)spawn: the user's Node binary, tried first, the
+    /// extra environment variables the bridge is allowed, and whether it is
+    /// sandboxed.
+    settings: Mutex<ShellSettings>,
+    /// Result of the most recent Node discovery.
+    discovery: Mutex<Option<NodeDiscovery>>,
+    /// Names of the variables the most recent spawn passed to the bridge.
+    env_names: Mutex<Option<Vec<String>>>,
+    /// How the most recent spawn confined the bridge.
+    sandbox: Mutex<Option<SandboxStatus>>,
+    policy: RestartPolicy,
+    /// Frame size limit and preferred encoding, handed to every bridge.
+    wire: Wire,
+    sink: EventSink,
+    notify: NotificationSink,
+    /// Where a spawned bridge's stderr goes.
+    stderr: LineSink,
+    /// Call metrics, kept across restarts.
+    metrics: Arc<Metrics>,
+    /// Records every bridge's traffic into one file, when recording is on.
+    recorder: Option<Arc<Recorder>>,
+}
+
+pub struct BridgeSupervisor {
+    inner: Arc<Inner>,
+}
+
+impl BridgeSupervisor {
+    /// Start the first bridge. Later crashes are handled in the background.
+    /// `notify`, `stderr`, `metrics` and `recorder` are handed to every bridge,
+    /// including restarted ones.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn start(
+        source: BridgeSource,
+        settings: ShellSettings,
+        policy: RestartPolicy,
+        wire: Wire,
+        sink: EventSink,
+        notify: NotificationSink,
+        stderr: LineSink,
+        metrics: Arc<Metrics>,
+        recorder: Option<Arc<Recorder>>,
+    ) -> Result<Self, StartError> {
+        let (crash_tx, crash_rx) = mpsc::unbounded_channel();
+        let inner = Arc::new(Inner {
+            current: Mutex::new(None),
+            crash_tx,
+            crashes: Mutex::new(VecDeque::new()),
+            gave_up: Mutex::new(None),
+            shutting_down: AtomicBool::new(false),
+            reloading: AtomicBool::new(false),
+            source,
+            settings: Mutex::new(settings),
+            discovery: Mutex::new(None),
+            env_names: Mutex::new(None),
+            sandbox: Mutex::new(None),
+            policy,
+            wire,
+            sink,
+            notify,
+            stderr,
+            metrics,
+            recorder,
+        });
+        let bridge = inner.spawn_bridge().await.map_err(|e| StartError {
+            reason: e.to_string(),
+            node: inner.discovery.lock().ok().and_then(|d| d.clone()),
+        })?;
+        inner.install(bridge);
+        tokio::spawn(Inner::watch(Arc::downgrade(&inner), crash_rx));
+        #[cfg(debug_assertions)]
+        if let Some(dir) = inner.watched_dir() {
+            tokio::spawn(Inner::watch_build(Arc::downgrade(&inner), DistWatcher::new(&dir)));
+        }
+        Ok(BridgeSupervisor { inner })
+    }
+
+    /// Call a typed bridge method and decode its result. A caller-chosen
+    /// `request_id` lets the UI cancel the call.
+    pub async fn request<M: Method>(
+        &self,
+        params: &M::Params,
+        request_id: Option<String>,
+    ) -> Result<M::Result, BridgeError> {
+        let response = self.call_with(M::NAME, protocol::encode::<M>(params)?, request_id).await?;
+        protocol::decode::<M>(&response)
+    }
+
+    /// Forward a raw call to the running bridge, or fail if it is restarting or gone.
+    pub async fn call_with(
+        &self,
+        method: &str,
+        params: Value,
+        request_id: Option<String>,
+    ) -> Result<Response, BridgeError> {
+        let current = self.current()?;
+        current
+            .call_with(method, params, request_id, bridge::default_timeout(method))
+            .await
+    }
+
+    /// Cancel an in-flight call by request id. Returns false if it already finished.
+    pub async fn cancel(&self, request_id: &str) -> Result<bool, BridgeError> {
+        Ok(self.current()?.cancel(request_id).await)
+    }
+
+    /// Handshake details of the running bridge.
+    pub fn info(&self) -> Result<BridgeInfo, BridgeError> {
+        Ok(self.current()?.info().clone())
+    }
+
+    /// The encoding the running bridge answers in.
+    pub fn encoding(&self) -> Result<Encoding, BridgeError> {
+        Ok(self.current()?.encoding())
+    }
+
+    /// Heartbeat state of the running bridge.
+    pub fn liveness(&self) -> Result<Liveness, BridgeError> {
+        Ok(self.current()?.liveness())
+    }
+
+    /// Shut the bridge down gracefully (see `Bridge::shutdown`) and stop
+    /// supervising it. Calls made afterwards fail with `notStarted`.
+    pub async fn shutdown(&self) {
+        self.inner.shutting_down.store(true, Ordering::SeqCst);
+        let current = self.inner.current.lock().ok().and_then(|mut c| c.take());
+        if let Some(bridge) = current {
+            bridge.shutdown().await;
+        }
+    }
+
+    /// Replace the bridge with a fresh one once its calls in flight finish;
+    /// `changed` lists the files that prompted it. The build watcher reloads
+    /// through `Inner` directly; this is for tests.
+    pub async fn reload(&self, changed: Vec<String>) {
+        self.inner.reload(changed).await;
+    }
+
+    /// Script the bridge runs from; None when it is not spawned by the shell.
+    pub fn location(&self) -> Option<&BridgeLocation> {
+        match &self.inner.source {
+            BridgeSource::Node(location) => Some(location),
+            BridgeSource::Connect(_) => None,
+        }
+    }
+
+    /// Candidates tried the last time a bridge was spawned.
+    pub fn node_discovery(&self) -> Option<NodeDiscovery> {
+        self.inner.discovery.lock().ok().and_then(|d| d.clone())
+    }
+
+    /// Environment variable names the running bridge was spawned with; None
+    /// when the shell did not spawn it.
+    pub fn env_names(&self) -> Option<Vec<String>> {
+        self.inner.env_names.lock().ok().and_then(|n| n.clone())
+    }
+
+    /// How the running bridge is confined; None when the shell did not spawn it.
+    pub fn sandbox_status(&self) -> Option<SandboxStatus> {
+        self.inner.sandbox.lock().ok().and_then(|s| s.clone())
+    }
+
+    /// Change the user's Node path, bridge environment and sandboxing; takes
+    /// effect the next time the bridge is spawned.
+    pub fn set_settings(&self, settings: ShellSettings) {
+        if let Ok(mut current) = self.inner.settings.lock() {
+            *current = settings;
+        }
+    }
+
+    fn current(&self) -> Result<Arc<Bridge>, BridgeError> {
+        let bridge = self
+            .inner
+            .current
+            .lock()
+            .map_err(|e| BridgeError::Transport(e.to_string()))?
+            .clone();
+        bridge.ok_or_else(|| BridgeError::NotStarted(self.inner.unavailable_reason()))
+    }
+}
+
+impl Inner {
+    /// Make `bridge` the current one and start its heartbeat.
+    fn install(&self, bridge: Bridge) {
+        let bridge = Arc::new(bridge);
+        tokio::spawn(Self::heartbeat(Arc::downgrade(&bridge), Arc::clone(&self.sink)));
+        if let Ok(mut current) = self.current.lock() {
+            *current = Some(bridge);
+        }
+    }
+
+    /// Ping `bridge` until it is gone, reporting when it stops and resumes
+    /// answering.
+    async fn heartbeat(bridge: Weak<Bridge>, sink: EventSink) {
+        let mut unresponsive = false;
+        loop {
+            tokio::time::sleep(bridge::HEARTBEAT_INTERVAL).await;
+            let Some(current) = bridge.upgrade() else { break };
+            match current.ping().await {
+                Ok(latency) if unresponsive => {
+                    unresponsive = false;
+                    info!(latency_ms = latency.as_secs_f64() * 1000.0, "Bridge is answering again");
+                    sink(BridgeEvent::Responsive {
+                        latency_ms: latency.as_secs_f64() * 1000.0,
+                    });
+                }
+                Ok(_) => {}
+                Err(BridgeError::Timeout { .. }) => {
+                    let liveness = current.liveness();
+                    if liveness.unresponsive() && !unresponsive {
+                        unresponsive = true;
+                        warn!(missed = liveness.missed, "Bridge is not answering pings");
+                        sink(BridgeEvent::Unresponsive {
+                            missed: liveness.missed,
+                            last_seen_ms: liveness.last_seen.map(|t| t.elapsed().as_millis() as u64),
+                        });
+                    }
+                }
+                // Closed or shutting down; the crash handling takes it from here.
+                Err(_) => break,
+            }
+        }
+    }
+
+    async fn spawn_bridge(&self) -> Result<Bridge, SpawnError> {
+        let crash_tx = self.crash_tx.clone();
+        let on_exit: bridge::ExitHook = Box::new(move |reason| {
+            let _ = crash_tx.send(reason);
+        });
+        match &self.source {
+            BridgeSource::Node(location) => {
+                let node = self.discover_node(location).await?;
+                let child = self.child_options(location, Path::new(&node));
+                let (notify, stderr, metrics) =
+                    (Arc::clone(&self.notify), Arc::clone(&self.stderr), Arc::clone(&self.metrics));
+                let recorder = self.recorder.clone();
+                Bridge::spawn(Path::new(&node), location, &child, self.wire, on_exit, notify, stderr, metrics, recorder).await
+            }
+            BridgeSource::Connect(connect) => {
+                let (notify, metrics) = (Arc::clone(&self.notify), Arc::clone(&self.metrics));
+                let recorder = self.recorder.clone();
+                Bridge::connect(connect().await?, self.wire, on_exit, notify, metrics, recorder).await
+            }
+        }
+    }
+
+    /// Discover Node afresh (it may have been upgraded or removed since the
+    /// last spawn) and return the binary to run the bridge on.
+    async fn discover_node(&self, location: &BridgeLocation) -> Result<String, SpawnError> {
+        let setting = self.settings.lock().ok().and_then(|s| s.node_path());
+        let discovery = node::discover(setting.as_deref(), location.bundled_node.as_deref()).await;
+        for candidate in &discovery.candidates {
+            info!(
+                path = %candidate.path,
+                source = %candidate.source,
+                version = candidate.version.as_deref(),
+                result = candidate.rejected.as_deref().unwrap_or("selected"),
+                "Node candidate"
+            );
+        }
+        let selected = discovery.selected.clone();
+        let failure = discovery.failure_message();
+        if let Ok(mut last) = self.discovery.lock() {
+            *last = Some(discovery);
+        }
+        selected.ok_or_else(|| failure.into())
+    }
+
+    /// The allow-listed environment and the sandbox for running the bridge on
+    /// `node`, remembered for diagnostics.
+    fn child_options(&self, location: &BridgeLocation, node: &Path) -> ChildOptions {
+        let (extra, sandboxed) = self
+            .settings
+            .lock()
+            .map(|s| (s.bridge_env(), s.sandbox_bridge()))
+            .unwrap_or_default();
+        let env = ChildEnv::from_current(&extra);
+        let names = env.names();
+        info!(variables = %names.join(","), "Bridge environment");
+        let sandbox = match sandboxed {
+            true => Sandbox::prepare(&SandboxPolicy::for_bridge(&location.script, node)),
+            false => Sandbox::off("Turned off in settings"),
+        };
+        let status = sandbox.status();
+        match &status.reason {
+            None => info!(landlock_abi = status.landlock_abi, "Bridge sandboxed"),
+            Some(reason) => warn!(%reason, process_group = status.process_group, "Bridge is not sandboxed"),
+        }
+        if let (Ok(mut last_env), Ok(mut last_sandbox)) = (self.env_names.lock(), self.sandbox.lock()) {
+            *last_env = Some(names);
+            *last_sandbox = Some(status.clone());
+        }
+        ChildOptions { env, sandbox }
+    }
+
+    /// Supervisor task: restarts the bridge each time one reports a crash.
+    async fn watch(inner: Weak<Inner>, mut crash_rx: mpsc::UnboundedReceiver<String>) {
+        while let Some(reason) = crash_rx.recv().await {
+            let Some(inner) = inner.upgrade() else { break };
+            inner.restart(reason).await;
+        }
+    }
+
+    /// Directory of the bridge script, watched for rebuilds in debug builds.
+    #[cfg(debug_assertions)]
+    fn watched_dir(&self) -> Option<PathBuf> {
+        match &self.source {
+            BridgeSource::Node(location) => location.script.parent().map(Path::to_path_buf),
+            BridgeSource::Connect(_) => None,
+        }
+    }
+
+    /// Watcher task: reloads the bridge each time its build output settles
+    /// after a change.
+    #[cfg(debug_assertions)]
+    async fn watch_build(inner: Weak<Inner>, mut watcher: DistWatcher) {
+        info!(dir = %watcher.dir().display(), "Watching the bridge build for changes");
+        loop {
+            let changed = watcher.changed().await;
+            let Some(inner) = inner.upgrade() else { break };
+            if inner.shutting_down.load(Ordering::SeqCst) {
+                break;
+            }
+            let changed = changed.iter().map(|p| p.display().to_string()).collect();
+            inner.reload(changed).await;
+        }
+    }
+
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    async fn reload(&self, changed: Vec<String>) {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return;
+        }
+        let started = tokio::time::Instant::now();
+        info!(files = changed.len(), "Reloading the bridge");
+        self.reloading.store(true, Ordering::SeqCst);
+        let old = self.current.lock().ok().and_then(|mut c| c.take());
+        if let Some(bridge) = old {
+            Self::drain(&bridge).await;
+            bridge.shutdown().await;
+        }
+        // A reload is a fresh start: earlier crashes no longer count against it.
+        if let Ok(mut crashes) = self.crashes.lock() {
+            crashes.clear();
+        }
+        if let Ok(mut gave_up) = self.gave_up.lock() {
+            *gave_up = None;
+        }
+        let spawned = self.spawn_bridge().await;
+        self.reloading.store(false, Ordering::SeqCst);
+
+        match spawned {
+            Ok(bridge) if self.shutting_down.load(Ordering::SeqCst) => bridge.shutdown().await,
+            Ok(bridge) => {
+                self.install(bridge);
+                let duration_ms = started.elapsed().as_secs_f64() * 1000.0;
+                info!(duration_ms, "Bridge reloaded");
+                (self.sink)(BridgeEvent::Reloaded { changed, duration_ms });
+            }
+            // Most likely a half-finished edit; handed to the crash watcher so
+            // restarts have one owner and this task goes back to watching. The
+            // next rebuild reloads again even after the supervisor gives up.
+            Err(e) => {
+                warn!(error = %e, "Bridge reload failed");
+                let _ = self.crash_tx.send(e.to_string());
+            }
+        }
+    }
+
+    /// Wait for `bridge`'s calls in flight to finish, up to `RELOAD_DRAIN_TIMEOUT`.
+    #[cfg_attr(not(debug_assertions), allow(dead_code))]
+    async fn drain(bridge: &Bridge) {
+        let deadline = tokio::time::Instant::now() + RELOAD_DRAIN_TIMEOUT;
+        loop {
+            let in_flight = bridge.liveness().in_flight;
+            if in_flight == 0 {
+                return;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                warn!(in_flight, "Reloading with calls still in flight");
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+
+    fn unavailable_reason(&self) -> String {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return "Bridge has been shut down.".to_string();
+        }
+        if self.reloading.load(Ordering::SeqCst) {
+            return "Bridge is reloading after a change to its build. Try again in a moment.".to_string();
+        }
+        match self.gave_up.lock().ok().and_then(|g| g.clone()) {
+            Some(reason) => format!("Bridge stopped after repeated crashes: {}", reason),
+            None => "Bridge crashed and is restarting. Try again in a moment.".to_string(),
+        }
+    }
+
+    async fn restart(&self, mut reason: String) {
+        if self.shutting_down.load(Ordering::SeqCst) {
+            return;
+        }
+        let dead = self.current.lock().ok().and_then(|mut c| c.take());
+        drop(dead);
+        (self.sink)(BridgeEvent::Crashed { reason: reason.clone() });
+
+        loop {
+            let attempt = self.record_crash();
+            if attempt > self.policy.max_restarts {
+                error!(crashes = attempt, %reason, "Giving up on the bridge");
+                if let Ok(mut gave_up) = self.gave_up.lock() {
+                    *gave_up = Some(reason.clone());
+                }
+                (self.sink)(BridgeEvent::GaveUp { reason, failures: attempt });
+                return;
+            }
+
+            let delay = self.policy.backoff(attempt);
+            warn!(attempt, delay_ms = delay.as_millis() as u64, %reason, "Restarting the bridge");
+            (self.sink)(BridgeEvent::Restarting {
+                attempt,
+                delay_ms: delay.as_millis() as u64,
+            });
+            tokio::time::sleep(delay).await;
+            if self.shutting_down.load(Ordering::SeqCst) {
+                return;
+            }
+
+            match self.spawn_bridge().await {
+                Ok(bridge) if self.shutting_down.load(Ordering::SeqCst) => {
+                    bridge.shutdown().await;
+                    return;
+                }
+                Ok(bridge) => {
+                    self.install(bridge);
+                    (self.sink)(BridgeEvent::Restarted { attempt });
+                    return;
+                }
+                Err(e) => {
+                    warn!(attempt, error = %e, "Bridge restart failed");
+                    reason = e.to_string();
+                }
+            }
+        }
+    }
+
+    /// Record a crash and return how many fall inside the restart window.
+    fn record_crash(&self) -> u32 {
+        let Ok(mut crashes) = self.crashes.lock() else {
+            return u32::MAX;
+        };
+        let now = Instant::now();
+        crashes.push_back(now);
+        while crashes
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > self.policy.window)
+        {
+            crashes.pop_front();
+        }
+        crashes.len() as u32
+    }
+}
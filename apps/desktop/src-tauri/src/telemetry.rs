@@ -0,0 +1,120 @@
+// Structured tracing for the shell.
+//
+// Spans and events from the bridge client, supervisor, keychain and command
+// layer go to two places: readable lines on stderr (for `tauri dev`) and JSON
+// lines in the `shell` log (`shell.jsonl` in the app log directory, see
+// `crate::logs`), so a slow call or a failure can be looked into after the
+// fact. The level comes from OPENQUERY_LOG if it is set, else the `logLevel`
+// shell setting, else info; a changed setting applies immediately.
+//
+// Request params are never recorded: they carry passwords and API keys. A
+// bridge call is recorded by method, id, duration, payload sizes and outcome.
+
+use crate::logs::{LogSource, LogStore};
+use std::io;
+use std::str::FromStr;
+use std::sync::Arc;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Layer, Registry};
+
+/// Overrides the `logLevel` setting: error, warn, info, debug, trace or off.
+pub const LEVEL_ENV: &str = "OPENQUERY_LOG";
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+
+/// Handle on the installed subscriber's level.
+pub struct Telemetry {
+    level: reload::Handle<LevelFilter, Registry>,
+    /// Set when OPENQUERY_LOG chose the level; the setting is then ignored.
+    from_env: bool,
+}
+
+impl Telemetry {
+    /// Install the global subscriber, at the level OPENQUERY_LOG or `setting` names.
+    pub fn init(logs: &Arc<LogStore>, setting: Option<&str>) -> Self {
+        let env = std::env::var(LEVEL_ENV).ok().filter(|v| !v.trim().is_empty());
+        let from_env = env.is_some();
+        let level = env
+            .as_deref()
+            .or(setting)
+            .map(|name| {
+                parse_level(name).unwrap_or_else(|e| {
+                    eprintln!("[telemetry] {}; using {}", e, DEFAULT_LEVEL);
+                    DEFAULT_LEVEL
+                })
+            })
+            .unwrap_or(DEFAULT_LEVEL);
+
+        let (filter, handle) = reload::Layer::new(level);
+        let installed = tracing_subscriber::registry()
+            .with(filter)
+            .with(fmt::layer().with_writer(io::stderr))
+            .with(json_layer(logs))
+            .try_init();
+        if let Err(e) = installed {
+            eprintln!("[telemetry] Tracing is already set up: {}", e);
+        }
+        Telemetry {
+            level: handle,
+            from_env,
+        }
+    }
+
+    /// Apply a changed `logLevel` setting, unless OPENQUERY_LOG overrides it.
+    pub fn set_level(&self, setting: Option<&str>) -> Result<(), String> {
+        let level = setting.map(parse_level).transpose()?.unwrap_or(DEFAULT_LEVEL);
+        if self.from_env {
+            return Ok(());
+        }
+        self.level.reload(level).map_err(|e| e.to_string())?;
+        tracing::info!(%level, "Log level changed");
+        Ok(())
+    }
+}
+
+pub fn parse_level(name: &str) -> Result<LevelFilter, String> {
+    LevelFilter::from_str(name.trim()).map_err(|_| {
+        format!("Unknown log level {:?}: expected error, warn, info, debug, trace or off", name)
+    })
+}
+
+/// JSON-lines records, with the fields of the span they happened in, written
+/// to the `shell` log.
+pub fn json_layer<S>(logs: &Arc<LogStore>) -> impl Layer<S>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    fmt::layer()
+        .json()
+        .with_current_span(true)
+        .with_span_list(false)
+        .with_writer(ShellLog(Arc::clone(logs)))
+}
+
+/// `MakeWriter` into the `shell` log. The formatter writes each record in one
+/// call, so every write is a whole line.
+struct ShellLog(Arc<LogStore>);
+
+impl<'a> MakeWriter<'a> for ShellLog {
+    type Writer = &'a LogStore;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        &self.0
+    }
+}
+
+impl io::Write for &LogStore {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for line in String::from_utf8_lossy(buf).lines().filter(|l| !l.is_empty()) {
+            self.push(LogSource::Shell, line);
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,1475 @@
+// Command tests against the scripted mock bridge (see `mock.rs`).
+//
+// Each test runs on a current-thread runtime with a paused clock, so method
+// deadlines, restart backoff and delayed replies elapse instantly and in order.
+
+use super::*;
+use crate::bridge::{Incoming, Notification, Response};
+use crate::framing::{Encoding, Wire};
+use crate::mock::{self, MockBridge, Reply};
+use crate::supervisor::BridgeEvent;
+use openquery_desktop::sandbox;
+use serde_json::json;
+use std::future::Future;
+use std::time::Duration;
+use tauri::test::{mock_app, MockRuntime};
+use tauri::App;
+
+struct Harness {
+    app: App<MockRuntime>,
+    notifications: Arc<Mutex<Vec<Notification>>>,
+    events: Arc<Mutex<Vec<BridgeEvent>>>,
+}
+
+impl Harness {
+    /// An app whose bridge launches connect to `mock`. Nothing is launched yet.
+    fn new(mock: &MockBridge) -> Self {
+        Self::with_wire(mock, Wire::default())
+    }
+
+    fn with_wire(mock: &MockBridge, wire: Wire) -> Self {
+        Self::with_connector(mock.connector(), wire, None)
+    }
+
+    fn with_connector(
+        connector: transport::Connector,
+        wire: Wire,
+        recorder: Option<Arc<recording::Recorder>>,
+    ) -> Self {
+        let notifications = Arc::new(Mutex::new(Vec::new()));
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink_events = Arc::clone(&events);
+        let sink_notifications = Arc::clone(&notifications);
+        let app = mock_app();
+        app.manage(AppState {
+            bridge: Mutex::new(None),
+            bridge_error: Mutex::new(None),
+            starting: AtomicBool::new(false),
+            launcher: Launcher {
+                resource_dir: None,
+                connector: Some(connector),
+                wire,
+                sink: Arc::new(move |event| sink_events.lock().unwrap().push(event)),
+                notify: Arc::new(move |n| sink_notifications.lock().unwrap().push(n)),
+                recorder,
+                busy: tokio::sync::Mutex::new(()),
+            },
+            settings: Arc::new(settings::SettingsStore::load(None)),
+            logs: Arc::new(logs::LogStore::new(None)),
+            telemetry: None,
+            metrics: Arc::new(metrics::Metrics::new(None, "test")),
+            passwords: credentials::SessionPasswords::default(),
+            profiles: credentials::ProfileIndex::default(),
+        });
+        Harness {
+            app,
+            notifications,
+            events,
+        }
+    }
+
+    fn state(&self) -> State<'_, AppState> {
+        self.app.state::<AppState>()
+    }
+
+    async fn launch(&self) {
+        self.state().launch_bridge().await.expect("mock bridge launches");
+    }
+}
+
+fn run<F: Future<Output = ()>>(test: F) {
+    // Commands read the keychain; never let a test reach the real one.
+    mock::use_memory_keychain();
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .start_paused(true)
+        .build()
+        .expect("test runtime")
+        .block_on(test)
+}
+
+/// Let the mock catch up with everything the shell has sent.
+async fn settle() {
+    tokio::time::sleep(Duration::from_millis(10)).await;
+}
+
+fn error_json(error: &BridgeError) -> Value {
+    serde_json::to_value(error).expect("errors serialize")
+}
+
+fn profile_row(name: &str) -> Value {
+    json!({
+        "id": format!("id-{}", name),
+        "name": name,
+        "db_type": "postgres",
+        "host": "localhost",
+        "port": 5432,
+        "database": "app",
+        "user": "me",
+        "ssl": 0,
+        "created_at": "2026-01-01T00:00:00Z",
+        "allow_write": 0,
+        "allow_dangerous": 0,
+        "power_confirm_phrase": null,
+    })
+}
+
+fn query_result() -> Value {
+    json!({
+        "columns": ["id"],
+        "rows": [{ "id": 1 }],
+        "rowCount": 1,
+        "truncated": false,
+        "execMs": 2.5,
+    })
+}
+
+fn workspace_result(status: &str) -> Value {
+    json!({
+        "status": status,
+        "classification": { "kind": "read" },
+        "validation": { "ok": true },
+        "rewrittenSql": "SELECT id FROM t LIMIT 100",
+        "explainSummary": {},
+        "explainWarnings": [],
+        "explainBlockers": [],
+        "error": null,
+        "executionResult": query_result(),
+    })
+}
+
+fn ask_result(status: &str) -> Value {
+    json!({
+        "queryId": "q-1",
+        "plan": {
+            "sql": "SELECT count(*) FROM orders",
+            "params": [{ "name": "status", "type": "string", "value": "paid" }],
+            "assumptions": [],
+            "safetyNotes": [],
+            "confidence": 0.9,
+            "referencedEntities": [{ "table": "orders", "columns": [] }],
+        },
+        "model": "gpt-4o",
+        "retried": false,
+        "validation": { "allowed": true, "warnings": [], "reason": "" },
+        "explainSummary": null,
+        "explainAllowed": true,
+        "explainWarnings": [],
+        "explainBlockers": [],
+        "executionResult": if status == "ok" { query_result() } else { Value::Null },
+        "status": status,
+    })
+}
+
+fn settings_result() -> Value {
+    json!({
+        "openAiKeySet": true,
+        "model": "gpt-4o",
+        "appVersion": "0.5.1",
+        "defaults": { "maxRowsThreshold": 1000.0, "maxCostThreshold": 5000.0, "enforceLimit": true },
+    })
+}
+
+// ── Shell commands ──────────────────────────────────────────────
+
+#[test]
+fn bridge_info_reports_the_handshake() {
+    let mock = MockBridge::new();
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        let info = bridge_info(h.state()).unwrap();
+        assert_eq!(info.shell_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.protocol_version, bridge::PROTOCOL_VERSION);
+        assert_eq!(info.bridge_version, "mock");
+        assert_eq!(info.methods.len(), protocol::ALL.len());
+    });
+}
+
+/// `SELECT id FROM t` on profile `local`, with its password typed this session.
+fn run_sql(h: &Harness) -> impl Future<Output = Result<protocol::WorkspaceSqlResult, BridgeError>> + '_ {
+    h.state().passwords.set("local", "pw");
+    workspace_sql(
+        h.state(),
+        "SELECT id FROM t".to_string(),
+        "safe".to_string(),
+        None,
+        None,
+        Some("local".to_string()),
+        None,
+        None,
+    )
+}
+
+#[test]
+fn heartbeat_reports_a_wedged_bridge_and_its_recovery() {
+    let mock = MockBridge::new();
+    let h = Harness::new(&mock);
+    run(async {
+        assert_eq!(bridge_health(h.state()).status, "down");
+        h.launch().await;
+        tokio::time::sleep(bridge::HEARTBEAT_INTERVAL).await;
+        settle().await;
+        let health = bridge_health(h.state());
+        assert_eq!(health.status, "healthy");
+        assert_eq!(health.missed_pings, 0);
+        assert!(health.latency_ms.is_some());
+        assert_eq!(mock.pings(), 1);
+
+        // Pings time out, but one miss is not enough to call it unresponsive.
+        mock.set_wedged(true);
+        tokio::time::sleep(bridge::HEARTBEAT_INTERVAL + bridge::PING_TIMEOUT).await;
+        settle().await;
+        assert_eq!(bridge_health(h.state()).status, "healthy");
+        assert_eq!(bridge_health(h.state()).missed_pings, 1);
+
+        tokio::time::sleep(bridge::HEARTBEAT_INTERVAL + bridge::PING_TIMEOUT).await;
+        settle().await;
+        let health = bridge_health(h.state());
+        assert_eq!(health.status, "unresponsive");
+        assert!(health.last_seen_ms.unwrap() >= 15_000);
+        // Calls still fail only by their own deadline; the bridge is not restarted.
+        assert!(bridge_status(h.state()).running);
+        assert_eq!(mock.connections(), 1);
+
+        mock.set_wedged(false);
+        tokio::time::sleep(bridge::HEARTBEAT_INTERVAL + bridge::PING_TIMEOUT).await;
+        settle().await;
+        assert_eq!(bridge_health(h.state()).status, "healthy");
+
+        let events: Vec<Value> = h
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["status"], "unresponsive");
+        assert_eq!(events[0]["missed"], 2);
+        assert_eq!(events[1]["status"], "responsive");
+    });
+}
+
+#[test]
+fn pings_sent_alongside_a_request_are_not_counted_as_missed() {
+    let mock = MockBridge::new();
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        mock.set_wedged(true);
+        let bridge = h.state().bridge().unwrap();
+        // A request goes out just after each of the first two pings, as a
+        // large one still being read would hold them up.
+        for _ in 0..2 {
+            tokio::time::sleep(bridge::HEARTBEAT_INTERVAL + Duration::from_millis(1)).await;
+            let bridge = Arc::clone(&bridge);
+            tokio::spawn(async move { bridge.request::<protocol::ProfilesList>(&protocol::NoParams {}, None).await });
+            tokio::time::sleep(bridge::PING_TIMEOUT).await;
+        }
+        settle().await;
+        let health = bridge_health(h.state());
+        assert_eq!((health.status.as_str(), health.missed_pings), ("healthy", 0));
+
+        // With nothing else on the pipe, unanswered pings count again.
+        tokio::time::sleep(2 * (bridge::HEARTBEAT_INTERVAL + bridge::PING_TIMEOUT)).await;
+        settle().await;
+        assert_eq!(bridge_health(h.state()).status, "unresponsive");
+    });
+}
+
+#[test]
+fn responses_switch_to_msgpack_when_the_bridge_offers_it() {
+    let mock = MockBridge::new();
+    mock.on("workspace.sql", Reply::ok(workspace_result("ok")));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        assert_eq!(mock.negotiated(), Some(Encoding::MessagePack));
+        assert_eq!(bridge_info(h.state()).unwrap().encoding, "msgpack");
+        let result = run_sql(&h).await.unwrap();
+        assert_eq!(serde_json::to_value(result).unwrap(), workspace_result("ok"));
+    });
+}
+
+#[test]
+fn responses_stay_json_unless_both_sides_want_msgpack() {
+    let mock = MockBridge::new();
+    mock.on("workspace.sql", Reply::ok(workspace_result("ok")));
+    mock.set_json_only(true);
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        assert_eq!(mock.negotiated(), None);
+        assert_eq!(bridge_info(h.state()).unwrap().encoding, "json");
+        assert_eq!(run_sql(&h).await.unwrap().status, "ok");
+    });
+
+    let mock = MockBridge::new();
+    mock.on("workspace.sql", Reply::ok(workspace_result("ok")));
+    let h = Harness::with_wire(
+        &mock,
+        Wire {
+            encoding: Encoding::Json,
+            ..Wire::default()
+        },
+    );
+    run(async {
+        h.launch().await;
+        assert_eq!(mock.negotiated(), None);
+        assert_eq!(bridge_info(h.state()).unwrap().encoding, "json");
+        assert_eq!(run_sql(&h).await.unwrap().status, "ok");
+    });
+}
+
+#[test]
+fn commands_fail_with_not_started_until_a_retry_succeeds() {
+    let mock = MockBridge::new();
+    mock.set_unavailable(true);
+    mock.on("profiles.getActive", Reply::ok(json!({ "name": "local" })));
+    let h = Harness::new(&mock);
+    run(async {
+        let err = profiles_get_active(h.state()).await.unwrap_err();
+        assert_eq!(error_json(&err)["kind"], "notStarted");
+        assert_eq!(err.to_string(), "Bridge is not running yet.");
+
+        assert!(h.state().launch_bridge().await.is_err());
+        let status = bridge_status(h.state());
+        assert!(!status.running);
+        assert_eq!(status.error.as_deref(), Some("Mock bridge is unavailable"));
+        let err = profiles_get_active(h.state()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Bridge is not running: Mock bridge is unavailable");
+        assert!(bridge_info(h.state()).is_err());
+
+        let diagnostics = diagnostics_startup(h.state());
+        assert_eq!(diagnostics.error.as_deref(), Some("Mock bridge is unavailable"));
+        assert!(diagnostics.bridge_script.is_none());
+
+        mock.set_unavailable(false);
+        let status = bridge_retry(h.state()).await.unwrap();
+        assert!(status.running);
+        assert!(status.error.is_none());
+        assert!(diagnostics_startup(h.state()).error.is_none());
+        let active = profiles_get_active(h.state()).await.unwrap();
+        assert_eq!(active.name.as_deref(), Some("local"));
+    });
+}
+
+#[test]
+fn a_bridge_that_never_sends_its_handshake_fails_the_launch() {
+    let mock = MockBridge::new();
+    mock.set_silent(true);
+    let h = Harness::new(&mock);
+    run(async {
+        let state = h.state();
+        let launching = state.launch_at_startup();
+        let meanwhile = async {
+            settle().await;
+            let status = bridge_status(h.state());
+            assert!(status.starting && !status.running);
+            assert!(status.error.is_none());
+            let err = profiles_list(h.state()).await.unwrap_err();
+            assert_eq!(err.to_string(), "Bridge is still starting.");
+        };
+        tokio::join!(launching, meanwhile);
+
+        let status = bridge_status(h.state());
+        assert!(!status.starting && !status.running);
+        let reason = "Bridge at in-memory did not send its ready handshake within 30s";
+        assert_eq!(status.error.as_deref(), Some(reason));
+        let events: Vec<Value> = h.events.lock().unwrap().iter().map(|e| serde_json::to_value(e).unwrap()).collect();
+        assert_eq!(events, vec![json!({ "status": "launched", "running": false, "error": reason })]);
+
+        mock.set_silent(false);
+        assert!(bridge_retry(h.state()).await.unwrap().running);
+    });
+}
+
+#[test]
+fn bridge_retry_replaces_the_running_bridge() {
+    let mock = MockBridge::new();
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        assert!(bridge_retry(h.state()).await.unwrap().running);
+        assert_eq!(mock.connections(), 2);
+        let shutdowns = mock.received().iter().filter(|r| r.method == "bridge.shutdown").count();
+        assert_eq!(shutdowns, 1);
+    });
+}
+
+#[test]
+fn shell_settings_round_trip() {
+    let mock = MockBridge::new();
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        assert!(shell_settings_get(h.state()).node_path.is_none());
+        let next = settings::ShellSettings {
+            node_path: Some("/opt/node/bin/node".to_string()),
+            log_level: Some("debug".to_string()),
+            persist_metrics: Some(true),
+            bridge_env: Some(vec!["NODE_EXTRA_CA_CERTS".to_string(), " ".to_string()]),
+            sandbox_bridge: None,
+        };
+        shell_settings_set(h.state(), next).await.unwrap();
+        assert_eq!(shell_settings_get(h.state()).node_path.as_deref(), Some("/opt/node/bin/node"));
+        assert!(shell_settings_get(h.state()).persist_metrics());
+        assert_eq!(shell_settings_get(h.state()).bridge_env(), ["NODE_EXTRA_CA_CERTS"]);
+        assert!(shell_settings_get(h.state()).sandbox_bridge());
+
+        let bad = settings::ShellSettings {
+            log_level: Some("chatty".to_string()),
+            ..Default::default()
+        };
+        let err = shell_settings_set(h.state(), bad).await.unwrap_err();
+        assert_eq!(error_json(&err)["kind"], "settings");
+        assert_eq!(shell_settings_get(h.state()).log_level.as_deref(), Some("debug"));
+
+        let bad = settings::ShellSettings {
+            bridge_env: Some(vec!["AWS SECRET".to_string()]),
+            ..Default::default()
+        };
+        let err = shell_settings_set(h.state(), bad).await.unwrap_err();
+        assert_eq!(error_json(&err)["kind"], "settings");
+    });
+}
+
+#[test]
+fn bridge_environment_keeps_only_allowed_variables() {
+    let vars = [
+        ("PATH", "/usr/bin"),
+        ("HOME", "/home/me"),
+        ("OPENAI_API_KEY", "sk-test"),
+        ("OPENQUERY_BRIDGE_MAX_FRAME_MB", "8"),
+        ("AWS_SECRET_ACCESS_KEY", "aws"),
+        ("GITHUB_TOKEN", "ghp"),
+        ("NODE_EXTRA_CA_CERTS", "/etc/ca.pem"),
+        ("CORP_PROXY_URL", "http://proxy"),
+    ]
+    .map(|(name, value)| (name.into(), value.into()));
+    let extra = ["NODE_EXTRA_CA_CERTS".to_string(), "CORP_*".to_string()];
+
+    let env = environment::ChildEnv::filter(vars.clone(), &extra);
+    assert_eq!(
+        env.names(),
+        [
+            "CORP_PROXY_URL",
+            "HOME",
+            "NODE_EXTRA_CA_CERTS",
+            "OPENAI_API_KEY",
+            "OPENQUERY_BRIDGE_MAX_FRAME_MB",
+            "PATH"
+        ]
+    );
+    assert_eq!(environment::ChildEnv::filter(vars, &[]).names().len(), 4);
+
+    assert!(environment::validate_pattern("MY_APP_*").is_ok());
+    for bad in ["*", "AWS SECRET", "FOO=bar"] {
+        assert!(environment::validate_pattern(bad).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn sandbox_policy_confines_the_bridge_to_its_files() {
+    let root = std::env::temp_dir().join(format!("oq-sandbox-{}", std::process::id()));
+    let dist = root.join("packages/bridge/dist");
+    std::fs::create_dir_all(&dist).unwrap();
+    std::fs::create_dir_all(root.join("node_modules")).unwrap();
+    let policy = sandbox::SandboxPolicy::for_bridge(&dist.join("index.js"), Path::new("/opt/node/bin/node"));
+    assert!(policy.read_only.contains(&root));
+    assert!(policy.read_only.contains(&PathBuf::from("/opt/node")));
+    assert!(policy.read_write.contains(&std::env::temp_dir()));
+    assert!(!policy.read_write.iter().any(|p| policy.read_only.contains(p)));
+
+    let off = sandbox::Sandbox::off("Turned off in settings");
+    assert!(!off.status().active && !off.process_group());
+    assert_eq!(off.status().reason.as_deref(), Some("Turned off in settings"));
+    std::fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn shutdown_stops_the_bridge() {
+    let mock = MockBridge::new();
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        h.state().shutdown_bridge().await;
+        assert_eq!(mock.received().last().map(|r| r.method.as_str()), Some("bridge.shutdown"));
+        assert!(!bridge_status(h.state()).running);
+        let err = profiles_list(h.state()).await.unwrap_err();
+        assert_eq!(error_json(&err)["kind"], "notStarted");
+    });
+}
+
+// ── Logs ────────────────────────────────────────────────────────
+
+#[test]
+fn logs_tail_returns_the_latest_bridge_lines() {
+    let mock = MockBridge::new();
+    let h = Harness::new(&mock);
+    let stderr = h.state().logs.sink(logs::LogSource::Bridge);
+    for line in ["first", "second", "third"] {
+        stderr(line);
+    }
+    let tail = logs_tail(h.state(), "bridge".to_string(), Some(2)).unwrap();
+    let lines: Vec<&str> = tail.iter().map(|l| l.line.as_str()).collect();
+    assert_eq!(lines, ["second", "third"]);
+    assert!(tail[0].timestamp.ends_with('Z'), "{}", tail[0].timestamp);
+
+    let err = logs_tail(h.state(), "kernel".to_string(), None).unwrap_err();
+    assert_eq!(error_json(&err)["kind"], "logs");
+}
+
+#[test]
+fn bridge_logs_rotate_and_export_oldest_first() {
+    let dir = std::env::temp_dir().join(format!("openquery-logs-test-{}", uuid::Uuid::new_v4()));
+    let store = logs::LogStore::new(Some(&dir));
+    // 16 KB lines: a bit over 5 MB rotates bridge.log once.
+    let filler = "x".repeat(16 * 1024);
+    for i in 0..400 {
+        store.push(logs::LogSource::Bridge, &format!("line {:03} {}", i, filler));
+    }
+    assert!(dir.join("bridge.log.1").exists());
+    assert!(!dir.join("bridge.log.2").exists());
+
+    let export = store.export().unwrap();
+    let text = std::fs::read_to_string(&export.path).unwrap();
+    assert_eq!(export.bytes, text.len() as u64);
+    assert!(text.starts_with("===== bridge.log =====\n"));
+    let numbers: Vec<u32> = text
+        .lines()
+        .skip(1)
+        .take_while(|l| !l.starts_with("====="))
+        .map(|l| l.split(' ').nth(2).unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(numbers, (0..400).collect::<Vec<_>>());
+    assert!(text.contains("===== shell.jsonl =====\n"));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn bridge_calls_are_traced_without_their_params() {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let mock = MockBridge::new();
+    mock.on("workspace.sql", Reply::ok(workspace_result("ok")));
+    mock.on("profiles.list", Reply::err("AUTH_FAILED", "bad password"));
+    let h = Harness::new(&mock);
+    let subscriber = tracing_subscriber::registry().with(telemetry::json_layer(&h.state().logs));
+    tracing::subscriber::with_default(subscriber, || {
+        run(async {
+            h.launch().await;
+            h.state().passwords.set("local", "hunter2-secret");
+            workspace_sql(
+                h.state(),
+                "SELECT 1".to_string(),
+                "safe".to_string(),
+                None,
+                None,
+                Some("local".to_string()),
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+            profiles_list(h.state()).await.unwrap_err();
+        })
+    });
+
+    let lines = h.state().logs.tail(logs::LogSource::Shell, 100);
+    assert!(lines.iter().all(|l| !l.line.contains("hunter2")), "password leaked into the trace");
+    let records: Vec<Value> = lines.iter().map(|l| serde_json::from_str(&l.line).unwrap()).collect();
+    let call = |method: &str| {
+        records
+            .iter()
+            .find(|r| r["span"]["name"] == "bridge_call" && r["span"]["method"] == method)
+            .unwrap_or_else(|| panic!("no trace for {}: {:#?}", method, records))
+    };
+
+    let sql = call("workspace.sql");
+    assert_eq!(sql["level"], "INFO");
+    assert_eq!(sql["fields"]["outcome"], "ok");
+    assert!(sql["fields"]["duration_ms"].is_number());
+    assert!(sql["fields"]["response_bytes"].as_u64().unwrap() > 0);
+    assert!(sql["span"]["request_bytes"].as_u64().unwrap() > 0);
+
+    let failed = call("profiles.list");
+    assert_eq!(failed["level"], "WARN");
+    assert_eq!(failed["fields"]["outcome"], "remote");
+    assert_eq!(failed["fields"]["code"], "AUTH_FAILED");
+}
+
+// ── Metrics ─────────────────────────────────────────────────────
+
+#[test]
+fn call_metrics_report_latency_errors_and_sizes() {
+    let mock = MockBridge::new();
+    for _ in 0..18 {
+        mock.on("workspace.sql", Reply::after(10, Reply::ok(workspace_result("ok"))));
+    }
+    mock.on("workspace.sql", Reply::after(200, Reply::ok(workspace_result("ok"))));
+    mock.on("profiles.list", Reply::err("AUTH_FAILED", "bad password"));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        for _ in 0..20 {
+            run_sql(&h).await.unwrap();
+        }
+        profiles_list(h.state()).await.unwrap_err();
+        profiles_list(h.state()).await.unwrap_err();
+    });
+
+    let report = diagnostics_metrics(h.state());
+    assert_eq!(report.shell_version, "test");
+    let method = |name: &str| report.methods.iter().find(|m| m.method == name).unwrap();
+    assert!(report.methods.iter().all(|m| m.method != "bridge.ping"));
+
+    let sql = method("workspace.sql");
+    assert_eq!((sql.calls, sql.errors), (20, 0));
+    // Quantiles are bucket upper bounds, at most 15% over.
+    assert!((10.0..11.5).contains(&sql.p50_ms), "p50 {}", sql.p50_ms);
+    assert!((200.0..230.0).contains(&sql.p95_ms), "p95 {}", sql.p95_ms);
+    assert_eq!(sql.max_ms, 200.0);
+    assert!(sql.request_bytes_mean > 0.0);
+    assert!(sql.request_bytes_max as f64 >= sql.request_bytes_mean);
+    assert!(sql.response_bytes_max > 0);
+
+    let failed = method("profiles.list");
+    assert_eq!((failed.calls, failed.errors), (2, 2));
+    assert_eq!(failed.error_codes.get("AUTH_FAILED"), Some(&2));
+    assert_eq!(failed.response_bytes_mean, 0.0);
+
+    assert_eq!(report.releases.len(), 1);
+    assert_eq!(report.releases[0].shell_version, "test");
+}
+
+#[test]
+fn saved_metrics_are_kept_per_release() {
+    let dir = std::env::temp_dir().join(format!("openquery-metrics-test-{}", uuid::Uuid::new_v4()));
+    let ok: Result<usize, &BridgeError> = Ok(100);
+    let old = metrics::Metrics::new(Some(&dir), "0.9.0");
+    old.record("workspace.sql", Duration::from_millis(40), 50, ok);
+    // Saving twice must not count the session twice.
+    old.save().unwrap();
+    old.save().unwrap();
+
+    let current = metrics::Metrics::new(Some(&dir), "0.10.0");
+    assert!(current.report().methods.is_empty());
+    let timeout = BridgeError::Timeout {
+        method: "workspace.sql".to_string(),
+        timeout_ms: 30_000,
+    };
+    current.record("workspace.sql", Duration::from_millis(30_000), 50, Err(&timeout));
+
+    let report = current.report();
+    let versions: Vec<&str> = report.releases.iter().map(|r| r.shell_version.as_str()).collect();
+    assert_eq!(versions, ["0.10.0", "0.9.0"]);
+    let before = &report.releases[1].methods[0];
+    assert_eq!((before.calls, before.errors, before.max_ms), (1, 0, 40.0));
+    assert_eq!(report.methods[0].error_codes.get("TIMEOUT"), Some(&1));
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ── Recording ───────────────────────────────────────────────────
+
+#[test]
+fn recorded_traffic_is_redacted_and_replays_offline() {
+    let path = std::env::temp_dir().join(format!("openquery-recording-{}.jsonl", uuid::Uuid::new_v4()));
+    let mock = MockBridge::new();
+    mock.on(
+        "workspace.sql",
+        Reply::Progress(vec![json!({ "stage": "explaining" })], Box::new(Reply::ok(workspace_result("ok")))),
+    )
+    .on("settings.testOpenAiKey", Reply::ok(json!({ "ok": false, "message": "invalid key" })))
+    .on("profiles.list", Reply::err("AUTH_FAILED", "bad password"));
+    let recorder = Arc::new(recording::Recorder::create(&path).unwrap());
+    let h = Harness::with_connector(mock.connector(), Wire::default(), Some(recorder));
+    let recorded = std::cell::RefCell::new(Vec::new());
+    run(async {
+        h.launch().await;
+        let result = run_sql(&h).await.unwrap();
+        recorded.borrow_mut().push(serde_json::to_value(result).unwrap());
+        let check = settings_test_openai_key(h.state(), Some("sk-live-secret".to_string())).await.unwrap();
+        recorded.borrow_mut().push(serde_json::to_value(check).unwrap());
+        let err = profiles_list(h.state()).await.unwrap_err();
+        recorded.borrow_mut().push(error_json(&err));
+    });
+
+    let text = std::fs::read_to_string(&path).unwrap();
+    for secret in ["\"pw\"", "sk-live-secret"] {
+        assert!(!text.contains(secret), "{} leaked into the recording", secret);
+    }
+    assert!(!text.contains("bridge.ping") && !text.contains("bridge.encoding"));
+    let entries: Vec<Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(entries[0]["type"], "session");
+    let sql = entries.iter().find(|e| e["method"] == "workspace.sql").unwrap();
+    assert_eq!(sql["params"]["password"], "[redacted]");
+    let cell = &sql["result"]["executionResult"]["rows"][0]["id"];
+    assert!(cell.as_str().unwrap().starts_with("sha256:"), "{}", cell);
+    assert!(entries.iter().any(|e| e["type"] == "progress" && e["data"]["stage"] == "explaining"));
+
+    // Replay against nothing but the file.
+    let replay = Harness::with_connector(recording::replay_connector(&path).unwrap(), Wire::default(), None);
+    run(async {
+        replay.launch().await;
+        assert_eq!(bridge_info(replay.state()).unwrap().bridge_version, "mock");
+        let result = serde_json::to_value(run_sql(&replay).await.unwrap()).unwrap();
+        assert_eq!(result["status"], "ok");
+        assert_eq!(result["executionResult"]["rows"][0]["id"], *cell);
+        let check = settings_test_openai_key(replay.state(), None).await.unwrap();
+        assert_eq!(serde_json::to_value(check).unwrap(), recorded.borrow()[1]);
+        let err = profiles_list(replay.state()).await.unwrap_err();
+        assert_eq!(error_json(&err), recorded.borrow()[2]);
+
+        // Replies are used up in order.
+        let err = profiles_list(replay.state()).await.unwrap_err();
+        assert_eq!(error_json(&err)["code"], "NOT_RECORDED");
+    });
+    let progress = replay.notifications.lock().unwrap();
+    assert!(matches!(&progress[..], [Notification::Progress { data, .. }] if data["stage"] == "explaining"));
+    let _ = std::fs::remove_file(&path);
+}
+
+// ── Keychain ────────────────────────────────────────────────────
+
+#[test]
+fn keychain_commands_store_and_forget_passwords() {
+    run(async {
+        keychain_set("kc-test".to_string(), "s3cret".to_string()).await.unwrap();
+        assert!(keychain_has("kc-test".to_string()).await.unwrap());
+        keychain_delete("kc-test".to_string()).await.unwrap();
+        assert!(!keychain_has("kc-test".to_string()).await.unwrap());
+        // Deleting a missing entry is not an error.
+        keychain_delete("kc-test".to_string()).await.unwrap();
+
+        // The shell's own entries can be checked for, but not written or removed.
+        settings_set_openai_key("sk-test".to_string()).await.unwrap();
+        assert!(keychain_has(credentials::OPENAI_KEY_ID.to_string()).await.unwrap());
+        let err = keychain_set(credentials::OPENAI_KEY_ID.to_string(), "sk-evil".to_string()).await.unwrap_err();
+        assert_eq!(error_json(&err)["code"], "KEYCHAIN");
+        assert!(keychain_delete(credentials::OPENAI_KEY_ID.to_string()).await.is_err());
+        assert_eq!(credentials::openai_key().await.as_deref(), Some("sk-test"));
+        settings_clear_openai_key().await.unwrap();
+        assert!(credentials::openai_key().await.is_none());
+    });
+}
+
+// ── Bridge-backed commands ──────────────────────────────────────
+
+#[test]
+fn profile_commands() {
+    let mock = MockBridge::new();
+    let mut listed = profile_row("local");
+    listed["_active"] = json!(true);
+    mock.on("profiles.list", Reply::ok(json!([listed])))
+        .on("profiles.add", Reply::ok(profile_row("staging")))
+        .on("profiles.remove", Reply::ok(json!({ "ok": true, "profileId": "id-staging" })))
+        .on("profiles.use", Reply::ok(json!({ "ok": true })))
+        .on("profiles.test", Reply::ok(json!({ "ok": true, "error": null, "serverVersion": "16.2" })))
+        .on("profiles.getActive", Reply::ok(json!({ "name": null })));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+
+        let profiles = profiles_list(h.state()).await.unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].active);
+        assert_eq!(profiles[0].profile.port, Some(5432));
+        assert_eq!(mock.params("profiles.list"), Some(json!({})));
+
+        let new_profile = protocol::NewProfile {
+            name: "staging".to_string(),
+            db_type: "postgres".to_string(),
+            host: Some("db.internal".to_string()),
+            port: None,
+            database: None,
+            user: None,
+            ssl: true,
+        };
+        let added = profiles_add(h.state(), new_profile).await.unwrap();
+        assert_eq!(added.name, "staging");
+        assert_eq!(
+            mock.params("profiles.add"),
+            Some(json!({ "name": "staging", "db_type": "postgres", "host": "db.internal", "ssl": true }))
+        );
+
+        keychain_set("id-staging".to_string(), "pw".to_string()).await.unwrap();
+        let removed = profiles_remove(h.state(), "staging".to_string()).await.unwrap();
+        assert_eq!(removed.profile_id, "id-staging");
+        assert_eq!(mock.params("profiles.remove"), Some(json!({ "name": "staging" })));
+        assert!(!keychain_has("id-staging".to_string()).await.unwrap());
+
+        assert!(profiles_use(h.state(), "local".to_string()).await.unwrap().ok);
+        assert_eq!(mock.params("profiles.use"), Some(json!({ "name": "local" })));
+
+        // A profile cannot be named into the shell's keychain namespace.
+        let reserved = protocol::NewProfile {
+            name: credentials::OPENAI_KEY_ID.to_string(),
+            db_type: "postgres".to_string(),
+            host: Some("attacker.example".to_string()),
+            port: None,
+            database: None,
+            user: None,
+            ssl: false,
+        };
+        assert!(profiles_add(h.state(), reserved).await.is_err());
+        assert_eq!(mock.received().iter().filter(|r| r.method == "profiles.add").count(), 1);
+
+        // Only the entry under the profile id counts, as Setup and Profiles
+        // save it; one under its name (which the webview picks) does not.
+        keychain_set("local".to_string(), "other".to_string()).await.unwrap();
+        profiles_test(h.state(), "local".to_string()).await.unwrap();
+        assert_eq!(mock.params("profiles.test"), Some(json!({ "name": "local", "password": "" })));
+        keychain_delete("local".to_string()).await.unwrap();
+        keychain_set("id-local".to_string(), "pw".to_string()).await.unwrap();
+        let test = profiles_test(h.state(), "local".to_string()).await.unwrap();
+        assert_eq!(test.server_version.as_deref(), Some("16.2"));
+        assert_eq!(mock.params("profiles.test"), Some(json!({ "name": "local", "password": "pw" })));
+        keychain_delete("id-local".to_string()).await.unwrap();
+
+        assert!(profiles_get_active(h.state()).await.unwrap().name.is_none());
+    });
+}
+
+#[test]
+fn schema_commands() {
+    let mock = MockBridge::new();
+    let (mut staging, mut local) = (profile_row("staging"), profile_row("local"));
+    staging["_active"] = json!(false);
+    local["_active"] = json!(true);
+    mock.on("profiles.list", Reply::ok(json!([staging, local])));
+    let table = json!({
+        "name": "orders",
+        "schema": "public",
+        "columns": [{
+            "name": "id",
+            "dataType": "integer",
+            "nullable": false,
+            "isPrimaryKey": true,
+            "defaultValue": null,
+        }],
+        "rowCountEstimate": 120.0,
+    });
+    mock.on("schema.refresh", Reply::ok(json!({ "tables": 3, "columns": 12 })))
+        .on(
+            "schema.search",
+            Reply::ok(json!([{ "schema": "public", "table": "orders", "column": "id", "dataType": "integer" }])),
+        )
+        .on("schema.tableDetail", Reply::ok(table.clone()))
+        .on("schema.getSnapshot", Reply::ok(json!(null)))
+        .on(
+            "schema.getSnapshot",
+            Reply::ok(json!({ "tables": [table], "capturedAt": "2026-01-01T00:00:00Z" })),
+        );
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+
+        // No name: the active profile's session password is used.
+        session_password_set(h.state(), "local".to_string(), "pw".to_string()).await.unwrap();
+        let summary = schema_refresh(h.state(), None, Some("refresh-1".to_string())).await.unwrap();
+        assert_eq!((summary.tables, summary.columns), (3, 12));
+        assert_eq!(mock.params("schema.refresh"), Some(json!({ "password": "pw", "name": "local" })));
+        assert!(mock.received().iter().any(|r| r.id == "refresh-1"));
+        // The profile list is kept: a second refresh goes straight to the bridge.
+        session_password_set(h.state(), "local".to_string(), String::new()).await.unwrap();
+        schema_refresh(h.state(), None, None).await.unwrap();
+        assert_eq!(mock.received().iter().filter(|r| r.method == "profiles.list").count(), 1);
+
+        let matches = schema_search(h.state(), "ord".to_string()).await.unwrap();
+        assert_eq!(matches[0].table, "orders");
+        assert_eq!(mock.params("schema.search"), Some(json!({ "query": "ord" })));
+
+        let detail = schema_table_detail(h.state(), "orders".to_string(), Some("public".to_string()))
+            .await
+            .unwrap();
+        assert!(detail.columns[0].is_primary_key);
+        assert_eq!(mock.params("schema.tableDetail"), Some(json!({ "table": "orders", "schema": "public" })));
+
+        assert!(schema_get_snapshot(h.state()).await.unwrap().is_none());
+        let snapshot = schema_get_snapshot(h.state()).await.unwrap().unwrap();
+        assert_eq!(snapshot.tables.len(), 1);
+    });
+}
+
+#[test]
+fn ask_and_workspace_commands() {
+    let mock = MockBridge::new();
+    mock.on("ask.dryRun", Reply::ok(ask_result("dry-run")))
+        .on("ask.run", Reply::ok(ask_result("ok")))
+        .on("workspace.sql", Reply::ok(workspace_result("ok")));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        h.state().passwords.set("local", "pw");
+
+        let dry = ask_dry_run(
+            h.state(),
+            "how many orders?".to_string(),
+            "safe".to_string(),
+            Some("local".to_string()),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(dry.plan.sql, "SELECT count(*) FROM orders");
+        assert_eq!(dry.plan.params[0].kind, "string");
+        assert!(dry.execution_result.is_none() && dry.error.is_none());
+        assert_eq!(
+            mock.params("ask.dryRun"),
+            Some(json!({ "question": "how many orders?", "mode": "safe", "password": "pw", "name": "local" }))
+        );
+
+        // The key saved in Settings is added by the shell.
+        settings_set_openai_key(" sk-test ".to_string()).await.unwrap();
+        let ran = ask_run(
+            h.state(),
+            "how many orders?".to_string(),
+            "safe".to_string(),
+            Some("local".to_string()),
+            Some("ask-1".to_string()),
+        )
+        .await
+        .unwrap();
+        settings_clear_openai_key().await.unwrap();
+        assert_eq!(ran.status, "ok");
+        assert_eq!(ran.execution_result.unwrap().row_count, 1);
+        assert_eq!(mock.params("ask.run").unwrap()["openAiApiKey"], "sk-test");
+
+        let policy = protocol::SqlPolicy {
+            max_rows_threshold: Some(50.0),
+            max_cost_threshold: None,
+            enforce_limit: Some(true),
+        };
+        let result = workspace_sql(
+            h.state(),
+            "SELECT id FROM t".to_string(),
+            "safe".to_string(),
+            Some("run".to_string()),
+            Some(policy),
+            Some("local".to_string()),
+            Some(false),
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.status, "ok");
+        assert_eq!(result.execution_result.unwrap().row_count, 1);
+        assert_eq!(
+            mock.params("workspace.sql"),
+            Some(json!({
+                "sql": "SELECT id FROM t",
+                "mode": "safe",
+                "password": "pw",
+                "action": "run",
+                "policy": { "maxRowsThreshold": 50.0, "enforceLimit": true },
+                "name": "local",
+                "streamRows": false,
+            }))
+        );
+    });
+}
+
+#[test]
+fn history_commands() {
+    let mock = MockBridge::new();
+    mock.on(
+        "history.list",
+        Reply::ok(json!([{
+            "id": "q1",
+            "question": "how many orders?",
+            "askedAt": "2026-01-01T00:00:00Z",
+            "status": "ok",
+            "execMs": 3.0,
+            "rowCount": 1,
+        }])),
+    )
+    .on(
+        "history.show",
+        Reply::ok(json!({
+            "query": {
+                "id": "q1",
+                "profileId": "id-local",
+                "question": "how many orders?",
+                "mode": "safe",
+                "dialect": "postgres",
+                "askedAt": "2026-01-01T00:00:00Z",
+            },
+            "generation": null,
+            "run": null,
+        })),
+    )
+    .on("history.exportMd", Reply::ok(json!("# how many orders?")));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+
+        let items = history_list(h.state(), Some(5)).await.unwrap();
+        assert_eq!(items[0].row_count, Some(1));
+        assert_eq!(mock.params("history.list"), Some(json!({ "limit": 5 })));
+        history_list(h.state(), None).await.unwrap();
+        assert_eq!(mock.params("history.list"), Some(json!({})));
+
+        let detail = history_show(h.state(), "q1".to_string()).await.unwrap();
+        assert_eq!(detail.query.dialect, "postgres");
+        assert!(detail.run.is_none());
+        assert_eq!(mock.params("history.show"), Some(json!({ "id": "q1" })));
+
+        assert_eq!(history_export_md(h.state(), "q1".to_string()).await.unwrap(), "# how many orders?");
+    });
+}
+
+#[test]
+fn settings_and_setup_commands() {
+    let mock = MockBridge::new();
+    let demo = json!({ "ready": true, "dbPath": "/tmp/demo.db", "profileName": "demo" });
+    mock.on("settings.status", Reply::ok(settings_result()))
+        .on("settings.testOpenAiKey", Reply::ok(json!({ "ok": false, "message": "invalid key" })))
+        .on(
+            "demo.noDockerStatus",
+            Reply::ok(json!({ "ready": false, "dbPath": "/tmp/demo.db", "active": false, "profileName": "demo" })),
+        )
+        .on("demo.noDockerPrepare", Reply::ok(demo.clone()))
+        .on("demo.noDockerReset", Reply::ok(demo))
+        .on("fixture.checkDocker", Reply::ok(json!({ "installed": true, "daemonRunning": false, "message": null })))
+        .on("fixture.pickPort", Reply::ok(json!({ "port": 55432 })))
+        .on("fixture.up", Reply::ok(json!({ "running": true, "port": 55432, "profileName": "fixture" })))
+        .on("fixture.down", Reply::ok(json!({ "ok": true })))
+        .on("fixture.status", Reply::ok(json!({ "running": true, "port": 55432, "message": null })))
+        .on("fixture.logs", Reply::ok(json!({ "lines": ["ready"] })));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+
+        let status = settings_status(h.state()).await.unwrap();
+        assert!(status.defaults.enforce_limit);
+
+        let check = settings_test_openai_key(h.state(), Some("sk-bad".to_string())).await.unwrap();
+        assert!(!check.ok);
+        assert_eq!(mock.params("settings.testOpenAiKey"), Some(json!({ "apiKey": "sk-bad" })));
+
+        assert!(!demo_no_docker_status(h.state()).await.unwrap().ready);
+        assert!(demo_no_docker_prepare(h.state(), None).await.unwrap().ready);
+        assert_eq!(mock.params("demo.noDockerPrepare"), Some(json!({ "reset": false })));
+        assert_eq!(demo_no_docker_reset(h.state()).await.unwrap().profile_name, "demo");
+
+        assert!(!fixture_check_docker(h.state()).await.unwrap().daemon_running);
+        let port = fixture_pick_port(h.state(), Some(vec![55432, 55433])).await.unwrap();
+        assert_eq!(port.port, 55432);
+        assert_eq!(mock.params("fixture.pickPort"), Some(json!({ "preferredPorts": [55432, 55433] })));
+        assert!(fixture_up(h.state(), 55432).await.unwrap().running);
+        assert_eq!(mock.params("fixture.up"), Some(json!({ "port": 55432 })));
+        assert!(fixture_down(h.state()).await.unwrap().ok);
+        assert_eq!(fixture_status(h.state()).await.unwrap().port, Some(55432));
+        assert_eq!(fixture_logs(h.state(), Some(20)).await.unwrap().lines, vec!["ready"]);
+        assert_eq!(mock.params("fixture.logs"), Some(json!({ "tail": 20 })));
+    });
+}
+
+#[test]
+fn power_and_write_commands() {
+    let mock = MockBridge::new();
+    mock.on("profile.updatePower", Reply::ok(json!({ "ok": true })))
+        .on(
+            "profile.getPower",
+            Reply::ok(json!({ "allowWrite": true, "allowDangerous": false, "confirmPhrase": null })),
+        )
+        .on(
+            "write.preview",
+            Reply::ok(json!({
+                "classification": { "kind": "write" },
+                "kind": "update",
+                "impactedTables": ["orders"],
+                "hasWhereClause": true,
+                "summary": "UPDATE orders",
+                "estimatedRowsAffected": 1.0,
+                "explainPlan": {},
+                "warnings": [],
+                "requiresConfirmation": true,
+                "confirmationPhrase": "UPDATE orders",
+                "requiresDangerousConfirmation": false,
+                "dangerousConfirmationPhrase": "",
+            })),
+        )
+        .on(
+            "write.execute",
+            Reply::ok(json!({ "success": true, "rowsAffected": 1, "execMs": 4.0, "error": null })),
+        );
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+
+        let update = protocol::PowerSettingsUpdate {
+            allow_write: Some(true),
+            allow_dangerous: None,
+            confirm_phrase: Some(None),
+        };
+        assert!(profile_update_power(h.state(), "local".to_string(), update).await.unwrap().ok);
+        assert_eq!(
+            mock.params("profile.updatePower"),
+            Some(json!({ "name": "local", "settings": { "allowWrite": true, "confirmPhrase": null } }))
+        );
+
+        let power = profile_get_power(h.state(), "local".to_string()).await.unwrap();
+        assert!(power.allow_write && !power.allow_dangerous);
+
+        let sql = "UPDATE orders SET status = $1 WHERE id = $2".to_string();
+        h.state().passwords.set("local", "pw");
+        let local = || Some("local".to_string());
+        let preview = write_preview(h.state(), sql.clone(), vec![json!("shipped"), json!(7)], local(), None)
+            .await
+            .unwrap();
+        assert!(preview.requires_confirmation);
+        assert_eq!(
+            mock.params("write.preview"),
+            Some(json!({ "sql": sql, "params": ["shipped", 7], "password": "pw", "name": "local" }))
+        );
+
+        let executed = write_execute(h.state(), sql, vec![], local(), None)
+            .await
+            .unwrap();
+        assert_eq!(executed.rows_affected, 1);
+    });
+}
+
+// ── Errors, timeouts, cancellation, ordering ────────────────────
+
+#[test]
+fn remote_errors_keep_the_bridge_code() {
+    let mock = MockBridge::new();
+    mock.on("profiles.test", Reply::err("AUTH_FAILED", "password authentication failed"));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        session_password_set(h.state(), "local".to_string(), "wrong".to_string()).await.unwrap();
+        let err = profiles_test(h.state(), "local".to_string()).await.unwrap_err();
+        assert_eq!(
+            error_json(&err),
+            json!({
+                "kind": "remote",
+                "code": "AUTH_FAILED",
+                "message": "password authentication failed",
+                "details": null,
+            })
+        );
+    });
+}
+
+#[test]
+fn mismatched_results_name_the_field() {
+    let mock = MockBridge::new();
+    let mut result = settings_result();
+    result.as_object_mut().unwrap().remove("model");
+    mock.on("settings.status", Reply::ok(result));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        let err = settings_status(h.state()).await.unwrap_err();
+        let json = error_json(&err);
+        assert_eq!(json["kind"], "protocol");
+        assert_eq!(json["details"], json!({ "method": "settings.status", "field": "model" }));
+    });
+}
+
+#[test]
+fn mismatched_nested_results_name_the_path() {
+    let mock = MockBridge::new();
+    let mut result = workspace_result("ok");
+    result["executionResult"]["rowCount"] = json!("one");
+    mock.on("workspace.sql", Reply::ok(result));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        let err = run_sql(&h).await.unwrap_err();
+        assert_eq!(
+            error_json(&err)["details"],
+            json!({ "method": "workspace.sql", "field": "executionResult.rowCount" })
+        );
+    });
+}
+
+#[test]
+fn unanswered_calls_time_out_and_are_cancelled_on_the_bridge() {
+    let mock = MockBridge::new();
+    mock.on("history.show", Reply::Never);
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        let err = history_show(h.state(), "q1".to_string()).await.unwrap_err();
+        assert_eq!(
+            error_json(&err),
+            json!({
+                "kind": "timeout",
+                "code": "TIMEOUT",
+                "message": "history.show timed out after 60s",
+                "details": { "method": "history.show", "timeoutMs": 60_000 },
+            })
+        );
+        settle().await;
+        let id = mock.received().last().unwrap().id.clone();
+        assert_eq!(mock.cancelled(), vec![id]);
+    });
+}
+
+#[test]
+fn bridge_cancel_stops_an_in_flight_call() {
+    let mock = MockBridge::new();
+    mock.on("ask.run", Reply::Never);
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        h.state().passwords.set("local", "pw");
+        let request_id = "ask-cancel".to_string();
+        let (asked, cancelled) = tokio::join!(
+            ask_run(
+                h.state(),
+                "slow question".to_string(),
+                "safe".to_string(),
+                Some("local".to_string()),
+                Some(request_id.clone()),
+            ),
+            async {
+                mock.wait_for(&request_id).await;
+                bridge_cancel(h.state(), request_id.clone()).await
+            },
+        );
+        assert!(cancelled.unwrap());
+        assert_eq!(error_json(&asked.unwrap_err())["kind"], "cancelled");
+        settle().await;
+        assert_eq!(mock.cancelled(), vec![request_id.clone()]);
+
+        // Nothing left to cancel.
+        assert!(!bridge_cancel(h.state(), request_id).await.unwrap());
+    });
+}
+
+#[test]
+fn replies_are_matched_to_callers_when_they_arrive_out_of_order() {
+    let mock = MockBridge::new();
+    let mut slow = workspace_result("ok");
+    slow["rewrittenSql"] = json!("SELECT slow");
+    let mut fast = workspace_result("ok");
+    fast["rewrittenSql"] = json!("SELECT fast");
+    mock.on("workspace.sql", Reply::after(50, Reply::ok(slow)))
+        .on("workspace.sql", Reply::ok(fast))
+        .on("settings.status", Reply::ok(settings_result()));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        h.state().passwords.set("local", "pw");
+        let finished = Mutex::new(Vec::new());
+        let sql = |request_id: &'static str| {
+            let finished = &finished;
+            let state = h.state();
+            async move {
+                let result = workspace_sql(
+                    state,
+                    request_id.to_string(),
+                    "safe".to_string(),
+                    None,
+                    None,
+                    Some("local".to_string()),
+                    None,
+                    Some(request_id.to_string()),
+                )
+                .await;
+                finished.lock().unwrap().push(request_id);
+                result
+            }
+        };
+        let (first, second, status) = tokio::join!(sql("first"), sql("second"), settings_status(h.state()));
+
+        assert_eq!(first.unwrap().rewritten_sql, "SELECT slow");
+        assert_eq!(second.unwrap().rewritten_sql, "SELECT fast");
+        assert!(status.is_ok());
+        let sent: Vec<String> = mock.received().iter().map(|r| r.id.clone()).collect();
+        assert_eq!(&sent[..2], ["first", "second"]);
+        assert_eq!(*finished.lock().unwrap(), vec!["second", "first"]);
+    });
+}
+
+#[test]
+fn duplicate_request_ids_are_rejected() {
+    let mock = MockBridge::new();
+    mock.on("schema.refresh", Reply::after(50, Reply::ok(json!({ "tables": 1, "columns": 1 }))));
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        h.state().passwords.set("local", "pw");
+        let (local, id) = (|| Some("local".to_string()), || Some("refresh".to_string()));
+        let (first, second) = tokio::join!(
+            schema_refresh(h.state(), local(), id()),
+            schema_refresh(h.state(), local(), id()),
+        );
+        assert!(first.is_ok());
+        assert_eq!(error_json(&second.unwrap_err())["kind"], "protocol");
+    });
+}
+
+#[test]
+fn progress_notifications_reach_the_sink() {
+    let mock = MockBridge::new();
+    let rows = vec![json!({ "rows": [{ "id": 1 }] }), json!({ "rows": [{ "id": 2 }] })];
+    mock.on(
+        "workspace.sql",
+        Reply::Progress(rows.clone(), Box::new(Reply::ok(workspace_result("ok")))),
+    );
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        h.state().passwords.set("local", "pw");
+        workspace_sql(
+            h.state(),
+            "SELECT id FROM t".to_string(),
+            "safe".to_string(),
+            None,
+            None,
+            Some("local".to_string()),
+            Some(true),
+            Some("stream-1".to_string()),
+        )
+        .await
+        .unwrap();
+        let progress: Vec<Value> = h
+            .notifications
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|n| match n {
+                Notification::Progress { request_id, data } => {
+                    assert_eq!(request_id, "stream-1");
+                    data.clone()
+                }
+                other => panic!("unexpected notification: {:?}", other),
+            })
+            .collect();
+        assert_eq!(progress, rows);
+    });
+}
+
+#[test]
+fn a_crashed_bridge_is_restarted() {
+    let mock = MockBridge::new();
+    mock.on("fixture.status", Reply::Crash).on(
+        "fixture.status",
+        Reply::ok(json!({ "running": false, "port": null, "message": null })),
+    );
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        let err = fixture_status(h.state()).await.unwrap_err();
+        assert_eq!(error_json(&err)["kind"], "transport");
+
+        // Backoff elapses on the paused clock; the supervisor reconnects.
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(mock.connections(), 2);
+        assert!(bridge_status(h.state()).running);
+        assert!(!fixture_status(h.state()).await.unwrap().running);
+
+        let events: Vec<Value> = h
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap()["status"].clone())
+            .collect();
+        assert_eq!(events, vec![json!("crashed"), json!("restarting"), json!("restarted")]);
+    });
+}
+
+#[test]
+fn a_reload_lets_calls_in_flight_finish() {
+    let mock = MockBridge::new();
+    mock.on(
+        "fixture.status",
+        Reply::after(2_000, Reply::ok(json!({ "running": true, "port": 5432, "message": null }))),
+    );
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        let supervisor = h.state().bridge().unwrap();
+        let reload = async {
+            settle().await;
+            supervisor.reload(vec!["dist/handlers.js".to_string()]).await;
+        };
+        let (status, ()) = tokio::join!(fixture_status(h.state()), reload);
+        assert!(status.unwrap().running);
+
+        let methods: Vec<String> = mock.received().into_iter().map(|r| r.method).collect();
+        let finished = methods.iter().position(|m| m == "fixture.status").unwrap();
+        assert!(methods[finished..].contains(&"bridge.shutdown".to_string()));
+        assert_eq!(mock.connections(), 2);
+        assert!(bridge_status(h.state()).running);
+
+        let events: Vec<Value> = h.events.lock().unwrap().iter().map(|e| serde_json::to_value(e).unwrap()).collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["status"], "reloaded");
+        assert_eq!(events[0]["changed"], json!(["dist/handlers.js"]));
+        assert!(events[0]["durationMs"].as_f64().unwrap() >= 2_000.0);
+    });
+}
+
+#[test]
+fn a_failed_reload_is_retried_by_the_crash_watcher() {
+    let mock = MockBridge::new();
+    let h = Harness::new(&mock);
+    run(async {
+        h.launch().await;
+        let supervisor = h.state().bridge().unwrap();
+        mock.set_unavailable(true);
+        let started = tokio::time::Instant::now();
+        supervisor.reload(vec!["dist/handlers.js".to_string()]).await;
+        // The reload hands the failure over instead of sitting out the backoff.
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        mock.set_unavailable(false);
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert_eq!(mock.connections(), 2);
+        assert!(bridge_status(h.state()).running);
+
+        let events: Vec<Value> = h
+            .events
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|e| serde_json::to_value(e).unwrap()["status"].clone())
+            .collect();
+        assert_eq!(events, vec![json!("crashed"), json!("restarting"), json!("restarted")]);
+    });
+}
+
+#[test]
+fn oversized_frames_fail_only_their_own_call() {
+    let mock = MockBridge::new();
+    let big = "x".repeat(100 * 1024);
+    mock.on("history.exportMd", Reply::ok(json!(big)))
+        .on("history.exportMd", Reply::ok(json!("# small")));
+    let h = Harness::with_wire(
+        &mock,
+        Wire {
+            max_frame: 64 * 1024,
+            ..Wire::default()
+        },
+    );
+    run(async {
+        h.launch().await;
+        let err = history_export_md(h.state(), "q1".to_string()).await.unwrap_err();
+        let json = error_json(&err);
+        assert_eq!(json["kind"], "protocol");
+        assert_eq!(json["code"], "FRAME_TOO_LARGE");
+        assert_eq!(json["details"]["method"], "history.exportMd");
+        assert_eq!(json["details"]["maxSize"], 64 * 1024);
+        assert!(json["details"]["size"].as_u64().unwrap() > 100 * 1024);
+
+        // The oversized frame was skipped whole; the next reply reads cleanly.
+        assert_eq!(history_export_md(h.state(), "q1".to_string()).await.unwrap(), "# small");
+
+        h.state().passwords.set("local", "pw");
+        let err = write_execute(h.state(), big, vec![], Some("local".to_string()), None)
+            .await
+            .unwrap_err();
+        assert_eq!(error_json(&err)["code"], "FRAME_TOO_LARGE");
+        assert!(mock.params("write.execute").is_none());
+    });
+}
@@ -0,0 +1,309 @@
+// Byte transports for the bridge.
+//
+// `Bridge` speaks the protocol over any `Connection`: a read half, a write half
+// and a `BridgeTransport` that controls whatever is on the other end. The app
+// runs the bridge as a child process over stdio; a Unix socket lets a bridge
+// started by hand (e.g. under a debugger) be attached instead; the in-memory
+// pair backs recording replays and the scripted mock used by the Rust tests.
+
+use crate::bridge::SpawnError;
+use crate::environment::ChildEnv;
+use crate::sandbox::Sandbox;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, BufReader};
+use tokio::process::{Child, ChildStderr, Command};
+
+pub type BoxReader = Box<dyn AsyncRead + Send + Unpin>;
+pub type BoxWriter = Box<dyn AsyncWrite + Send + Unpin>;
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Lifecycle control over the far end of a bridge connection.
+pub trait BridgeTransport: Send + Sync {
+    /// What is on the other end, for error messages.
+    fn describe(&self) -> String;
+
+    /// Wait up to `timeout` for the far end to go away. Returns how it went
+    /// (e.g. the exit status), or None if it is still there.
+    fn wait(&self, timeout: Duration) -> BoxFuture<'_, Option<String>>;
+
+    /// Ask the far end to stop (SIGTERM for a process). False if there is no
+    /// such thing, in which case the caller goes straight to `kill`.
+    fn terminate(&self) -> bool;
+
+    /// Stop the far end forcibly and wait until it is gone.
+    fn kill(&self) -> BoxFuture<'_, ()>;
+
+    /// Best-effort, non-blocking kill for `Drop`.
+    fn abort(&self);
+
+    /// Called once the read half hit EOF: how the far end went away. One that
+    /// closed its output but keeps running is useless to us, so it is killed
+    /// after a short grace period.
+    fn reap(&self) -> BoxFuture<'_, String> {
+        Box::pin(async move {
+            match self.wait(Duration::from_secs(1)).await {
+                Some(exit) => exit,
+                None => {
+                    self.kill().await;
+                    format!("{} closed its output and was killed", self.describe())
+                }
+            }
+        })
+    }
+}
+
+/// An open bridge channel, ready for the ready handshake.
+pub struct Connection {
+    pub reader: BoxReader,
+    pub writer: BoxWriter,
+    pub transport: Box<dyn BridgeTransport>,
+}
+
+/// Opens a fresh connection for each (re)start of a bridge the shell does not
+/// spawn itself.
+pub type Connector = Arc<dyn Fn() -> BoxFuture<'static, Result<Connection, SpawnError>> + Send + Sync>;
+
+/// Receives the bridge's stderr one line at a time (see `crate::logs`).
+pub type LineSink = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Longest stderr line passed on whole; longer ones arrive in pieces.
+const MAX_LINE_BYTES: u64 = 16 * 1024;
+
+// ── Child process over stdio ───────────────────────────────────
+
+/// How the bridge process is started besides its command line.
+pub struct ChildOptions {
+    /// Its whole environment.
+    pub env: ChildEnv,
+    pub sandbox: Sandbox,
+}
+
+pub struct StdioTransport {
+    child: tokio::sync::Mutex<Child>,
+    script: PathBuf,
+    /// The child leads its own process group; signals go to the whole group.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    process_group: bool,
+}
+
+impl StdioTransport {
+    /// Run `script` on `node` with piped stdio, set up as `options` say. The
+    /// protocol runs over stdin/stdout; stderr is read line by line into
+    /// `stderr`.
+    pub fn spawn(node: &Path, script: &Path, options: &ChildOptions, stderr: LineSink) -> Result<Connection, SpawnError> {
+        let mut command = Command::new(node);
+        options.sandbox.apply(&mut command);
+        let mut child = command
+            .arg(script)
+            .env_clear()
+            .envs(options.env.vars().iter().map(|(name, value)| (name, value)))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| {
+                format!(
+                    "Failed to spawn bridge: {}. Node: {}. Script: {}",
+                    e,
+                    node.display(),
+                    script.display()
+                )
+            })?;
+
+        let writer = child.stdin.take().ok_or("No stdin")?;
+        let reader = child.stdout.take().ok_or("No stdout")?;
+        tokio::spawn(forward_lines(child.stderr.take().ok_or("No stderr")?, stderr));
+        Ok(Connection {
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+            transport: Box::new(StdioTransport {
+                child: tokio::sync::Mutex::new(child),
+                script: script.to_path_buf(),
+                process_group: options.sandbox.process_group(),
+            }),
+        })
+    }
+}
+
+/// Hand each line of `stderr` to `sink` until the pipe closes.
+async fn forward_lines(stderr: ChildStderr, sink: LineSink) {
+    let mut reader = BufReader::new(stderr);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match (&mut reader).take(MAX_LINE_BYTES).read_until(b'\n', &mut line).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => sink(String::from_utf8_lossy(&line).trim_end_matches(['\r', '\n'])),
+        }
+    }
+}
+
+impl BridgeTransport for StdioTransport {
+    fn describe(&self) -> String {
+        self.script.display().to_string()
+    }
+
+    fn wait(&self, timeout: Duration) -> BoxFuture<'_, Option<String>> {
+        Box::pin(async move {
+            let mut child = self.child.lock().await;
+            match tokio::time::timeout(timeout, child.wait()).await {
+                Ok(Ok(status)) => Some(format!("Bridge process exited ({})", status)),
+                Ok(Err(e)) => Some(format!("Bridge process exited (status unavailable: {})", e)),
+                Err(_) => None,
+            }
+        })
+    }
+
+    #[cfg(unix)]
+    fn terminate(&self) -> bool {
+        self.child.try_lock().is_ok_and(|child| self.signal(&child, libc::SIGTERM))
+    }
+
+    #[cfg(not(unix))]
+    fn terminate(&self) -> bool {
+        false
+    }
+
+    fn kill(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async move {
+            let mut child = self.child.lock().await;
+            #[cfg(unix)]
+            if self.process_group {
+                self.signal(&child, libc::SIGKILL);
+            }
+            // `kill` also waits, so the process is reaped either way.
+            if let Err(e) = child.kill().await {
+                tracing::warn!(error = %e, "Failed to kill bridge process");
+            }
+        })
+    }
+
+    fn abort(&self) {
+        // If the reader task holds the child it is already reaping it; otherwise
+        // kill here. Either way tokio reaps the process in the background.
+        if let Ok(mut child) = self.child.try_lock() {
+            #[cfg(unix)]
+            if self.process_group {
+                self.signal(&child, libc::SIGKILL);
+            }
+            let _ = child.start_kill();
+        }
+    }
+}
+
+#[cfg(unix)]
+impl StdioTransport {
+    /// Send `signal` to the child, or to its process group if it leads one.
+    fn signal(&self, child: &Child, signal: libc::c_int) -> bool {
+        // Only signal a pid we still own: `id()` is None once the child is reaped.
+        let Some(pid) = child.id() else {
+            return false;
+        };
+        let target = if self.process_group { -(pid as libc::pid_t) } else { pid as libc::pid_t };
+        // SAFETY: plain kill(2) on a pid (group) we spawned and have not yet reaped.
+        unsafe { libc::kill(target, signal) == 0 }
+    }
+}
+
+// ── Unix domain socket ─────────────────────────────────────────
+
+/// A bridge someone else started, listening on a socket (see
+/// `OPENQUERY_BRIDGE_SOCKET` in bridge/main.ts). The shell does not own the
+/// process, so stopping it only closes the connection.
+#[cfg(unix)]
+pub struct UnixSocketTransport {
+    path: PathBuf,
+}
+
+#[cfg(unix)]
+impl UnixSocketTransport {
+    /// Connect to the socket at `path` on every (re)start.
+    pub fn connector(path: PathBuf) -> Connector {
+        Arc::new(move || {
+            let path = path.clone();
+            Box::pin(async move { Self::connect(&path).await })
+        })
+    }
+
+    pub async fn connect(path: &Path) -> Result<Connection, SpawnError> {
+        let stream = tokio::net::UnixStream::connect(path)
+            .await
+            .map_err(|e| format!("Failed to connect to bridge socket {}: {}", path.display(), e))?;
+        let (reader, writer) = stream.into_split();
+        Ok(Connection {
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+            transport: Box::new(UnixSocketTransport {
+                path: path.to_path_buf(),
+            }),
+        })
+    }
+}
+
+#[cfg(unix)]
+impl BridgeTransport for UnixSocketTransport {
+    fn describe(&self) -> String {
+        format!("socket {}", self.path.display())
+    }
+
+    fn wait(&self, _timeout: Duration) -> BoxFuture<'_, Option<String>> {
+        Box::pin(async move { Some(format!("Bridge closed socket {}", self.path.display())) })
+    }
+
+    fn terminate(&self) -> bool {
+        false
+    }
+
+    fn kill(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+
+    fn abort(&self) {}
+}
+
+// ── In memory ──────────────────────────────────────────────────
+
+/// A connection to something in this process: a replayed recording (see
+/// `crate::recording`) or the scripted bridge in `mock.rs`.
+pub struct MemoryTransport;
+
+impl MemoryTransport {
+    /// A connected pair: the shell's `Connection`, and the stream the other
+    /// side reads requests from and writes responses to.
+    pub fn pair() -> (Connection, tokio::io::DuplexStream) {
+        let (shell, peer) = tokio::io::duplex(64 * 1024);
+        let (reader, writer) = tokio::io::split(shell);
+        let connection = Connection {
+            reader: Box::new(reader),
+            writer: Box::new(writer),
+            transport: Box::new(MemoryTransport),
+        };
+        (connection, peer)
+    }
+}
+
+impl BridgeTransport for MemoryTransport {
+    fn describe(&self) -> String {
+        "in-memory".to_string()
+    }
+
+    fn wait(&self, _timeout: Duration) -> BoxFuture<'_, Option<String>> {
+        Box::pin(async { Some("In-memory bridge closed".to_string()) })
+    }
+
+    fn terminate(&self) -> bool {
+        false
+    }
+
+    fn kill(&self) -> BoxFuture<'_, ()> {
+        Box::pin(async {})
+    }
+
+    fn abort(&self) {}
+}
@@ -5,7 +5,7 @@ import ProfilesPage from './pages/ProfilesPage';
 import HistoryPage from './pages/HistoryPage';
 import SettingsPage from './pages/SettingsPage';
 import QuickstartPage from './pages/QuickstartPage';
-import { getOpenAIKey } from './lib/secretStore';
+import { hasOpenAIKey } from './lib/secretStore';
 
 type Page = 'workspace' | 'setup' | 'profiles' | 'history' | 'settings';
 type ConnectionStatus = 'unknown' | 'ok' | 'error';
@@ -21,6 +21,13 @@ interface WorkspaceDraft {
   sql?: string;
 }
 
+interface TypedPassword {
+  profile: string | null;
+  value: string;
+  /** Changed since it was last handed to the shell. */
+  edited: boolean;
+}
+
 export interface SafePolicySettings {
   maxRowsThreshold: number;
   maxCostThreshold: number;
@@ -35,6 +42,7 @@ const DEFAULT_POLICY: SafePolicySettings = {
 
 const POLICY_STORAGE_KEY = 'openquery.safe-policy.v1';
 const STALE_SCHEMA_MS = 24 * 60 * 60 * 1000;
+const BRIDGE_HEALTH_POLL_MS = 10_000;
 
 interface SetupState {
   needsSetup: boolean;
@@ -63,7 +71,12 @@ function loadStoredPolicy(): SafePolicySettings {
 
 export default function App() {
   const [page, setPage] = useState<Page>('workspace');
-  const [password, setPassword] = useState('');
+  // The top-bar password and the profile it was typed for. Switching profiles
+  // clears it, so it never becomes another profile's password.
+  const [typedPassword, setTypedPassword] = useState<TypedPassword>({ profile: null, value: '', edited: false });
+  // Profiles the shell holds a session password for.
+  const [sessionPasswordProfiles, setSessionPasswordProfiles] = useState<string[]>([]);
+  const [passwordSaved, setPasswordSaved] = useState(false);
   const [profiles, setProfiles] = useState<ProfileSummary[]>([]);
   const [activeProfile, setActiveProfile] = useState<string | null>(null);
   const [connectionStatus, setConnectionStatus] = useState<ConnectionStatus>('unknown');
@@ -81,6 +94,8 @@ export default function App() {
   const [helpOpen, setHelpOpen] = useState(false);
   const [aiReady, setAiReady] = useState(false);
   const [refreshingSchema, setRefreshingSchema] = useState(false);
+  const [bridgeStatus, setBridgeStatus] = useState<api.BridgeStatus | null>(null);
+  const [bridgeHealth, setBridgeHealth] = useState<api.BridgeHealth | null>(null);
 
   const navItems: Array<{ id: Page; label: string; meta?: string | null }> = useMemo(
     () => [
@@ -97,10 +112,50 @@ export default function App() {
     [profiles, activeProfile],
   );
 
+  const password = typedPassword.profile === activeProfile ? typedPassword.value : '';
+  const passwordHeld = activeProfile !== null && sessionPasswordProfiles.includes(activeProfile);
+  const hasPassword = Boolean(password.trim()) || passwordHeld || passwordSaved;
+
   useEffect(() => {
     localStorage.setItem(POLICY_STORAGE_KEY, JSON.stringify(safePolicy));
   }, [safePolicy]);
 
+  useEffect(() => {
+    setTypedPassword({ profile: activeProfile, value: '', edited: false });
+  }, [activeProfile]);
+
+  // The shell keeps the session password and adds it to database calls, which
+  // only name the profile; a password saved in the keychain is used otherwise.
+  // It is handed over on blur or Enter, and before any database call.
+  const submitPassword = async (): Promise<void> => {
+    const { profile, value, edited } = typedPassword;
+    if (!profile || profile !== activeProfile || !edited) return;
+    const trimmed = value.trim();
+    try {
+      await api.sessionPasswordSet(profile, trimmed);
+    } catch {
+      // Without the desktop shell there is nothing to connect with anyway.
+      return;
+    }
+    setTypedPassword((prev) => (prev.profile === profile && prev.value === value ? { ...prev, edited: false } : prev));
+    setSessionPasswordProfiles((prev) => {
+      const others = prev.filter((name) => name !== profile);
+      return trimmed ? [...others, profile] : others;
+    });
+  };
+
+  useEffect(() => {
+    const profileId = profiles.find((profile) => profile.name === activeProfile)?.id;
+    if (!profileId) {
+      setPasswordSaved(false);
+      return;
+    }
+    api
+      .keychainHas(profileId)
+      .then(setPasswordSaved)
+      .catch(() => setPasswordSaved(false));
+  }, [profiles, activeProfile]);
+
   const evaluateSetupState = async (
     nextProfiles: ProfileSummary[],
     nextActive: string | null,
@@ -129,8 +184,8 @@ export default function App() {
     } else {
       try {
         const snapshot = await api.schemaGetSnapshot();
-        const hasTables = Array.isArray(snapshot?.tables) && snapshot.tables.length > 0;
-        const capturedAtRaw = typeof snapshot?.capturedAt === 'string' ? snapshot.capturedAt : null;
+        const hasTables = (snapshot?.tables.length ?? 0) > 0;
+        const capturedAtRaw = snapshot?.capturedAt ?? null;
         const capturedAtMs = capturedAtRaw ? Date.parse(capturedAtRaw) : Number.NaN;
         const stale = !Number.isFinite(capturedAtMs) || Date.now() - capturedAtMs > STALE_SCHEMA_MS;
         next = {
@@ -162,10 +217,10 @@ export default function App() {
         api.profilesList(),
         api.profilesGetActive(),
       ]);
-      const typed = allProfiles.map((p: any) => ({
-        id: p.id as string,
-        name: p.name as string,
-        db_type: (p.db_type as string) || 'postgres',
+      const typed = allProfiles.map((p) => ({
+        id: p.id,
+        name: p.name,
+        db_type: p.db_type || 'postgres',
       }));
       setProfiles(typed);
       setActiveProfile(active.name);
@@ -181,15 +236,63 @@ export default function App() {
     }
   };
 
+  /** The bridge status once the launch the shell starts with the window has finished. */
+  const bridgeLaunched = async (): Promise<api.BridgeStatus> => {
+    let stop: (() => void) | undefined;
+    // Listen first, so the event cannot slip between the check and the wait.
+    const launched = new Promise<void>((resolve) => {
+      void api
+        .onBridgeStatus((event) => {
+          if (event.status === 'launched') resolve();
+        })
+        .then((unlisten) => {
+          stop = unlisten;
+        });
+    });
+    try {
+      const status = await api.bridgeStatus();
+      if (!status.starting) return status;
+      await launched;
+      return await api.bridgeStatus();
+    } finally {
+      stop?.();
+    }
+  };
+
   useEffect(() => {
-    void syncProfileState(true);
+    const start = async (): Promise<void> => {
+      try {
+        const status = await bridgeLaunched();
+        setBridgeStatus(status);
+        if (!status.running) {
+          // Nothing else works without the bridge; Setup explains how to fix it.
+          setPage('setup');
+          setSetupChecked(true);
+          return;
+        }
+      } catch {
+        // Fall through: the profile sync below surfaces any bridge error itself.
+      }
+      await syncProfileState(true);
+    };
+    void start();
   }, []);
 
+  const handleBridgeRetry = async (): Promise<api.BridgeStatus> => {
+    const status = await api.bridgeRetry();
+    setBridgeStatus(status);
+    if (status.running) {
+      setTopError('');
+      await syncProfileState(true);
+    }
+    return status;
+  };
+
   useEffect(() => {
     const refreshAiStatus = async (): Promise<void> => {
       try {
-        const [stored, settings] = await Promise.all([getOpenAIKey(), api.settingsStatus()]);
-        setAiReady(Boolean(stored) || Boolean(settings.openAiKeySet));
+        const [stored, settings] = await Promise.all([hasOpenAIKey(), api.settingsStatus()]);
+        setAiReady(stored || Boolean(settings.openAiKeySet));
       } catch {
         setAiReady(false);
       }
@@ -197,6 +300,27 @@ export default function App() {
     void refreshAiStatus();
   }, [page]);
 
+  useEffect(() => {
+    const refreshHealth = async (): Promise<void> => {
+      try {
+        setBridgeHealth(await api.bridgeHealth());
+      } catch {
+        setBridgeHealth(null);
+      }
+    };
+    void refreshHealth();
+    const timer = window.setInterval(() => void refreshHealth(), BRIDGE_HEALTH_POLL_MS);
+    // Crashes, restarts and missed heartbeats update the pill right away.
+    const unlisten = api.onBridgeStatus(() => void refreshHealth());
+    // Development reloads of the bridge swap it out without touching UI state.
+    const unlistenReload = api.onBridgeReloaded(() => void refreshHealth());
+    return () => {
+      window.clearInterval(timer);
+      void unlisten.then((stop) => stop());
+      void unlistenReload.then((stop) => stop());
+    };
+  }, []);
+
   useEffect(() => {
     const loadPower = async (): Promise<void> => {
       if (!activeProfile) {
@@ -233,15 +357,15 @@ export default function App() {
       return;
     }
     const profileType = profiles.find((p) => p.name === activeProfile)?.db_type ?? 'postgres';
-    const resolvedPassword = profileType === 'sqlite' ? '' : password.trim();
-    if (profileType !== 'sqlite' && !resolvedPassword) {
+    if (profileType !== 'sqlite' && !hasPassword) {
       setTopError('Enter a session password to test the active profile.');
       return;
     }
+    await submitPassword();
     setTopError('');
     setConnectionStatus('unknown');
     try {
-      const result = await api.profilesTest(activeProfile, resolvedPassword);
+      const result = await api.profilesTest(activeProfile);
       setConnectionStatus(result.ok ? 'ok' : 'error');
       if (!result.ok) {
         setTopError(result.error || 'Connection test failed.');
@@ -259,15 +383,15 @@ export default function App() {
       return;
     }
     const profileType = profiles.find((p) => p.name === activeProfile)?.db_type ?? 'postgres';
-    const resolvedPassword = profileType === 'sqlite' ? '' : password.trim();
-    if (profileType !== 'sqlite' && !resolvedPassword) {
+    if (profileType !== 'sqlite' && !hasPassword) {
       setTopError('Enter session password, then click Refresh schema.');
       return;
     }
+    await submitPassword();
     setRefreshingSchema(true);
     setTopError('');
     try {
-      await api.schemaRefresh(resolvedPassword, activeProfile);
+      await api.schemaRefresh(activeProfile);
       await evaluateSetupState(profiles, activeProfile, false);
     } catch (err: unknown) {
       const msg = err instanceof Error ? err.message : String(err);
@@ -282,6 +406,27 @@ export default function App() {
       ? 'Schema stale'
       : 'Schema missing'
     : 'Schema ready';
+  const bridgeHealthClass =
+    bridgeHealth?.status === 'healthy'
+      ? 'status-ok'
+      : bridgeHealth?.status === 'unresponsive'
+        ? 'status-bridge-warn'
+        : 'status-error';
+  const bridgeHealthLabel =
+    bridgeHealth?.status === 'healthy'
+      ? 'Bridge OK'
+      : bridgeHealth?.status === 'unresponsive'
+        ? 'Bridge not responding'
+        : 'Bridge down';
+  const bridgeHealthTitle = bridgeHealth
+    ? [
+        bridgeHealth.latencyMs != null ? `Ping ${Math.round(bridgeHealth.latencyMs)} ms` : null,
+        bridgeHealth.lastSeenMs != null ? `last heard ${Math.round(bridgeHealth.lastSeenMs / 1000)} s ago` : null,
+        bridgeHealth.inFlight > 0 ? `${bridgeHealth.inFlight} request(s) in flight` : null,
+      ]
+        .filter(Boolean)
+        .join(', ')
+    : undefined;
 
   return (
     <div className="app-shell">
@@ -341,8 +486,14 @@ export default function App() {
                 <input
                   type="password"
                   value={password}
-                  onChange={(e) => setPassword(e.target.value)}
-                  placeholder="Required for DB actions"
+                  onChange={(e) => setTypedPassword({ profile: activeProfile, value: e.target.value, edited: true })}
+                  onBlur={() => void submitPassword()}
+                  onKeyDown={(e) => {
+                    if (e.key === 'Enter') void submitPassword();
+                  }}
+                  placeholder={
+                    passwordHeld ? 'Set for this session' : passwordSaved ? 'Saved in keychain' : 'Required for DB actions'
+                  }
                 />
               </label>
               <span className={`status-pill mode-${powerEnabled ? 'power' : 'safe'}`}>
@@ -379,6 +530,12 @@ export default function App() {
               </div>
             </div>
             <div className="top-status top-status-row">
+              {bridgeHealth && (
+                <span className={`status-pill ${bridgeHealthClass}`} title={bridgeHealthTitle}>
+                  <span className="status-dot" />
+                  {bridgeHealthLabel}
+                </span>
+              )}
               <span className={`status-pill status-${connectionStatus}`}>
                 <span className="status-dot" />
                 {connectionStatus === 'ok' ? 'Connected' : connectionStatus === 'error' ? 'Connection failed' : 'Connection unknown'}
@@ -411,7 +568,8 @@ export default function App() {
           <div className="main-content-inner">
             {page === 'workspace' && (
               <WorkspacePage
-                password={password}
+                hasPassword={hasPassword}
+                onSubmitPassword={submitPassword}
                 activeProfile={activeProfile}
                 activeProfileType={activeProfileType}
                 safePolicy={safePolicy}
@@ -428,6 +586,8 @@ export default function App() {
                 activeProfile={activeProfile}
                 profiles={profiles}
                 setupState={setupState}
+                bridgeStatus={bridgeStatus}
+                onBridgeRetry={handleBridgeRetry}
                 onReloadProfileState={() => syncProfileState(false)}
                 onNavigate={setPage}
               />
@@ -438,6 +598,7 @@ export default function App() {
                 activeProfile={activeProfile}
                 onProfilesChanged={(nextProfiles, nextActive) => {
                   setProfiles(nextProfiles);
+                  setSessionPasswordProfiles((prev) => prev.filter((name) => nextProfiles.some((p) => p.name === name)));
                   setActiveProfile(nextActive);
                   void evaluateSetupState(nextProfiles, nextActive, false);
                 }}
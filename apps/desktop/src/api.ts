@@ -1,9 +1,12 @@
 /**
  * Tauri invoke wrappers for the desktop app.
- * Each function maps to a Tauri command defined in main.rs.
+ * Each function maps to a Tauri command defined in main.rs; names, arguments and
+ * results are checked against the generated `bindings.ts`.
  */
 
-import { invoke } from '@tauri-apps/api/core';
+import { invoke, type InvokeArgs } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import type * as B from './bindings';
 
 function hasTauriBridge(): boolean {
   if (typeof window === 'undefined') return true;
@@ -15,18 +18,197 @@ function hasTauriBridge(): boolean {
   return typeof maybeWindow.__TAURI_INTERNALS__?.invoke === 'function';
 }
 
-async function invokeCommand<T>(command: string, args?: Record<string, unknown>): Promise<T> {
+export type BridgeErrorKind = B.BridgeErrorKind;
+
+/** Stable codes carried by `remote` errors (set by the Node bridge). */
+export const ErrorCode = {
+  AuthFailed: 'AUTH_FAILED',
+  ConnectionRefused: 'CONNECTION_REFUSED',
+  PolicyBlocked: 'POLICY_BLOCKED',
+  LlmQuota: 'LLM_QUOTA',
+  LlmKeyMissing: 'LLM_KEY_MISSING',
+  Timeout: 'TIMEOUT',
+  Cancelled: 'CANCELLED',
+} as const;
+
+/** Error raised by Tauri commands: `{kind, code, message, details}` from the Rust side. */
+export class BridgeCallError extends Error {
+  readonly kind: BridgeErrorKind;
+  readonly code: string;
+  readonly details: unknown;
+
+  constructor(kind: BridgeErrorKind, code: string, message: string, details?: unknown) {
+    super(message);
+    this.name = 'BridgeCallError';
+    this.kind = kind;
+    this.code = code;
+    this.details = details ?? null;
+  }
+}
+
+export function errorCode(err: unknown): string | null {
+  return err instanceof BridgeCallError ? err.code : null;
+}
+
+export function isCancelled(err: unknown): boolean {
+  return err instanceof BridgeCallError && err.kind === 'cancelled';
+}
+
+function toError(err: unknown): unknown {
+  if (err && typeof err === 'object' && 'kind' in err && 'message' in err) {
+    const { kind, code, message, details } = err as B.BridgeError;
+    return new BridgeCallError(kind, code, message, details);
+  }
+  return err;
+}
+
+type CommandName = keyof B.Commands;
+type CommandArgs<K extends CommandName> = B.Commands[K]['args'];
+type CommandResult<K extends CommandName> = B.Commands[K]['result'];
+
+async function invokeCommand<K extends CommandName>(
+  command: K,
+  ...args: Record<string, never> extends CommandArgs<K> ? [CommandArgs<K>?] : [CommandArgs<K>]
+): Promise<CommandResult<K>> {
   if (!hasTauriBridge()) {
     throw new Error(
       'Desktop bridge unavailable. Launch OpenQuery with `pnpm --filter @openquery/desktop dev:tauri`.',
     );
   }
-  return invoke<T>(command, args);
+  try {
+    return await invoke<CommandResult<K>>(command, args[0] as InvokeArgs | undefined);
+  } catch (err: unknown) {
+    throw toError(err);
+  }
+}
+
+/** Id for a cancellable request; pass it to the command and, to stop it, to `bridgeCancel`. */
+export function newRequestId(): string {
+  return crypto.randomUUID();
+}
+
+export type BridgeInfo = B.ShellInfo;
+
+export async function bridgeInfo(): Promise<BridgeInfo> {
+  return invokeCommand('bridge_info');
+}
+
+/** True if the running bridge implements `method` (e.g. `'write.preview'`). */
+export function bridgeSupports(info: BridgeInfo | null, method: string): boolean {
+  return info?.methods.includes(method) ?? false;
+}
+
+export async function bridgeCancel(requestId: string): Promise<boolean> {
+  return invokeCommand('bridge_cancel', { requestId });
+}
+
+export type BridgeStatus = B.BridgeStatus;
+
+/** Whether the bridge is running or still starting; if it failed to launch, why. */
+export async function bridgeStatus(): Promise<BridgeStatus> {
+  return invokeCommand('bridge_status');
+}
+
+export type BridgeHealth = B.BridgeHealth;
+
+/** Whether the running bridge answers its heartbeat, with the latest round trip. */
+export async function bridgeHealth(): Promise<BridgeHealth> {
+  return invokeCommand('bridge_health');
+}
+
+/** Relaunch the bridge, e.g. after installing Node or setting its path. */
+export async function bridgeRetry(): Promise<BridgeStatus> {
+  return invokeCommand('bridge_retry');
+}
+
+export type StartupDiagnostics = B.StartupDiagnostics;
+
+/** Bridge script and every Node runtime tried at the last bridge launch. */
+export async function diagnosticsStartup(): Promise<StartupDiagnostics> {
+  return invokeCommand('diagnostics_startup');
+}
+
+export type MetricsReport = B.MetricsReport;
+export type MethodMetrics = B.MethodMetrics;
+
+export async function diagnosticsMetrics(): Promise<MetricsReport> {
+  return invokeCommand('diagnostics_metrics');
+}
+
+// ── Logs ────────────────────────────────────────────────────────
+
+export type LogSource = 'bridge' | 'shell';
+export type LogLine = B.LogLine;
+export type LogExport = B.LogExport;
+
+/** The most recent captured lines from `source` (default 200), oldest first. */
+export async function logsTail(source: LogSource, lines?: number): Promise<LogLine[]> {
+  return invokeCommand('logs_tail', { source, lines: lines ?? null });
+}
+
+/** Write every captured log to one text file for a bug report. */
+export async function logsExport(): Promise<LogExport> {
+  return invokeCommand('logs_export');
+}
+
+// ── Shell settings ──────────────────────────────────────────────
+
+export type ShellSettings = B.ShellSettings;
+
+export async function shellSettingsGet(): Promise<ShellSettings> {
+  return invokeCommand('shell_settings_get');
+}
+
+export async function shellSettingsSet(settings: ShellSettings): Promise<void> {
+  await invokeCommand('shell_settings_set', { settings });
+}
+
+// ── Bridge lifecycle ────────────────────────────────────────────
+
+export type BridgeStatusEvent =
+  | { status: 'crashed'; reason: string }
+  | { status: 'restarting'; attempt: number; delayMs: number }
+  | { status: 'restarted'; attempt: number }
+  | { status: 'gaveUp'; reason: string; failures: number }
+  | { status: 'unresponsive'; missed: number; lastSeenMs: number | null }
+  | { status: 'responsive'; latencyMs: number }
+  | { status: 'launched'; running: boolean; error: string | null };
+
+export async function onBridgeStatus(handler: (event: BridgeStatusEvent) => void): Promise<UnlistenFn> {
+  if (!hasTauriBridge()) return () => {};
+  return listen<BridgeStatusEvent>('bridge://status', (event) => handler(event.payload));
+}
+
+/** Sent in debug builds when the bridge restarts after its build output changed. */
+export interface BridgeReloadedEvent {
+  changed: string[];
+  durationMs: number;
+}
+
+export async function onBridgeReloaded(handler: (event: BridgeReloadedEvent) => void): Promise<UnlistenFn> {
+  if (!hasTauriBridge()) return () => {};
+  return listen<BridgeReloadedEvent>('bridge://reloaded', (event) => handler(event.payload));
+}
+
+/** Progress notification for one in-flight request (stage updates, row batches). */
+export type BridgeProgress =
+  | { stage: 'rows'; columns: string[]; offset: number; rows: Record<string, unknown>[] }
+  | { stage: string; [key: string]: unknown };
+
+export async function onBridgeProgress(
+  requestId: string,
+  handler: (progress: BridgeProgress) => void,
+): Promise<UnlistenFn> {
+  if (!hasTauriBridge()) return () => {};
+  return listen<BridgeProgress>(`bridge://progress/${requestId}`, (event) => handler(event.payload));
 }
 
 // ── Profiles ────────────────────────────────────────────────────
 
-export async function profilesList(): Promise<any[]> {
+export type Profile = B.Profile;
+export type ListedProfile = B.ListedProfile;
+
+export async function profilesList(): Promise<B.ListedProfile[]> {
   return invokeCommand('profiles_list');
 }
 
@@ -38,99 +220,107 @@ export async function profilesAdd(params: {
   database: string;
   user: string;
   ssl: boolean;
-}): Promise<any> {
+}): Promise<B.Profile> {
   return invokeCommand('profiles_add', { params });
 }
 
-export async function profilesRemove(name: string): Promise<any> {
+export async function profilesRemove(name: string): Promise<B.Removed> {
   return invokeCommand('profiles_remove', { name });
 }
 
-export async function profilesUse(name: string): Promise<any> {
+export async function profilesUse(name: string): Promise<B.Ack> {
   return invokeCommand('profiles_use', { name });
 }
 
-export async function profilesTest(name: string, password: string): Promise<{ ok: boolean; error?: string; serverVersion?: string }> {
-  return invokeCommand('profiles_test', { name, password });
+export async function profilesTest(name: string): Promise<B.ConnectionTest> {
+  return invokeCommand('profiles_test', { name });
 }
 
-export async function profilesGetActive(): Promise<{ name: string | null }> {
+export async function profilesGetActive(): Promise<B.ActiveProfile> {
   return invokeCommand('profiles_get_active');
 }
 
 // ── Keychain ────────────────────────────────────────────────────
+// Secrets go in but never come back out: database commands take a profile
+// name and the shell looks the password up itself.
 
 export async function keychainSet(profileId: string, password: string): Promise<void> {
-  return invokeCommand('keychain_set', { profileId, password });
+  await invokeCommand('keychain_set', { profileId, password });
 }
 
-export async function keychainGet(profileId: string): Promise<string | null> {
-  return invokeCommand('keychain_get', { profileId });
+export async function keychainHas(profileId: string): Promise<boolean> {
+  return invokeCommand('keychain_has', { profileId });
 }
 
 export async function keychainDelete(profileId: string): Promise<void> {
-  return invokeCommand('keychain_delete', { profileId });
+  await invokeCommand('keychain_delete', { profileId });
+}
+
+/** Use `password` for profile `name` until the app exits; empty forgets it. */
+export async function sessionPasswordSet(name: string, password: string): Promise<void> {
+  await invokeCommand('session_password_set', { name, password });
 }
 
 // ── Schema ──────────────────────────────────────────────────────
 
-export async function schemaRefresh(password: string, name?: string): Promise<{ tables: number; columns: number }> {
-  return invokeCommand('schema_refresh', { password, name: name ?? null });
+export type SchemaMatch = B.SchemaMatch;
+export type SchemaSnapshot = B.SchemaSnapshot;
+export type TableInfo = B.TableInfo;
+
+export async function schemaRefresh(name?: string, requestId?: string): Promise<B.SchemaRefreshSummary> {
+  return invokeCommand('schema_refresh', { name: name ?? null, requestId: requestId ?? null });
 }
 
-export async function schemaSearch(query: string): Promise<any[]> {
+export async function schemaSearch(query: string): Promise<B.SchemaMatch[]> {
   return invokeCommand('schema_search', { query });
 }
 
-export async function schemaTableDetail(table: string, schema?: string): Promise<any> {
+export async function schemaTableDetail(table: string, schema?: string): Promise<B.TableInfo> {
   return invokeCommand('schema_table_detail', { table, schema: schema ?? null });
 }
 
-export async function schemaGetSnapshot(): Promise<any> {
+export async function schemaGetSnapshot(): Promise<B.SchemaSnapshot | null> {
   return invokeCommand('schema_get_snapshot');
 }
 
 // ── Ask ─────────────────────────────────────────────────────────
 
-export async function askDryRun(
-  question: string,
-  mode: string,
-  password: string,
-  openAiApiKey?: string | null,
-): Promise<any> {
-  return invokeCommand('ask_dry_run', { question, mode, password, openAiApiKey: openAiApiKey ?? null });
+export type AskResult = B.AskResult;
+export type QueryResult = B.QueryResult;
+
+export async function askDryRun(question: string, mode: string, name?: string, requestId?: string): Promise<B.AskResult> {
+  return invokeCommand('ask_dry_run', { question, mode, name: name ?? null, requestId: requestId ?? null });
 }
 
-export async function askRun(
-  question: string,
-  mode: string,
-  password: string,
-  openAiApiKey?: string | null,
-): Promise<any> {
-  return invokeCommand('ask_run', { question, mode, password, openAiApiKey: openAiApiKey ?? null });
+export async function askRun(question: string, mode: string, name?: string, requestId?: string): Promise<B.AskResult> {
+  return invokeCommand('ask_run', { question, mode, name: name ?? null, requestId: requestId ?? null });
 }
 
 // ── Workspace SQL ───────────────────────────────────────────────
 
+export type WorkspaceSqlResult = B.WorkspaceSqlResult;
+
 export async function workspaceSql(params: {
   sql: string;
   mode: 'safe' | 'standard';
   action: 'run' | 'dry-run' | 'explain';
-  password: string;
   name?: string;
   policy?: {
     maxRowsThreshold: number;
     maxCostThreshold: number;
     enforceLimit: boolean;
   };
-}): Promise<any> {
+  requestId?: string;
+  streamRows?: boolean;
+}): Promise<B.WorkspaceSqlResult> {
   return invokeCommand('workspace_sql', {
     sql: params.sql,
     mode: params.mode,
     action: params.action,
-    password: params.password,
     name: params.name ?? null,
     policy: params.policy ?? null,
+    requestId: params.requestId ?? null,
+    streamRows: params.streamRows ?? null,
   });
 }
 
@@ -139,43 +329,32 @@ export async function workspaceSql(params: {
 export async function profileUpdatePower(
   name: string,
   settings: { allowWrite?: boolean; allowDangerous?: boolean; confirmPhrase?: string | null },
-): Promise<any> {
+): Promise<B.Ack> {
   return invokeCommand('profile_update_power', { name, settings });
 }
 
-export async function profileGetPower(name: string): Promise<{
-  allowWrite: boolean;
-  allowDangerous: boolean;
-  confirmPhrase: string | null;
-}> {
+export async function profileGetPower(name: string): Promise<B.PowerSettings> {
   return invokeCommand('profile_get_power', { name });
 }
 
-export async function writePreview(
-  sql: string,
-  params: unknown[],
-  password: string,
-  name?: string,
-): Promise<any> {
-  return invokeCommand('write_preview', { sql, params, password, name: name ?? null });
+export type WritePreviewResult = B.WritePreviewResult;
+export type WriteExecution = B.WriteExecution;
+
+export async function writePreview(sql: string, params: unknown[], name?: string, requestId?: string): Promise<B.WritePreviewResult> {
+  return invokeCommand('write_preview', { sql, params, name: name ?? null, requestId: requestId ?? null });
 }
 
-export async function writeExecute(
-  sql: string,
-  params: unknown[],
-  password: string,
-  name?: string,
-): Promise<any> {
-  return invokeCommand('write_execute', { sql, params, password, name: name ?? null });
+export async function writeExecute(sql: string, params: unknown[], name?: string, requestId?: string): Promise<B.WriteExecution> {
+  return invokeCommand('write_execute', { sql, params, name: name ?? null, requestId: requestId ?? null });
 }
 
 // ── History ─────────────────────────────────────────────────────
 
-export async function historyList(limit?: number): Promise<any[]> {
+export async function historyList(limit?: number): Promise<B.HistoryListItem[]> {
   return invokeCommand('history_list', { limit: limit ?? null });
 }
 
-export async function historyShow(id: string): Promise<any> {
+export async function historyShow(id: string): Promise<B.HistoryDetail> {
   return invokeCommand('history_show', { id });
 }
 
@@ -185,82 +364,57 @@ export async function historyExportMd(id: string): Promise<string> {
 
 // ── Settings ────────────────────────────────────────────────────
 
-export async function settingsStatus(): Promise<{
-  openAiKeySet: boolean;
-  model: string;
-  appVersion: string;
-  defaults: {
-    maxRowsThreshold: number;
-    maxCostThreshold: number;
-    enforceLimit: boolean;
-  };
-}> {
+export async function settingsStatus(): Promise<B.SettingsStatusResult> {
   return invokeCommand('settings_status');
 }
 
-export async function settingsTestOpenAiKey(apiKey?: string | null): Promise<{ ok: boolean; message: string }> {
+export async function settingsTestOpenAiKey(apiKey?: string | null): Promise<B.KeyCheck> {
   return invokeCommand('settings_test_openai_key', { apiKey: apiKey ?? null });
 }
 
+/** Save the OpenAI key where only the shell reads it. */
+export async function settingsSetOpenAiKey(apiKey: string): Promise<void> {
+  await invokeCommand('settings_set_openai_key', { apiKey });
+}
+
+export async function settingsClearOpenAiKey(): Promise<void> {
+  await invokeCommand('settings_clear_openai_key');
+}
+
 // ── Setup + fixture ─────────────────────────────────────────────
 
-export async function demoNoDockerStatus(): Promise<{
-  ready: boolean;
-  dbPath: string;
-  active: boolean;
-  profileName: string;
-}> {
+export async function demoNoDockerStatus(): Promise<B.DemoStatus> {
   return invokeCommand('demo_no_docker_status');
 }
 
-export async function demoNoDockerPrepare(reset = false): Promise<{
-  ready: boolean;
-  dbPath: string;
-  profileName: string;
-}> {
+export async function demoNoDockerPrepare(reset = false): Promise<B.DemoReady> {
   return invokeCommand('demo_no_docker_prepare', { reset });
 }
 
-export async function demoNoDockerReset(): Promise<{
-  ready: boolean;
-  dbPath: string;
-  profileName: string;
-}> {
+export async function demoNoDockerReset(): Promise<B.DemoReady> {
   return invokeCommand('demo_no_docker_reset');
 }
 
-export async function fixtureCheckDocker(): Promise<{
-  installed: boolean;
-  daemonRunning: boolean;
-  message?: string;
-}> {
+export async function fixtureCheckDocker(): Promise<B.DockerCheck> {
   return invokeCommand('fixture_check_docker');
 }
 
-export async function fixturePickPort(preferredPorts?: number[]): Promise<{ port: number }> {
+export async function fixturePickPort(preferredPorts?: number[]): Promise<B.PickedPort> {
   return invokeCommand('fixture_pick_port', { preferredPorts: preferredPorts ?? null });
 }
 
-export async function fixtureUp(port: number): Promise<{
-  running: boolean;
-  port: number;
-  profileName: string;
-}> {
+export async function fixtureUp(port: number): Promise<B.FixtureUpResult> {
   return invokeCommand('fixture_up', { port });
 }
 
-export async function fixtureDown(): Promise<{ ok: boolean }> {
+export async function fixtureDown(): Promise<B.Ack> {
   return invokeCommand('fixture_down');
 }
 
-export async function fixtureStatus(): Promise<{
-  running: boolean;
-  port?: number;
-  message?: string;
-}> {
+export async function fixtureStatus(): Promise<B.FixtureStatusResult> {
   return invokeCommand('fixture_status');
 }
 
-export async function fixtureLogs(tail = 50): Promise<{ lines: string[] }> {
+export async function fixtureLogs(tail = 50): Promise<B.FixtureLogLines> {
   return invokeCommand('fixture_logs', { tail });
 }
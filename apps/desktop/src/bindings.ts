@@ -0,0 +1,549 @@
+// Generated by src-tauri/build.rs from the Tauri commands and serde types in src-tauri/src.
+// Do not edit by hand: run `pnpm --filter @openquery/desktop bindings` to regenerate.
+
+export type BridgeErrorKind = 'transport' | 'timeout' | 'cancelled' | 'protocol' | 'remote' | 'keychain' | 'notStarted' | 'settings' | 'logs';
+
+/** Rejection value of every command. */
+export interface BridgeError {
+  kind: BridgeErrorKind;
+  code: string;
+  message: string;
+  details: unknown;
+}
+
+/** Returned by the `bridge_info` command: versions of both halves of the app. */
+export interface ShellInfo {
+  shellVersion: string;
+  protocolVersion: number;
+  bridgeVersion: string;
+  /** Bridge methods the running bridge supports. */
+  methods: string[];
+  /** How the bridge encodes its responses: json or msgpack. */
+  encoding: string;
+}
+
+/** Sent by the UI as-is, so it uses the same snake_case keys as the stored profile. */
+export interface NewProfile {
+  name: string;
+  db_type: string;
+  host?: string | null;
+  port?: number | null;
+  database?: string | null;
+  user?: string | null;
+  ssl: boolean;
+}
+
+/** A row of the `profiles` table. */
+export interface Profile {
+  id: string;
+  name: string;
+  db_type: string;
+  host: string | null;
+  port: number | null;
+  database: string | null;
+  user: string | null;
+  ssl: number;
+  created_at: string;
+  allow_write: number;
+  allow_dangerous: number;
+  power_confirm_phrase: string | null;
+}
+
+export interface ListedProfile extends Profile {
+  _active: boolean;
+}
+
+export interface Ack {
+  ok: boolean;
+}
+
+export interface Removed {
+  ok: boolean;
+  profileId: string;
+}
+
+export interface ConnectionTest {
+  ok: boolean;
+  error: string | null;
+  serverVersion: string | null;
+}
+
+export interface ActiveProfile {
+  name: string | null;
+}
+
+export interface SchemaRefreshSummary {
+  tables: number;
+  columns: number;
+}
+
+export interface SchemaMatch {
+  schema: string | null;
+  table: string;
+  column: string | null;
+  dataType: string | null;
+}
+
+export interface SchemaSnapshot {
+  tables: TableInfo[];
+  capturedAt: string;
+}
+
+export interface TableInfo {
+  name: string;
+  schema: string | null;
+  columns: ColumnInfo[];
+  rowCountEstimate: number | null;
+}
+
+export interface ColumnInfo {
+  name: string;
+  dataType: string;
+  nullable: boolean;
+  isPrimaryKey: boolean;
+  defaultValue: string | null;
+}
+
+export interface AskResult {
+  queryId: string;
+  plan: SqlPlan;
+  model: string;
+  retried: boolean;
+  validation: unknown;
+  explainSummary: unknown;
+  explainAllowed: boolean;
+  explainWarnings: string[];
+  explainBlockers: string[];
+  executionResult: QueryResult | null;
+  /** ok, blocked, error or dry-run. */
+  status: string;
+  error: string | null;
+}
+
+/** The statement the model generated for a question. */
+export interface SqlPlan {
+  sql: string;
+  params: PlanParam[];
+  assumptions: string[];
+  safetyNotes: string[];
+  confidence: number;
+  referencedEntities: unknown;
+}
+
+export interface PlanParam {
+  name: string;
+  type: string;
+  value: unknown;
+}
+
+/** Safe-mode thresholds the UI can override per run. */
+export interface SqlPolicy {
+  maxRowsThreshold?: number | null;
+  maxCostThreshold?: number | null;
+  enforceLimit?: boolean | null;
+}
+
+export interface WorkspaceSqlResult {
+  /** ok, blocked, error, explained, dry-run or requires-power. */
+  status: string;
+  classification: unknown;
+  validation: unknown;
+  rewrittenSql: string;
+  explainSummary: unknown;
+  explainWarnings: string[];
+  explainBlockers: string[];
+  error: string | null;
+  executionResult: QueryResult | null;
+}
+
+export interface QueryResult {
+  columns: string[];
+  rows: Record<string, unknown>[];
+  rowCount: number;
+  truncated: boolean;
+  execMs: number;
+  /** Set when rows were sent as progress batches and `rows` is empty. */
+  rowsStreamed?: boolean | null;
+}
+
+export interface HistoryListItem {
+  id: string;
+  question: string;
+  askedAt: string;
+  status: string | null;
+  execMs: number | null;
+  rowCount: number | null;
+}
+
+export interface HistoryDetail {
+  query: HistoryQuery;
+  generation: HistoryGeneration | null;
+  run: HistoryRun | null;
+}
+
+export interface HistoryQuery {
+  id: string;
+  profileId: string;
+  question: string;
+  mode: string;
+  dialect: string;
+  askedAt: string;
+}
+
+export interface HistoryGeneration {
+  id: string;
+  model: string;
+  generatedSql: string;
+  params: unknown[];
+  confidence: number;
+  assumptions: string[];
+  safetyNotes: string[];
+  generatedAt: string;
+}
+
+export interface HistoryRun {
+  id: string;
+  rewrittenSql: string;
+  params: unknown[];
+  explainSummary: unknown;
+  execMs: number;
+  rowCount: number;
+  truncated: boolean;
+  status: string;
+  errorText: string | null;
+  ranAt: string;
+}
+
+export interface SettingsStatusResult {
+  openAiKeySet: boolean;
+  model: string;
+  appVersion: string;
+  defaults: SafeModeDefaults;
+}
+
+export interface SafeModeDefaults {
+  maxRowsThreshold: number;
+  maxCostThreshold: number;
+  enforceLimit: boolean;
+}
+
+export interface KeyCheck {
+  ok: boolean;
+  message: string;
+}
+
+export interface DemoStatus {
+  ready: boolean;
+  dbPath: string;
+  active: boolean;
+  profileName: string;
+}
+
+export interface DemoReady {
+  ready: boolean;
+  dbPath: string;
+  profileName: string;
+}
+
+export interface DockerCheck {
+  installed: boolean;
+  daemonRunning: boolean;
+  message: string | null;
+}
+
+export interface PickedPort {
+  port: number;
+}
+
+export interface FixtureUpResult {
+  running: boolean;
+  port: number;
+  profileName: string;
+}
+
+export interface FixtureStatusResult {
+  running: boolean;
+  port: number | null;
+  message: string | null;
+}
+
+export interface FixtureLogLines {
+  lines: string[];
+}
+
+/** Partial update: absent fields are left alone, `confirmPhrase: null` clears the phrase. */
+export interface PowerSettingsUpdate {
+  allowWrite?: boolean | null;
+  allowDangerous?: boolean | null;
+  confirmPhrase?: string | null;
+}
+
+export interface PowerSettings {
+  allowWrite: boolean;
+  allowDangerous: boolean;
+  confirmPhrase: string | null;
+}
+
+export interface WritePreviewResult {
+  classification: unknown;
+  kind: string;
+  impactedTables: string[];
+  hasWhereClause: boolean;
+  summary: string;
+  estimatedRowsAffected: number | null;
+  explainPlan: unknown;
+  warnings: string[];
+  requiresConfirmation: boolean;
+  confirmationPhrase: string;
+  requiresDangerousConfirmation: boolean;
+  dangerousConfirmationPhrase: string;
+}
+
+export interface WriteExecution {
+  success: boolean;
+  rowsAffected: number;
+  execMs: number;
+  error: string | null;
+}
+
+/** One Node binary considered during discovery. */
+export interface NodeCandidate {
+  path: string;
+  /** Where the candidate came from: env, setting, bundled, path, volta, asdf, mise, fnm, nvm or system. */
+  source: string;
+  /** Output of `node --version`, when it ran. */
+  version: string | null;
+  accepted: boolean;
+  /** Why the candidate was not used; null for the accepted one. */
+  rejected: string | null;
+}
+
+/** Outcome of a discovery run. */
+export interface NodeDiscovery {
+  /** The binary the bridge runs on, if any candidate qualified. */
+  selected: string | null;
+  minMajor: number;
+  candidates: NodeCandidate[];
+}
+
+/** Shell-owned settings, stored in shell-settings.json. */
+export interface ShellSettings {
+  /** Node binary to run the bridge with, tried before PATH and version managers. */
+  nodePath?: string | null;
+  /**
+   * Trace level for the shell log: error, warn, info, debug or trace.
+   * OPENQUERY_LOG overrides it.
+   */
+  logLevel?: string | null;
+  /** Keep bridge call metrics across sessions, per OpenQuery version. */
+  persistMetrics?: boolean | null;
+  /**
+   * Environment variables passed to the bridge besides the built-in list:
+   * names, or prefixes ending in `*`.
+   */
+  bridgeEnv?: string[] | null;
+  /**
+   * Confine the bridge on Linux (Landlock, resource limits, own process
+   * group). On unless set to false.
+   */
+  sandboxBridge?: boolean | null;
+}
+
+/** Whether the bridge is up, and if not, why and with which paths. */
+export interface BridgeStatus {
+  running: boolean;
+  /** A launch is under way; commands fail until it finishes. */
+  starting: boolean;
+  /** Why the bridge is unavailable: the launch failure, or a crash it is restarting from. */
+  error: string | null;
+  bridgeScript: string | null;
+  /** Node binary selected for the bridge, if discovery found one. */
+  nodePath: string | null;
+}
+
+/** Heartbeat view of the bridge, for the status pill in the top bar. */
+export interface BridgeHealth {
+  /** healthy, unresponsive (running but not answering pings) or down. */
+  status: string;
+  /** Milliseconds since the bridge last sent anything. */
+  lastSeenMs: number | null;
+  /** Round trip of the most recent answered ping. */
+  latencyMs: number | null;
+  /** Pings in a row that went unanswered. */
+  missedPings: number;
+  /** Calls waiting on the bridge. */
+  inFlight: number;
+}
+
+/**
+ * Bridge call metrics for this session, and per shell version when they are
+ * kept across sessions (see `crate::metrics`).
+ */
+export interface MetricsReport {
+  shellVersion: string;
+  /** When this session started, in milliseconds since the Unix epoch. */
+  sinceMs: number;
+  /** This session, by method name. */
+  methods: MethodMetrics[];
+  /** Saved totals per shell version, newest first, this session included. */
+  releases: ReleaseMetrics[];
+}
+
+export interface ReleaseMetrics {
+  shellVersion: string;
+  methods: MethodMetrics[];
+}
+
+/**
+ * Latency, errors and payload sizes of one bridge method. Percentiles are
+ * histogram estimates, at most 15% high; the max is exact.
+ */
+export interface MethodMetrics {
+  method: string;
+  calls: number;
+  errors: number;
+  /** Failed calls by error code (TIMEOUT, AUTH_FAILED, ...). */
+  errorCodes: Record<string, number>;
+  p50Ms: number;
+  p95Ms: number;
+  maxMs: number;
+  meanMs: number;
+  requestBytesMean: number;
+  requestBytesMax: number;
+  /** Over the calls that got a response. */
+  responseBytesMean: number;
+  responseBytesMax: number;
+}
+
+/** How the bridge was launched: which script, and every Node runtime considered. */
+export interface StartupDiagnostics {
+  shellVersion: string;
+  bridgeScript: string | null;
+  /** Node runtime shipped in the app resources, if this build has one. */
+  bundledNode: string | null;
+  /** Node discovery from the most recent bridge spawn. */
+  node: NodeDiscovery | null;
+  /**
+   * Names (never values) of the environment variables the running bridge
+   * was spawned with; None when the shell did not spawn it.
+   */
+  bridgeEnv: string[] | null;
+  /** How the running bridge is confined; None when the shell did not spawn it. */
+  sandbox: SandboxStatus | null;
+  /** Why the last launch failed, if it did. */
+  error: string | null;
+}
+
+/** The bridge process's confinement (see `crate::sandbox`). */
+export interface SandboxStatus {
+  /** Whether the Landlock filesystem ruleset is enforced. */
+  active: boolean;
+  /** Why it is not: turned off in settings, not Linux, or no kernel support. */
+  reason: string | null;
+  /** Landlock ABI version of the running kernel, if it has Landlock. */
+  landlockAbi: number | null;
+  readOnly: string[];
+  readWrite: string[];
+  memoryLimitMb: number | null;
+  openFilesLimit: number | null;
+  /** Whether the bridge leads its own process group. */
+  processGroup: boolean;
+}
+
+/** One captured line. */
+export interface LogLine {
+  /** When the shell read the line, as UTC ISO 8601. */
+  timestamp: string;
+  line: string;
+}
+
+/** Where `logs_export` wrote the logs. */
+export interface LogExport {
+  path: string;
+  bytes: number;
+}
+
+/** Every Tauri command, keyed by name, with its invoke arguments and result. */
+export interface Commands {
+  /**
+   * Versions of the shell and the running bridge, plus the bridge methods it
+   * supports, so the UI can hide features an older bridge lacks.
+   */
+  bridge_info: { args: Record<string, never>; result: ShellInfo };
+  bridge_cancel: { args: { requestId: string }; result: boolean };
+  /** Whether the bridge is running; if not, why, and the script and Node it resolved. */
+  bridge_status: { args: Record<string, never>; result: BridgeStatus };
+  /** Whether the running bridge answers its heartbeat, with the latest round trip. */
+  bridge_health: { args: Record<string, never>; result: BridgeHealth };
+  /**
+   * Relaunch the bridge (e.g. after installing Node or setting its path) and
+   * report the outcome. Any running bridge is replaced.
+   */
+  bridge_retry: { args: Record<string, never>; result: BridgeStatus };
+  /** Bridge script and Node runtime candidates, for the troubleshooting view. */
+  diagnostics_startup: { args: Record<string, never>; result: StartupDiagnostics };
+  /**
+   * The last `lines` (default 200) captured lines from `source` ("bridge" or
+   * "shell"), oldest first.
+   */
+  logs_tail: { args: { source: string; lines?: number | null }; result: LogLine[] };
+  /** Write all captured logs to one file for a bug report and return where it is. */
+  logs_export: { args: Record<string, never>; result: LogExport };
+  /**
+   * Per-method latency, error and payload-size metrics for bridge calls, for
+   * this session and (when kept) earlier OpenQuery versions.
+   */
+  diagnostics_metrics: { args: Record<string, never>; result: MetricsReport };
+  shell_settings_get: { args: Record<string, never>; result: ShellSettings };
+  /**
+   * Save shell settings. A changed log level applies at once, a changed Node
+   * path or bridge environment the next time the bridge starts.
+   */
+  shell_settings_set: { args: { settings: ShellSettings }; result: null };
+  profiles_list: { args: Record<string, never>; result: ListedProfile[] };
+  profiles_add: { args: { params: NewProfile }; result: Profile };
+  profiles_remove: { args: { name: string }; result: Removed };
+  profiles_use: { args: { name: string }; result: Ack };
+  profiles_test: { args: { name: string }; result: ConnectionTest };
+  profiles_get_active: { args: Record<string, never>; result: ActiveProfile };
+  keychain_set: { args: { profileId: string; password: string }; result: null };
+  /**
+   * Whether a secret is stored under `profile_id`. Stored secrets are never
+   * read back into the webview; commands resolve them in `credentials`.
+   */
+  keychain_has: { args: { profileId: string }; result: boolean };
+  keychain_delete: { args: { profileId: string }; result: null };
+  /**
+   * Use `password` for profile `name` until the app exits, ahead of the
+   * keychain; an empty one forgets it.
+   */
+  session_password_set: { args: { name: string; password: string }; result: null };
+  schema_refresh: { args: { name?: string | null; requestId?: string | null }; result: SchemaRefreshSummary };
+  schema_search: { args: { query: string }; result: SchemaMatch[] };
+  schema_table_detail: { args: { table: string; schema?: string | null }; result: TableInfo };
+  schema_get_snapshot: { args: Record<string, never>; result: SchemaSnapshot | null };
+  ask_dry_run: { args: { question: string; mode: string; name?: string | null; requestId?: string | null }; result: AskResult };
+  ask_run: { args: { question: string; mode: string; name?: string | null; requestId?: string | null }; result: AskResult };
+  workspace_sql: { args: { sql: string; mode: string; action?: string | null; policy?: SqlPolicy | null; name?: string | null; streamRows?: boolean | null; requestId?: string | null }; result: WorkspaceSqlResult };
+  history_list: { args: { limit?: number | null }; result: HistoryListItem[] };
+  history_show: { args: { id: string }; result: HistoryDetail };
+  history_export_md: { args: { id: string }; result: string };
+  settings_status: { args: Record<string, never>; result: SettingsStatusResult };
+  settings_test_openai_key: { args: { apiKey?: string | null }; result: KeyCheck };
+  /** Save the OpenAI key in the keychain, where only the shell reads it. */
+  settings_set_openai_key: { args: { apiKey: string }; result: null };
+  settings_clear_openai_key: { args: Record<string, never>; result: null };
+  demo_no_docker_status: { args: Record<string, never>; result: DemoStatus };
+  demo_no_docker_prepare: { args: { reset?: boolean | null }; result: DemoReady };
+  demo_no_docker_reset: { args: Record<string, never>; result: DemoReady };
+  fixture_check_docker: { args: Record<string, never>; result: DockerCheck };
+  fixture_pick_port: { args: { preferredPorts?: number[] | null }; result: PickedPort };
+  fixture_up: { args: { port: number }; result: FixtureUpResult };
+  fixture_down: { args: Record<string, never>; result: Ack };
+  fixture_status: { args: Record<string, never>; result: FixtureStatusResult };
+  fixture_logs: { args: { tail?: number | null }; result: FixtureLogLines };
+  profile_update_power: { args: { name: string; settings: PowerSettingsUpdate }; result: Ack };
+  profile_get_power: { args: { name: string }; result: PowerSettings };
+  write_preview: { args: { sql: string; params: unknown[]; name?: string | null; requestId?: string | null }; result: WritePreviewResult };
+  write_execute: { args: { sql: string; params: unknown[]; name?: string | null; requestId?: string | null }; result: WriteExecution };
+}
@@ -8,9 +8,9 @@ function normalizeKey(value: string | null | undefined): string | null {
   return trimmed.length > 0 ? trimmed : null;
 }
 
-export async function getOpenAIKey(): Promise<string | null> {
-  const key = await api.keychainGet(OPENAI_KEY_ID);
-  return normalizeKey(key);
+/** Whether a key is saved. The key itself stays in the shell, which adds it to Ask calls. */
+export async function hasOpenAIKey(): Promise<boolean> {
+  return api.keychainHas(OPENAI_KEY_ID);
 }
 
 export async function setOpenAIKey(key: string): Promise<void> {
@@ -18,11 +18,11 @@ export async function setOpenAIKey(key: string): Promise<void> {
   if (!normalized) {
     throw new Error('OpenAI API key cannot be empty.');
   }
-  await api.keychainSet(OPENAI_KEY_ID, normalized);
+  await api.settingsSetOpenAiKey(normalized);
 }
 
 export async function clearOpenAIKey(): Promise<void> {
-  await api.keychainDelete(OPENAI_KEY_ID);
+  await api.settingsClearOpenAiKey();
 }
 
 export async function testOpenAIKey(key?: string): Promise<{ ok: boolean; message: string }> {
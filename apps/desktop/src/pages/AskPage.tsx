@@ -1,26 +1,12 @@
 import { useState } from 'react';
 import * as api from '../api';
-import { getOpenAIKey } from '../lib/secretStore';
+import { hasOpenAIKey } from '../lib/secretStore';
 
 interface Props {
-  password: string;
+  hasPassword: boolean;
 }
 
-interface WritePreviewData {
-  classification: string;
-  kind: string;
-  impactedTables: string[];
-  hasWhereClause: boolean;
-  summary: string;
-  estimatedRowsAffected: number | null;
-  warnings: string[];
-  requiresConfirmation: boolean;
-  confirmationPhrase: string;
-  requiresDangerousConfirmation: boolean;
-  dangerousConfirmationPhrase: string;
-}
-
-export default function AskPage({ password }: Props) {
+export default function AskPage({ hasPassword }: Props) {
   const [question, setQuestion] = useState('');
   const [mode, setMode] = useState<'safe' | 'standard'>('safe');
   const [loading, setLoading] = useState(false);
@@ -29,7 +15,7 @@ export default function AskPage({ password }: Props) {
   const [openAiKeyMissing, setOpenAiKeyMissing] = useState(false);
 
   // Write confirmation modal state
-  const [writePreview, setWritePreview] = useState<WritePreviewData | null>(null);
+  const [writePreview, setWritePreview] = useState<api.WritePreviewResult | null>(null);
   const [showWriteModal, setShowWriteModal] = useState(false);
   const [phraseInput, setPhraseInput] = useState('');
   const [dangerousPhraseInput, setDangerousPhraseInput] = useState('');
@@ -38,46 +24,40 @@ export default function AskPage({ password }: Props) {
 
   const handleAsk = async (execute: boolean) => {
     if (!question.trim()) return;
-    if (!password) {
+    if (!hasPassword) {
       setError('Enter a password in the sidebar first.');
       return;
     }
     setError(''); setResult(null); setLoading(true);
     try {
-      const [storedKey, settings] = await Promise.all([
-        getOpenAIKey(),
+      const [hasStoredKey, settings] = await Promise.all([
+        hasOpenAIKey(),
         api.settingsStatus(),
       ]);
-      const keyPresent = Boolean(storedKey) || Boolean(settings.openAiKeySet);
+      const keyPresent = hasStoredKey || Boolean(settings.openAiKeySet);
       setOpenAiKeyMissing(!keyPresent);
       if (!keyPresent) {
         throw new Error('No OpenAI API key set. Open Settings to save a key.');
       }
       const r = execute
-        ? await api.askRun(question, mode, password, storedKey)
-        : await api.askDryRun(question, mode, password, storedKey);
+        ? await api.askRun(question, mode)
+        : await api.askDryRun(question, mode);
       setResult(r);
-
-      // Check if the generated SQL is a write that was blocked due to policy
-      // If the plan was generated but blocked, offer write preview if available
-      if (r?.plan?.sql && r?.status === 'blocked' && r?.validation?.reason?.includes('POWER mode')) {
-        // User needs to enable power mode first
-      }
     } catch (e: any) {
-      setError(e.toString());
+      setError(e instanceof Error ? e.message : String(e));
     } finally {
       setLoading(false);
     }
   };
 
   const handleWritePreview = async (sql: string, params: unknown[]) => {
-    if (!password) {
+    if (!hasPassword) {
       setError('Enter a password in the sidebar first.');
       return;
     }
     setError('');
     try {
-      const preview = await api.writePreview(sql, params, password);
+      const preview = await api.writePreview(sql, params);
       setWritePreview(preview);
       setPendingSql(sql);
       setPendingParams(params);
@@ -85,7 +65,7 @@ export default function AskPage({ password }: Props) {
       setDangerousPhraseInput('');
       setShowWriteModal(true);
     } catch (e: any) {
-      setError(e.toString());
+      setError(e instanceof Error ? e.message : String(e));
     }
   };
 
@@ -106,7 +86,7 @@ export default function AskPage({ password }: Props) {
     setLoading(true);
     setError('');
     try {
-      const writeResult = await api.writeExecute(pendingSql, pendingParams, password);
+      const writeResult = await api.writeExecute(pendingSql, pendingParams);
       setResult({
         ...result,
         writeResult,
@@ -114,7 +94,7 @@ export default function AskPage({ password }: Props) {
         error: writeResult.error,
       });
     } catch (e: any) {
-      setError(e.toString());
+      setError(e instanceof Error ? e.message : String(e));
     } finally {
       setLoading(false);
     }
@@ -59,24 +59,18 @@ export default function HistoryPage({ onOpenWorkspace }: Props) {
         api.historyList(75),
         api.profilesList(),
       ]);
-      const profileNameById = new Map((rawProfiles as any[]).map((p) => [p.id as string, p.name as string]));
+      const profileNameById = new Map(rawProfiles.map((p) => [p.id, p.name]));
       const withDetails = await Promise.all(
-        (rawHistory as any[]).map(async (item) => {
+        rawHistory.map(async (item): Promise<HistoryItem> => {
           const detail = await api.historyShow(item.id);
-          const sqlPreview =
-            detail?.run?.rewrittenSql || detail?.generation?.generatedSql || '';
+          const sqlPreview = detail.run?.rewrittenSql || detail.generation?.generatedSql || '';
           return {
-            id: item.id as string,
-            question: item.question as string,
-            askedAt: item.askedAt as string,
-            status: (item.status ?? null) as string | null,
-            rowCount: (item.rowCount ?? null) as number | null,
-            execMs: (item.execMs ?? null) as number | null,
-            profileName: profileNameById.get(detail?.query?.profileId as string) || 'unknown',
+            ...item,
+            profileName: profileNameById.get(detail.query.profileId) || 'unknown',
             statementType: classifySql(sqlPreview),
             sqlPreview,
             detail,
-          } as HistoryItem;
+          };
         }),
       );
       setItems(withDetails);
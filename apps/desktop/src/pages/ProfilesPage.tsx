@@ -1,17 +1,6 @@
 import { useEffect, useState } from 'react';
 import * as api from '../api';
 
-interface ProfileState {
-  id: string;
-  name: string;
-  db_type: string;
-  host: string;
-  port: number;
-  database: string;
-  user: string;
-  ssl: boolean;
-}
-
 interface PowerState {
   allowWrite: boolean;
   allowDangerous: boolean;
@@ -33,7 +22,7 @@ export default function ProfilesPage({
   onProfilesChanged,
   onConnectionStatusChange,
 }: Props) {
-  const [profiles, setProfiles] = useState<ProfileState[]>([]);
+  const [profiles, setProfiles] = useState<api.ListedProfile[]>([]);
   const [powerByName, setPowerByName] = useState<Record<string, PowerState>>({});
   const [error, setError] = useState('');
   const [status, setStatus] = useState('');
@@ -52,13 +41,13 @@ export default function ProfilesPage({
   const load = async (): Promise<void> => {
     try {
       const [list, active] = await Promise.all([api.profilesList(), api.profilesGetActive()]);
-      setProfiles(list as ProfileState[]);
+      setProfiles(list);
       onProfilesChanged(
-        (list as ProfileState[]).map((p) => ({ id: p.id, name: p.name, db_type: p.db_type })),
+        list.map((p) => ({ id: p.id, name: p.name, db_type: p.db_type })),
         active.name,
       );
       const powerEntries = await Promise.all(
-        (list as ProfileState[]).map(async (p) => {
+        list.map(async (p) => {
           try {
             const power = await api.profileGetPower(p.name);
             return [p.name, power] as const;
@@ -81,11 +70,17 @@ export default function ProfilesPage({
     load();
   }, []);
 
-  const resolvePassword = async (profile: Pick<ProfileState, 'id' | 'db_type'>): Promise<string | null> => {
-    if (profile.db_type === 'sqlite') return '';
-    if (password.trim()) return password;
-    const stored = await api.keychainGet(profile.id);
-    return stored;
+  /**
+   * Hand the shell the session password for `profile`; false if it has none to use.
+   * The top-bar password belongs to the active profile, so only that one gets it.
+   */
+  const providePassword = async (profile: Pick<api.Profile, 'id' | 'name' | 'db_type'>): Promise<boolean> => {
+    if (profile.db_type === 'sqlite') return true;
+    if (profile.name === activeProfile && password.trim()) {
+      await api.sessionPasswordSet(profile.name, password.trim());
+      return true;
+    }
+    return api.keychainHas(profile.id);
   };
 
   const handleAdd = async (): Promise<void> => {
@@ -155,18 +150,17 @@ export default function ProfilesPage({
     }
   };
 
-  const handleTest = async (profile: ProfileState): Promise<void> => {
+  const handleTest = async (profile: api.Profile): Promise<void> => {
     setError('');
     setStatus('');
     setLoadingName(profile.name);
     try {
-      const resolved = await resolvePassword(profile);
-      if (!resolved) {
+      if (!(await providePassword(profile))) {
         setError('No password found. Enter a session password or save one in keychain.');
         onConnectionStatusChange('error');
         return;
       }
-      const result = await api.profilesTest(profile.name, resolved);
+      const result = await api.profilesTest(profile.name);
       if (result.ok) {
         onConnectionStatusChange('ok');
         setStatus(`Connection successful: ${result.serverVersion ?? 'server reachable'}`);
@@ -182,23 +176,23 @@ export default function ProfilesPage({
     } catch (err: unknown) {
       const msg = err instanceof Error ? err.message : String(err);
       onConnectionStatusChange('error');
-      setError(CONNECTION_HELP_RE.test(msg) ? `${msg}\nTip: Start Docker Desktop, then click Test.` : msg);
+      const refused = api.errorCode(err) === api.ErrorCode.ConnectionRefused;
+      setError(refused ? `${msg}\nTip: Start Docker Desktop, then click Test.` : msg);
     } finally {
       setLoadingName(null);
     }
   };
 
-  const handleRefreshSchema = async (profile: ProfileState): Promise<void> => {
+  const handleRefreshSchema = async (profile: api.Profile): Promise<void> => {
     setError('');
     setStatus('');
     setLoadingName(profile.name);
     try {
-      const resolved = await resolvePassword(profile);
-      if (!resolved) {
+      if (!(await providePassword(profile))) {
         setError('No password found. Enter a session password or save one in keychain.');
         return;
       }
-      await api.schemaRefresh(resolved, profile.name);
+      await api.schemaRefresh(profile.name);
       setStatus(`Schema snapshot refreshed for "${profile.name}".`);
     } catch (err: unknown) {
       const msg = err instanceof Error ? err.message : String(err);
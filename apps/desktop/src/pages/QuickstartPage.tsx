@@ -1,6 +1,6 @@
 import { useEffect, useMemo, useState } from 'react';
 import * as api from '../api';
-import { getOpenAIKey } from '../lib/secretStore';
+import { hasOpenAIKey } from '../lib/secretStore';
 
 type NavPage = 'workspace' | 'setup' | 'profiles' | 'history' | 'settings';
 type SetupMode = 'no-docker' | 'docker' | 'custom';
@@ -50,6 +50,8 @@ interface Props {
   activeProfile: string | null;
   profiles: ProfileSummary[];
   setupState: SetupState;
+  bridgeStatus: api.BridgeStatus | null;
+  onBridgeRetry: () => Promise<api.BridgeStatus>;
   onReloadProfileState: () => Promise<void>;
   onNavigate: (page: NavPage) => void;
 }
@@ -81,6 +83,8 @@ export default function QuickstartPage({
   activeProfile,
   profiles,
   setupState,
+  bridgeStatus,
+  onBridgeRetry,
   onReloadProfileState,
   onNavigate,
 }: Props) {
@@ -101,7 +105,7 @@ export default function QuickstartPage({
   const [hasOpenAiKey, setHasOpenAiKey] = useState(false);
   const [checkingOpenAiKey, setCheckingOpenAiKey] = useState(true);
   const [demoNoDocker, setDemoNoDocker] = useState<{ ready: boolean; dbPath: string; active: boolean } | null>(null);
-  const [dockerStatus, setDockerStatus] = useState<{ installed: boolean; daemonRunning: boolean; message?: string } | null>(null);
+  const [dockerStatus, setDockerStatus] = useState<{ installed: boolean; daemonRunning: boolean; message?: string | null } | null>(null);
   const [dockerRunning, setDockerRunning] = useState(false);
   const [dockerPort, setDockerPort] = useState<number | null>(null);
 
@@ -121,10 +125,10 @@ export default function QuickstartPage({
       setCheckingOpenAiKey(true);
       try {
         const [storedKey, settings] = await Promise.all([
-          getOpenAIKey(),
+          hasOpenAIKey(),
           api.settingsStatus(),
         ]);
-        const present = Boolean(storedKey) || Boolean(settings.openAiKeySet);
+        const present = storedKey || Boolean(settings.openAiKeySet);
         setHasOpenAiKey(present);
         setOpenAiKeyMissing(!present);
       } catch {
@@ -167,7 +171,7 @@ export default function QuickstartPage({
       await task();
     } catch (err: unknown) {
       const msg = err instanceof Error ? err.message : String(err);
-      if (msg.includes('OPENAI_API_KEY') || msg.includes('OpenAI API key')) {
+      if (api.errorCode(err) === api.ErrorCode.LlmKeyMissing) {
         setOpenAiKeyMissing(true);
         setHasOpenAiKey(false);
         setError('No OpenAI API key set. You can still run SQL directly in Workspace.');
@@ -217,17 +221,21 @@ export default function QuickstartPage({
     }
   };
 
-  const resolvePassword = (): string => {
+  /** Hand the shell the password for `profileName`; database calls only name the profile. */
+  const providePassword = async (profileName: string, profileId?: string): Promise<void> => {
     if (mode === 'no-docker') {
-      return '';
+      return;
     }
-    if (mode === 'docker') {
-      return 'openquery_dev';
+    // The top-bar password belongs to the active profile only.
+    const sessionPassword = profileName === activeProfile ? password.trim() : '';
+    const typed = mode === 'docker' ? 'openquery_dev' : form.password.trim() || sessionPassword;
+    if (typed) {
+      await api.sessionPasswordSet(profileName, typed);
+      return;
+    }
+    if (profileId && (await api.keychainHas(profileId))) {
+      return;
     }
-    const fromForm = form.password.trim();
-    if (fromForm) return fromForm;
-    const fromSession = password.trim();
-    if (fromSession) return fromSession;
     throw new Error('Enter database password in Setup Step 2, or fill Session Password in the top bar.');
   };
 
@@ -275,11 +283,11 @@ export default function QuickstartPage({
       ssl: form.ssl,
     });
     await api.profilesUse(profileName);
-    if (form.saveInKeychain && form.password.trim() && typeof created?.id === 'string') {
+    if (form.saveInKeychain && form.password.trim()) {
       await api.keychainSet(created.id, form.password.trim());
     }
     await onReloadProfileState();
-    return { profileName, profileId: typeof created?.id === 'string' ? created.id : undefined };
+    return { profileName, profileId: created.id };
   };
 
   const handlePrepareNoDocker = async (reset: boolean): Promise<void> => {
@@ -346,8 +354,9 @@ export default function QuickstartPage({
 
   const handleTestConnection = async (): Promise<void> => {
     await runAction('test-connection', async () => {
-      const { profileName } = await ensureProfile();
-      const result = await api.profilesTest(profileName, resolvePassword());
+      const { profileName, profileId } = await ensureProfile();
+      await providePassword(profileName, profileId);
+      const result = await api.profilesTest(profileName);
       if (!result.ok) {
         setConnectionOk(false);
         throw new Error(result.error || 'Connection failed.');
@@ -360,10 +369,11 @@ export default function QuickstartPage({
 
   const handleRefreshSchema = async (): Promise<void> => {
     await runAction('refresh-schema', async () => {
-      const { profileName } = await ensureProfile();
-      await api.schemaRefresh(resolvePassword(), profileName);
+      const { profileName, profileId } = await ensureProfile();
+      await providePassword(profileName, profileId);
+      await api.schemaRefresh(profileName);
       const snapshot = await api.schemaGetSnapshot();
-      const capturedAt = typeof snapshot?.capturedAt === 'string' ? snapshot.capturedAt : new Date().toISOString();
+      const capturedAt = snapshot?.capturedAt ?? new Date().toISOString();
       setSchemaRefreshedAt(capturedAt);
       setStatus('Schema refreshed. Guardrails and Ask now use your latest structure.');
       setStep((prev) => Math.max(prev, 4));
@@ -372,21 +382,21 @@ export default function QuickstartPage({
 
   const handleRunSqlSample = async (): Promise<void> => {
     await runAction('run-sql-sample', async () => {
-      const { profileName } = await ensureProfile();
+      const { profileName, profileId } = await ensureProfile();
+      await providePassword(profileName, profileId);
       const sqlResult = await api.workspaceSql({
         sql: SAMPLE_SQL,
         mode: 'safe',
         action: 'run',
-        password: resolvePassword(),
         name: profileName,
       });
       setAskResult({
         status: sqlResult.status,
-        plan: { sql: sqlResult.rewrittenSql ?? SAMPLE_SQL },
-        validation: sqlResult.validation,
-        explainSummary: sqlResult.explainSummary,
-        executionResult: sqlResult.executionResult ?? null,
-        error: sqlResult.error,
+        plan: { sql: sqlResult.rewrittenSql },
+        validation: sqlResult.validation as AskResult['validation'],
+        explainSummary: sqlResult.explainSummary as AskResult['explainSummary'],
+        executionResult: sqlResult.executionResult,
+        error: sqlResult.error ?? undefined,
       });
       setStatus('Sample SQL executed in Safe mode.');
       setStep((prev) => Math.max(prev, 4));
@@ -398,21 +408,27 @@ export default function QuickstartPage({
       if (!prompt.trim()) {
         throw new Error('Enter a question first.');
       }
-      const { profileName } = await ensureProfile();
+      const { profileName, profileId } = await ensureProfile();
+      await providePassword(profileName, profileId);
       const [storedKey, settings] = await Promise.all([
-        getOpenAIKey(),
+        hasOpenAIKey(),
         api.settingsStatus(),
       ]);
-      const keyPresent = Boolean(storedKey) || Boolean(settings.openAiKeySet);
+      const keyPresent = storedKey || Boolean(settings.openAiKeySet);
       setHasOpenAiKey(keyPresent);
       setOpenAiKeyMissing(!keyPresent);
       if (!keyPresent) {
         throw new Error('No OpenAI API key set. Open Settings and save a key to enable Ask.');
       }
       const result = execute
-        ? (await api.askRun(prompt, 'safe', resolvePassword(), storedKey))
-        : (await api.askDryRun(prompt, 'safe', resolvePassword(), storedKey));
-      setAskResult(result as AskResult);
+        ? (await api.askRun(prompt, 'safe', profileName))
+        : (await api.askDryRun(prompt, 'safe', profileName));
+      setAskResult({
+        ...result,
+        validation: result.validation as AskResult['validation'],
+        explainSummary: result.explainSummary as AskResult['explainSummary'],
+        error: result.error ?? undefined,
+      });
       setStatus(execute ? 'Generated and executed in Safe mode.' : 'Generated in dry-run mode.');
       setStep((prev) => Math.max(prev, 4));
       if (profileName) {
@@ -424,6 +440,18 @@ export default function QuickstartPage({
   const renderedRows = askResult?.executionResult?.rows?.slice(0, 5) ?? [];
   const renderedColumns = askResult?.executionResult?.columns ?? [];
 
+  if (bridgeStatus && !bridgeStatus.running && !bridgeStatus.starting) {
+    return (
+      <section className="page-stack">
+        <header className="page-header">
+          <h2>Quickstart Setup</h2>
+          <p>The query engine needs Node.js before setup can continue.</p>
+        </header>
+        <BridgeUnavailable status={bridgeStatus} onRetry={onBridgeRetry} />
+      </section>
+    );
+  }
+
   return (
     <section className="page-stack">
       <header className="page-header">
@@ -898,3 +926,108 @@ export default function QuickstartPage({
     </section>
   );
 }
+
+function BridgeUnavailable({
+  status,
+  onRetry,
+}: {
+  status: api.BridgeStatus;
+  onRetry: () => Promise<api.BridgeStatus>;
+}) {
+  const [diagnostics, setDiagnostics] = useState<api.StartupDiagnostics | null>(null);
+  const [nodePath, setNodePath] = useState('');
+  const [retrying, setRetrying] = useState(false);
+  const [error, setError] = useState('');
+
+  const loadDiagnostics = async (): Promise<void> => {
+    try {
+      const [next, shell] = await Promise.all([api.diagnosticsStartup(), api.shellSettingsGet()]);
+      setDiagnostics(next);
+      setNodePath((prev) => prev || shell.nodePath || '');
+    } catch (err: unknown) {
+      setError(err instanceof Error ? err.message : String(err));
+    }
+  };
+
+  useEffect(() => {
+    void loadDiagnostics();
+  }, [status]);
+
+  const handleRetry = async (): Promise<void> => {
+    setRetrying(true);
+    setError('');
+    try {
+      await api.shellSettingsSet({ nodePath: nodePath.trim() || null });
+      const next = await onRetry();
+      if (!next.running) {
+        setError(next.error ?? 'The query engine still did not start.');
+      }
+    } catch (err: unknown) {
+      setError(err instanceof Error ? err.message : String(err));
+    } finally {
+      setRetrying(false);
+    }
+  };
+
+  const candidates = diagnostics?.node?.candidates ?? [];
+  const minMajor = diagnostics?.node?.minMajor ?? 18;
+
+  return (
+    <div className="card stack">
+      <h3>Query engine not running</h3>
+      <div className="inline-error preserve-lines">{error || status.error}</div>
+      <ol className="checklist">
+        <li>
+          Install Node.js {minMajor} or newer from{' '}
+          <a href="https://nodejs.org" target="_blank" rel="noreferrer">nodejs.org</a>, or with nvm, volta, asdf, fnm or mise.
+        </li>
+        <li>If Node is installed somewhere unusual, enter the path to the node binary below.</li>
+        <li>Click Retry. No restart needed.</li>
+      </ol>
+      <div className="settings-grid">
+        <label>
+          <span>Node path (optional)</span>
+          <input
+            type="text"
+            value={nodePath}
+            onChange={(e) => setNodePath(e.target.value)}
+            placeholder="/usr/local/bin/node"
+            autoComplete="off"
+          />
+        </label>
+      </div>
+      <div className="action-row">
+        <button type="button" className="btn" onClick={handleRetry} disabled={retrying}>
+          {retrying ? 'Starting...' : 'Retry'}
+        </button>
+      </div>
+      {status.bridgeScript && (
+        <p className="muted">Bridge script: <code>{status.bridgeScript}</code></p>
+      )}
+      {candidates.length > 0 && (
+        <div className="table-wrapper">
+          <table className="data-table">
+            <thead>
+              <tr>
+                <th>Node candidate</th>
+                <th>Source</th>
+                <th>Version</th>
+                <th>Result</th>
+              </tr>
+            </thead>
+            <tbody>
+              {candidates.map((candidate) => (
+                <tr key={candidate.path}>
+                  <td><code>{candidate.path}</code></td>
+                  <td>{candidate.source}</td>
+                  <td>{candidate.version ?? '—'}</td>
+                  <td>{candidate.accepted ? 'Selected' : candidate.rejected}</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+      )}
+    </div>
+  );
+}
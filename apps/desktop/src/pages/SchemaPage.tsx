@@ -2,25 +2,25 @@ import { useState } from 'react';
 import * as api from '../api';
 
 interface Props {
-  password: string;
+  hasPassword: boolean;
 }
 
-export default function SchemaPage({ password }: Props) {
+export default function SchemaPage({ hasPassword }: Props) {
   const [error, setError] = useState('');
   const [status, setStatus] = useState('');
   const [searchQuery, setSearchQuery] = useState('');
-  const [searchResults, setSearchResults] = useState<any[]>([]);
-  const [tableDetail, setTableDetail] = useState<any>(null);
+  const [searchResults, setSearchResults] = useState<api.SchemaMatch[]>([]);
+  const [tableDetail, setTableDetail] = useState<api.TableInfo | null>(null);
   const [refreshing, setRefreshing] = useState(false);
 
   const handleRefresh = async () => {
-    if (!password) {
+    if (!hasPassword) {
       setError('Enter a password in the sidebar first.');
       return;
     }
     setError(''); setStatus(''); setRefreshing(true);
     try {
-      const result = await api.schemaRefresh(password);
+      const result = await api.schemaRefresh();
       setStatus(`Schema refreshed: ${result.tables} tables, ${result.columns} columns.`);
     } catch (e: any) {
       setError(e.toString());
@@ -79,7 +79,7 @@ export default function SchemaPage({ password }: Props) {
           </thead>
           <tbody>
             {searchResults.map((r, i) => (
-              <tr key={i} onClick={() => handleTableClick(r.table, r.schema)} className="clickable">
+              <tr key={i} onClick={() => handleTableClick(r.table, r.schema ?? undefined)} className="clickable">
                 <td>{r.schema ?? '-'}</td>
                 <td>{r.table}</td>
                 <td>{r.column ?? '(table match)'}</td>
@@ -93,7 +93,7 @@ export default function SchemaPage({ password }: Props) {
       {tableDetail && (
         <div className="detail-card">
           <h3>{tableDetail.schema ? `${tableDetail.schema}.` : ''}{tableDetail.name}</h3>
-          {tableDetail.rowCountEstimate !== undefined && (
+          {tableDetail.rowCountEstimate !== null && (
             <p className="muted">~{tableDetail.rowCountEstimate.toLocaleString()} rows</p>
           )}
           <table className="data-table">
@@ -101,7 +101,7 @@ export default function SchemaPage({ password }: Props) {
               <tr><th>Column</th><th>Type</th><th>Nullable</th><th>PK</th><th>Default</th></tr>
             </thead>
             <tbody>
-              {tableDetail.columns?.map((col: any) => (
+              {tableDetail.columns.map((col) => (
                 <tr key={col.name}>
                   <td>{col.name}</td>
                   <td>{col.dataType}</td>
@@ -1,7 +1,7 @@
 import { useEffect, useState } from 'react';
 import * as api from '../api';
 import type { SafePolicySettings } from '../App';
-import { clearOpenAIKey, getOpenAIKey, setOpenAIKey, testOpenAIKey } from '../lib/secretStore';
+import { clearOpenAIKey, hasOpenAIKey, setOpenAIKey, testOpenAIKey } from '../lib/secretStore';
 
 interface Props {
   safePolicy: SafePolicySettings;
@@ -26,15 +26,22 @@ export default function SettingsPage({ safePolicy, onSafePolicyChange }: Props)
   const [openAiFromEnv, setOpenAiFromEnv] = useState(false);
   const [openAiValidation, setOpenAiValidation] = useState<'not_set' | 'valid' | 'invalid'>('not_set');
   const [openAiValidationMessage, setOpenAiValidationMessage] = useState('No key configured.');
-  const [runningAction, setRunningAction] = useState<'save' | 'clear' | 'test' | null>(null);
+  const [runningAction, setRunningAction] = useState<'save' | 'clear' | 'test' | 'node' | 'level' | 'logs' | 'export' | 'metrics' | 'persist' | null>(null);
+  const [nodePathInput, setNodePathInput] = useState('');
+  const [bridgeEnvInput, setBridgeEnvInput] = useState('');
+  const [logLevel, setLogLevel] = useState('');
+  const [persistMetrics, setPersistMetrics] = useState(false);
+  const [sandboxBridge, setSandboxBridge] = useState(true);
+  const [startup, setStartup] = useState<api.StartupDiagnostics | null>(null);
+  const [bridgeLog, setBridgeLog] = useState<api.LogLine[] | null>(null);
+  const [metrics, setMetrics] = useState<api.MetricsReport | null>(null);
 
   const refreshKeyState = async (): Promise<void> => {
-    const [nextStatus, storedKey] = await Promise.all([
+    const [nextStatus, hasStored] = await Promise.all([
       api.settingsStatus(),
-      getOpenAIKey(),
+      hasOpenAIKey(),
     ]);
     setStatus(nextStatus);
-    const hasStored = Boolean(storedKey);
     const hasEnv = !hasStored && Boolean(nextStatus.openAiKeySet);
     setOpenAiStored(hasStored);
     setOpenAiFromEnv(hasEnv);
@@ -43,7 +50,7 @@ export default function SettingsPage({ safePolicy, onSafePolicyChange }: Props)
       setOpenAiValidationMessage('No key configured.');
       return;
     }
-    const probe = await testOpenAIKey(hasStored ? storedKey ?? undefined : undefined);
+    const probe = await testOpenAIKey();
     setOpenAiValidation(probe.ok ? 'valid' : 'invalid');
     setOpenAiValidationMessage(probe.message);
   };
@@ -58,10 +65,23 @@ export default function SettingsPage({ safePolicy, onSafePolicyChange }: Props)
       }
     };
     load();
+
+    Promise.all([api.shellSettingsGet(), api.diagnosticsStartup()])
+      .then(([shell, diagnostics]) => {
+        setNodePathInput(shell.nodePath ?? '');
+        setBridgeEnvInput((shell.bridgeEnv ?? []).join(', '));
+        setLogLevel(shell.logLevel ?? '');
+        setPersistMetrics(shell.persistMetrics ?? false);
+        setSandboxBridge(shell.sandboxBridge ?? true);
+        setStartup(diagnostics);
+      })
+      .catch(() => {
+        // Runtime details are informational; the rest of the page still works.
+      });
   }, []);
 
   const runAction = async (
-    action: 'save' | 'clear' | 'test',
+    action: 'save' | 'clear' | 'test' | 'node' | 'level' | 'logs' | 'export' | 'metrics' | 'persist',
     task: () => Promise<void>,
   ): Promise<void> => {
     setRunningAction(action);
@@ -121,6 +141,65 @@ export default function SettingsPage({ safePolicy, onSafePolicyChange }: Props)
     });
   };
 
+  /** Shell settings are saved whole, so send the current values with the change. */
+  const saveShellSettings = (change: Partial<api.ShellSettings>): Promise<void> => {
+    const nodePath = nodePathInput.trim();
+    return api.shellSettingsSet({
+      nodePath: nodePath || null,
+      logLevel: logLevel || null,
+      persistMetrics,
+      bridgeEnv: parseEnvNames(bridgeEnvInput),
+      sandboxBridge,
+      ...change,
+    });
+  };
+
+  const handleSaveNodePath = async (): Promise<void> => {
+    await runAction('node', async () => {
+      const nodePath = nodePathInput.trim();
+      await saveShellSettings({ nodePath: nodePath || null });
+      setNotice(
+        nodePath
+          ? 'Node settings saved. They are used the next time the bridge starts.'
+          : 'Node settings saved. Node is discovered automatically the next time the bridge starts.',
+      );
+    });
+  };
+
+  const handleLogLevelChange = async (level: string): Promise<void> => {
+    await runAction('level', async () => {
+      await saveShellSettings({ logLevel: level || null });
+      setLogLevel(level);
+      setNotice(`Log level set to ${level || 'info (default)'}.`);
+    });
+  };
+
+  const handleShowBridgeLog = async (): Promise<void> => {
+    await runAction('logs', async () => {
+      setBridgeLog(await api.logsTail('bridge'));
+    });
+  };
+
+  const handleExportLogs = async (): Promise<void> => {
+    await runAction('export', async () => {
+      const exported = await api.logsExport();
+      setNotice(`Logs exported to ${exported.path}. Attach this file to your bug report.`);
+    });
+  };
+
+  const handleShowMetrics = async (): Promise<void> => {
+    await runAction('metrics', async () => {
+      setMetrics(await api.diagnosticsMetrics());
+    });
+  };
+
+  const handlePersistMetricsChange = async (persist: boolean): Promise<void> => {
+    await runAction('persist', async () => {
+      await saveShellSettings({ persistMetrics: persist });
+      setPersistMetrics(persist);
+    });
+  };
+
   return (
     <section className="page-stack">
       <header className="page-header">
@@ -256,6 +335,225 @@ export default function SettingsPage({ safePolicy, onSafePolicyChange }: Props)
         </div>
       </div>
 
+      <div className="section">
+        <div className="section-header">
+          <div className="stack-sm">
+            <h3>Node Runtime</h3>
+            <p className="muted prose">
+              The query engine runs on Node.js {startup?.node?.minMajor ?? 18} or newer, found on PATH or through nvm, volta, asdf, fnm or mise.
+            </p>
+          </div>
+        </div>
+        <div className="card stack">
+          <div className="settings-grid">
+            <label>
+              <span>Node path (optional)</span>
+              <input
+                type="text"
+                value={nodePathInput}
+                onChange={(e) => setNodePathInput(e.target.value)}
+                placeholder="/usr/local/bin/node"
+                autoComplete="off"
+              />
+            </label>
+            <label>
+              <span>Extra environment variables (optional)</span>
+              <input
+                type="text"
+                value={bridgeEnvInput}
+                onChange={(e) => setBridgeEnvInput(e.target.value)}
+                placeholder="NODE_EXTRA_CA_CERTS, MY_APP_*"
+                autoComplete="off"
+              />
+            </label>
+          </div>
+          <p className="muted">
+            The engine only sees PATH, HOME, locale and temp-directory variables, OPENAI_API_KEY and OPENQUERY_*, plus the names listed here.
+          </p>
+          <label className="toggle-row compact">
+            <input
+              type="checkbox"
+              checked={sandboxBridge}
+              onChange={(e) => setSandboxBridge(e.target.checked)}
+            />
+            <span>Sandbox the query engine (Linux): files limited to its own, Node's and ~/.openquery</span>
+          </label>
+          <div className="action-row">
+            <button
+              type="button"
+              className="btn"
+              onClick={handleSaveNodePath}
+              disabled={runningAction !== null}
+            >
+              {runningAction === 'node' ? 'Saving...' : 'Save'}
+            </button>
+          </div>
+          {startup?.node?.selected && (
+            <p className="muted">In use: <code>{startup.node.selected}</code></p>
+          )}
+          {startup?.bridgeEnv && (
+            <p className="muted">Environment passed: <code>{startup.bridgeEnv.join(', ') || 'none'}</code></p>
+          )}
+          {startup?.sandbox && (
+            <p className="muted">
+              {startup.sandbox.active
+                ? `Sandboxed with Landlock ABI ${startup.sandbox.landlockAbi}, ${startup.sandbox.memoryLimitMb} MB data limit, ${startup.sandbox.openFilesLimit} open files.`
+                : `Not sandboxed: ${startup.sandbox.reason}.`}
+            </p>
+          )}
+          {startup?.node && startup.node.candidates.length > 0 && (
+            <div className="table-wrapper">
+              <table className="data-table">
+                <thead>
+                  <tr>
+                    <th>Candidate</th>
+                    <th>Source</th>
+                    <th>Version</th>
+                    <th>Result</th>
+                  </tr>
+                </thead>
+                <tbody>
+                  {startup.node.candidates.map((candidate) => (
+                    <tr key={candidate.path}>
+                      <td><code>{candidate.path}</code></td>
+                      <td>{candidate.source}</td>
+                      <td>{candidate.version ?? '—'}</td>
+                      <td>{candidate.accepted ? 'Selected' : candidate.rejected}</td>
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+            </div>
+          )}
+        </div>
+      </div>
+
+      <div className="section">
+        <div className="section-header">
+          <div className="stack-sm">
+            <h3>Logs</h3>
+            <p className="muted prose">
+              Warnings and errors from the query engine, and a trace of the app's own activity, kept so they can go with a bug report. Passwords and API keys are never logged.
+            </p>
+          </div>
+        </div>
+        <div className="card stack">
+          <div className="settings-grid">
+            <label>
+              <span>Log level</span>
+              <select
+                value={logLevel}
+                onChange={(e) => void handleLogLevelChange(e.target.value)}
+                disabled={runningAction !== null}
+              >
+                <option value="">Default (info)</option>
+                <option value="error">error</option>
+                <option value="warn">warn</option>
+                <option value="info">info</option>
+                <option value="debug">debug</option>
+                <option value="trace">trace</option>
+              </select>
+            </label>
+          </div>
+          <div className="action-row">
+            <button
+              type="button"
+              className="btn btn-secondary"
+              onClick={handleShowBridgeLog}
+              disabled={runningAction !== null}
+            >
+              {runningAction === 'logs' ? 'Loading...' : 'Show Recent'}
+            </button>
+            <button
+              type="button"
+              className="btn"
+              onClick={handleExportLogs}
+              disabled={runningAction !== null}
+            >
+              {runningAction === 'export' ? 'Exporting...' : 'Export Logs'}
+            </button>
+          </div>
+          {bridgeLog && (
+            bridgeLog.length === 0
+              ? <p className="muted">Nothing logged since the app started.</p>
+              : <pre><code>{bridgeLog.map((entry) => `${entry.timestamp} ${entry.line}`).join('\n')}</code></pre>
+          )}
+        </div>
+      </div>
+
+      <div className="section">
+        <div className="section-header">
+          <div className="stack-sm">
+            <h3>Performance</h3>
+            <p className="muted prose">
+              How long each query engine call has taken since the app started, how often it failed, and how much data it moved. p50 and p95 are estimates, at most 15% high.
+            </p>
+          </div>
+        </div>
+        <div className="card stack">
+          <label className="toggle-row compact">
+            <input
+              type="checkbox"
+              checked={persistMetrics}
+              onChange={(e) => void handlePersistMetricsChange(e.target.checked)}
+              disabled={runningAction !== null}
+            />
+            <span>Keep timings across sessions, to compare OpenQuery versions</span>
+          </label>
+          <div className="action-row">
+            <button
+              type="button"
+              className="btn btn-secondary"
+              onClick={handleShowMetrics}
+              disabled={runningAction !== null}
+            >
+              {runningAction === 'metrics' ? 'Loading...' : 'Show Timings'}
+            </button>
+          </div>
+          {metrics && metrics.releases.map((release) => (
+            <div className="stack-sm" key={release.shellVersion}>
+              <p className="muted">
+                {release.shellVersion === metrics.shellVersion ? `${release.shellVersion} (this version)` : release.shellVersion}
+              </p>
+              {release.methods.length === 0 ? (
+                <p className="muted">No calls yet.</p>
+              ) : (
+                <div className="table-wrapper">
+                  <table className="data-table">
+                    <thead>
+                      <tr>
+                        <th>Method</th>
+                        <th>Calls</th>
+                        <th>Errors</th>
+                        <th>p50 ms</th>
+                        <th>p95 ms</th>
+                        <th>Max ms</th>
+                        <th>Avg response</th>
+                      </tr>
+                    </thead>
+                    <tbody>
+                      {release.methods.map((m) => (
+                        <tr key={m.method}>
+                          <td><code>{m.method}</code></td>
+                          <td>{m.calls}</td>
+                          <td title={Object.entries(m.errorCodes).map(([code, n]) => `${code}: ${n}`).join(', ')}>
+                            {m.errors}
+                          </td>
+                          <td>{m.p50Ms.toFixed(1)}</td>
+                          <td>{m.p95Ms.toFixed(1)}</td>
+                          <td>{m.maxMs.toFixed(1)}</td>
+                          <td>{formatBytes(m.responseBytesMean)}</td>
+                        </tr>
+                      ))}
+                    </tbody>
+                  </table>
+                </div>
+              )}
+            </div>
+          ))}
+        </div>
+      </div>
+
       <div className="section">
         <div className="section-header">
           <div className="stack-sm">
@@ -283,3 +581,15 @@ export default function SettingsPage({ safePolicy, onSafePolicyChange }: Props)
     </section>
   );
 }
+
+/** Split a comma- or space-separated list of variable names. */
+function parseEnvNames(input: string): string[] | null {
+  const names = input.split(/[\s,]+/).filter(Boolean);
+  return names.length > 0 ? names : null;
+}
+
+function formatBytes(bytes: number): string {
+  if (bytes < 1024) return `${Math.round(bytes)} B`;
+  if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+  return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+}
@@ -1,10 +1,13 @@
-import { useEffect, useMemo, useState } from 'react';
+import { useEffect, useMemo, useRef, useState } from 'react';
 import * as api from '../api';
 import type { SafePolicySettings } from '../App';
-import { getOpenAIKey } from '../lib/secretStore';
+import { hasOpenAIKey } from '../lib/secretStore';
 
 interface Props {
-  password: string;
+  /** A session password is typed or one is saved for the active profile. */
+  hasPassword: boolean;
+  /** Hand the shell the password typed in the top bar; run before a database call. */
+  onSubmitPassword: () => Promise<void>;
   activeProfile: string | null;
   activeProfileType: string | null;
   safePolicy: SafePolicySettings;
@@ -19,20 +22,6 @@ interface SqlClassification {
   classification: 'read' | 'write' | 'dangerous';
 }
 
-interface WritePreviewData {
-  classification: string;
-  kind: string;
-  impactedTables: string[];
-  hasWhereClause: boolean;
-  summary: string;
-  estimatedRowsAffected: number | null;
-  warnings: string[];
-  requiresConfirmation: boolean;
-  confirmationPhrase: string;
-  requiresDangerousConfirmation: boolean;
-  dangerousConfirmationPhrase: string;
-}
-
 interface WorkspaceResult {
   status: string;
   source: 'ask' | 'sql';
@@ -43,13 +32,7 @@ interface WorkspaceResult {
   explainSummary?: any;
   explainWarnings?: string[];
   explainBlockers?: string[];
-  executionResult?: {
-    columns: string[];
-    rows: Record<string, unknown>[];
-    rowCount: number;
-    truncated: boolean;
-    execMs: number;
-  } | null;
+  executionResult?: api.QueryResult | null;
   model?: string;
   confidence?: number;
   error?: string;
@@ -78,7 +61,8 @@ function classifySqlText(sql: string): SqlClassification {
 }
 
 export default function WorkspacePage({
-  password,
+  hasPassword,
+  onSubmitPassword,
   activeProfile,
   activeProfileType,
   safePolicy,
@@ -102,13 +86,17 @@ export default function WorkspacePage({
   const [question, setQuestion] = useState('');
   const [sqlText, setSqlText] = useState('');
   const [running, setRunning] = useState(false);
+  const [activeRequestId, setActiveRequestId] = useState<string | null>(null);
+  const [streamedRows, setStreamedRows] = useState<Record<string, unknown>[]>([]);
+  const [streamedColumns, setStreamedColumns] = useState<string[]>([]);
+  const stopRowStream = useRef<(() => void) | null>(null);
   const [result, setResult] = useState<WorkspaceResult | null>(null);
   const [openAiKeyMissing, setOpenAiKeyMissing] = useState(false);
   const [hasOpenAiKey, setHasOpenAiKey] = useState(false);
   const [checkingOpenAiKey, setCheckingOpenAiKey] = useState(true);
   const [showHelp, setShowHelp] = useState(false);
 
-  const [writePreview, setWritePreview] = useState<WritePreviewData | null>(null);
+  const [writePreview, setWritePreview] = useState<api.WritePreviewResult | null>(null);
   const [showWriteModal, setShowWriteModal] = useState(false);
   const [phraseInput, setPhraseInput] = useState('');
   const [dangerousPhraseInput, setDangerousPhraseInput] = useState('');
@@ -146,6 +134,8 @@ export default function WorkspacePage({
     loadSnapshot();
   }, [activeProfile]);
 
+  useEffect(() => () => stopRowStream.current?.(), []);
+
   useEffect(() => {
     if (!draft) return;
     if (draft.question) {
@@ -164,10 +154,10 @@ export default function WorkspacePage({
       setCheckingOpenAiKey(true);
       try {
         const [storedKey, settings] = await Promise.all([
-          getOpenAIKey(),
+          hasOpenAIKey(),
           api.settingsStatus(),
         ]);
-        const present = Boolean(storedKey) || Boolean(settings.openAiKeySet);
+        const present = storedKey || Boolean(settings.openAiKeySet);
         setHasOpenAiKey(present);
         setOpenAiKeyMissing(!present);
       } catch {
@@ -191,8 +181,12 @@ export default function WorkspacePage({
     });
   }, [schemaSnapshot, schemaSearch]);
 
-  const rows = result?.executionResult?.rows ?? [];
-  const columns = result?.executionResult?.columns ?? [];
+  // Workspace SQL results arrive as row batches (progress events) while the query runs.
+  const showStreamedRows = Boolean(result?.executionResult?.rowsStreamed) || (running && streamedRows.length > 0);
+  const rows = showStreamedRows ? streamedRows : (result?.executionResult?.rows ?? []);
+  const columns = showStreamedRows && !result?.executionResult?.rowsStreamed
+    ? streamedColumns
+    : (result?.executionResult?.columns ?? []);
   const maxPage = Math.max(1, Math.ceil(rows.length / pageSize));
   const pagedRows = rows.slice((page - 1) * pageSize, page * pageSize);
 
@@ -227,25 +221,32 @@ export default function WorkspacePage({
 
   const handleRefreshSchema = async (): Promise<void> => {
     if (!activeProfile) return;
-    const resolvedPassword = requiresPassword ? password.trim() : '';
-    if (requiresPassword && !resolvedPassword) {
+    if (requiresPassword && !hasPassword) {
       setError('Enter a session password, then refresh schema.');
       return;
     }
+    await onSubmitPassword();
     setRunning(true);
     setActivityLabel('Refreshing schema...');
     setError('');
     setStatus('');
     setLoadingSchema(true);
+    const requestId = api.newRequestId();
+    setActiveRequestId(requestId);
     try {
-      await api.schemaRefresh(resolvedPassword);
+      await api.schemaRefresh(activeProfile, requestId);
       await loadSnapshot();
       setStatus('Schema snapshot refreshed.');
     } catch (err: unknown) {
+      if (api.isCancelled(err)) {
+        setStatus('Schema refresh stopped.');
+        return;
+      }
       const msg = err instanceof Error ? err.message : String(err);
       setError(msg);
     } finally {
       setRunning(false);
+      setActiveRequestId(null);
       setLoadingSchema(false);
       setActivityLabel('');
     }
@@ -253,20 +254,20 @@ export default function WorkspacePage({
 
   const runAsk = async (execute: boolean): Promise<void> => {
     if (!question.trim()) return;
-    const resolvedPassword = requiresPassword ? password.trim() : '';
-    if (requiresPassword && !resolvedPassword) {
+    if (requiresPassword && !hasPassword) {
       setError('Enter a session password before running Ask.');
       return;
     }
+    await onSubmitPassword();
     setRunning(true);
     setActivityLabel(execute ? 'Generating SQL, validating policy, and executing query...' : 'Generating SQL and validating policy...');
     setError('');
     setStatus('');
-    let storedKey: string | null = null;
+    const requestId = api.newRequestId();
+    setActiveRequestId(requestId);
     try {
-      storedKey = await getOpenAIKey();
-      const settings = await api.settingsStatus();
-      const keyPresent = Boolean(storedKey) || Boolean(settings.openAiKeySet);
+      const [storedKey, settings] = await Promise.all([hasOpenAIKey(), api.settingsStatus()]);
+      const keyPresent = storedKey || Boolean(settings.openAiKeySet);
       setHasOpenAiKey(keyPresent);
       setOpenAiKeyMissing(!keyPresent);
       if (!keyPresent) {
@@ -274,27 +275,31 @@ export default function WorkspacePage({
         return;
       }
       const askResult = execute
-        ? await api.askRun(question, askMode, resolvedPassword, storedKey)
-        : await api.askDryRun(question, askMode, resolvedPassword, storedKey);
-      const classification = classifySqlText(askResult?.plan?.sql ?? '');
+        ? await api.askRun(question, askMode, activeProfile ?? undefined, requestId)
+        : await api.askDryRun(question, askMode, activeProfile ?? undefined, requestId);
+      const classification = classifySqlText(askResult.plan.sql);
       setResult({
         status: askResult.status,
         source: 'ask',
-        sql: askResult?.plan?.sql ?? '',
-        params: (askResult?.plan?.params ?? []).map((p: any) => p.value),
+        sql: askResult.plan.sql,
+        params: askResult.plan.params.map((p) => p.value),
         validation: askResult.validation,
         classification,
         explainSummary: askResult.explainSummary,
-        explainWarnings: askResult.explainWarnings ?? [],
-        explainBlockers: askResult.explainBlockers ?? [],
-        executionResult: askResult.executionResult ?? null,
+        explainWarnings: askResult.explainWarnings,
+        explainBlockers: askResult.explainBlockers,
+        executionResult: askResult.executionResult,
         model: askResult.model,
-        confidence: askResult?.plan?.confidence,
-        error: askResult.error,
+        confidence: askResult.plan.confidence,
+        error: askResult.error ?? undefined,
       });
     } catch (err: unknown) {
+      if (api.isCancelled(err)) {
+        setStatus('Ask stopped.');
+        return;
+      }
       const msg = err instanceof Error ? err.message : String(err);
-      if (msg.includes('OPENAI_API_KEY') || msg.includes('OpenAI API key')) {
+      if (api.errorCode(err) === api.ErrorCode.LlmKeyMissing) {
         setOpenAiKeyMissing(true);
         setHasOpenAiKey(false);
         setError('No OpenAI API key set. You can still run SQL directly, or use dry-run with local fixtures.');
@@ -303,17 +308,18 @@ export default function WorkspacePage({
       setError(msg);
     } finally {
       setRunning(false);
+      setActiveRequestId(null);
       setActivityLabel('');
     }
   };
 
   const runSqlAction = async (action: 'run' | 'dry-run' | 'explain'): Promise<void> => {
     if (!sqlText.trim()) return;
-    const resolvedPassword = requiresPassword ? password.trim() : '';
-    if (requiresPassword && !resolvedPassword) {
+    if (requiresPassword && !hasPassword) {
       setError('Enter a session password before running SQL.');
       return;
     }
+    await onSubmitPassword();
     setRunning(true);
     setActivityLabel(
       action === 'run'
@@ -324,13 +330,27 @@ export default function WorkspacePage({
     );
     setError('');
     setStatus('');
+    const requestId = api.newRequestId();
+    setActiveRequestId(requestId);
+    stopRowStream.current?.();
+    setStreamedRows([]);
+    setStreamedColumns([]);
+    // Left subscribed after the call resolves: the last batches can land just after the result.
+    stopRowStream.current = await api.onBridgeProgress(requestId, (progress) => {
+      if (progress.stage !== 'rows') return;
+      const batch = progress as Extract<api.BridgeProgress, { stage: 'rows' }>;
+      setStreamedColumns(batch.columns);
+      setStreamedRows((prev) => [...prev, ...batch.rows]);
+    });
     try {
       const sqlResult = await api.workspaceSql({
         sql: sqlText,
         mode: sqlMode,
         action,
-        password: resolvedPassword,
+        name: activeProfile ?? undefined,
         policy: safePolicy,
+        requestId,
+        streamRows: true,
       });
       setResult({
         status: sqlResult.status,
@@ -342,33 +362,48 @@ export default function WorkspacePage({
         explainSummary: sqlResult.explainSummary,
         explainWarnings: sqlResult.explainWarnings ?? [],
         explainBlockers: sqlResult.explainBlockers ?? [],
-        executionResult: sqlResult.executionResult ?? null,
-        error: sqlResult.error,
+        executionResult: sqlResult.executionResult,
+        error: sqlResult.error ?? undefined,
       });
     } catch (err: unknown) {
+      if (api.isCancelled(err)) {
+        setStatus('Query stopped.');
+        return;
+      }
       const msg = err instanceof Error ? err.message : String(err);
       setError(msg);
     } finally {
       setRunning(false);
+      setActiveRequestId(null);
       setActivityLabel('');
     }
   };
 
+  const stopActiveRequest = async (): Promise<void> => {
+    if (!activeRequestId) return;
+    try {
+      await api.bridgeCancel(activeRequestId);
+    } catch (err: unknown) {
+      const msg = err instanceof Error ? err.message : String(err);
+      setError(msg);
+    }
+  };
+
   const openWritePreview = async (sql: string, params: unknown[]): Promise<void> => {
     if (!powerEnabled) {
       setError('POWER mode is disabled for the active profile. Enable it in Profiles.');
       return;
     }
-    const resolvedPassword = requiresPassword ? password.trim() : '';
-    if (requiresPassword && !resolvedPassword) {
+    if (requiresPassword && !hasPassword) {
       setError('Enter a session password before write preview.');
       return;
     }
+    await onSubmitPassword();
     setRunning(true);
     setActivityLabel('Preparing POWER write preview...');
     setError('');
     try {
-      const preview = await api.writePreview(sql, params, resolvedPassword);
+      const preview = await api.writePreview(sql, params, activeProfile ?? undefined);
       setWritePreview(preview);
       setPendingSql(sql);
       setPendingParams(params);
@@ -401,9 +436,8 @@ export default function WorkspacePage({
     setRunning(true);
     setActivityLabel('Executing POWER write...');
     setError('');
-    const resolvedPassword = requiresPassword ? password.trim() : '';
     try {
-      const writeResult = await api.writeExecute(pendingSql, pendingParams, resolvedPassword);
+      const writeResult = await api.writeExecute(pendingSql, pendingParams, activeProfile ?? undefined);
       setResult((prev) => ({
         ...(prev ?? {
           status: 'ok',
@@ -412,7 +446,7 @@ export default function WorkspacePage({
           params: pendingParams,
         }),
         status: writeResult.success ? 'ok' : 'error',
-        error: writeResult.error,
+        error: writeResult.error ?? undefined,
       }));
       if (writeResult.success) {
         setStatus(
@@ -517,7 +551,16 @@ export default function WorkspacePage({
     <section className="workspace-shell">
       {error && <div className="inline-error preserve-lines">{error}</div>}
       {status && <div className="inline-success">{status}</div>}
-      {running && activityLabel && <div className="inline-warning">{activityLabel}</div>}
+      {running && activityLabel && (
+        <div className="inline-warning">
+          {activityLabel}
+          {activeRequestId && (
+            <button type="button" className="btn btn-sm btn-secondary" onClick={() => void stopActiveRequest()}>
+              Stop
+            </button>
+          )}
+        </div>
+      )}
       <div className="workspace-header">
         <div>
           <h2>Workspace</h2>
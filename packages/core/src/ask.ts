@@ -30,6 +30,8 @@ export interface AskInput {
   mode: GuardrailMode;
   execute: boolean;
   dryRun: boolean;
+  /** Stops the EXPLAIN or query on the server when aborted. */
+  signal?: AbortSignal;
 }
 
 export interface AskResult {
@@ -157,6 +159,7 @@ export async function askAndMaybeRun(
     user: input.profile.user,
     password: input.password,
     ssl: input.profile.ssl,
+    signal: input.signal,
   };
 
   const smConfig = engine.getSafeModeConfig();
@@ -98,6 +98,17 @@ describe('sqlite demo adapter', () => {
       assert.deepEqual(query.columns, ['id', 'email']);
       assert.equal(query.rowCount, 2);
       assert.equal(query.rows[0].email, 'alice@example.com');
+
+      const batches: unknown[][] = [];
+      const streamed = await executeQuery({
+        ...conn,
+        sql: 'SELECT id, email FROM users ORDER BY id',
+        stream: { batchSize: 2, onRows: (batch) => batches.push(batch.rows.map((row) => row.id)) },
+      });
+      assert.deepEqual(batches, [[1, 2], [3]]);
+      assert.deepEqual(streamed.rows, []);
+      assert.equal(streamed.rowCount, 3);
+      assert.equal(streamed.truncated, false);
     } finally {
       rmSync(tempDir, { recursive: true, force: true });
     }
@@ -99,4 +99,34 @@ runDescribe('postgres integration', () => {
     assert.ok(explain.estimatedRows >= 0);
     assert.ok(explain.estimatedCost >= 0);
   });
+
+  it('streams read results in batches through a cursor', async () => {
+    const batches: { offset: number; rows: number }[] = [];
+    const result = await executeQuery({
+      ...cfg,
+      sql: 'SELECT g AS n FROM generate_series(1, 25) g',
+      limits: { maxRows: 20 },
+      stream: { batchSize: 8, onRows: (batch) => batches.push({ offset: batch.offset, rows: batch.rows.length }) },
+    });
+
+    assert.deepEqual(batches, [
+      { offset: 0, rows: 8 },
+      { offset: 8, rows: 8 },
+      { offset: 16, rows: 4 },
+    ]);
+    assert.deepEqual(result.columns, ['n']);
+    assert.deepEqual(result.rows, []);
+    assert.equal(result.rowCount, 20);
+    assert.equal(result.truncated, true);
+  });
+
+  it('cancels a running query on the server when its signal aborts', async () => {
+    const controller = new AbortController();
+    const started = Date.now();
+    const running = executeQuery({ ...cfg, sql: 'SELECT pg_sleep(10)', signal: controller.signal });
+    setTimeout(() => controller.abort(), 200);
+
+    await assert.rejects(running, /canceling statement due to user request/);
+    assert.ok(Date.now() - started < 5_000);
+  });
 });
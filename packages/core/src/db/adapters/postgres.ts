@@ -24,6 +24,23 @@ export interface ExecuteResult {
   execMs: number;
 }
 
+/** A slice of a result handed over while the query is still fetching. */
+export interface RowBatch {
+  columns: string[];
+  /** Position of the batch's first row in the result. */
+  offset: number;
+  rows: Record<string, unknown>[];
+}
+
+/**
+ * Receive a read's rows in batches as they are fetched, instead of in the
+ * result (whose `rows` is then empty and `rowCount` the number delivered).
+ */
+export interface RowStream {
+  batchSize: number;
+  onRows(batch: RowBatch): void;
+}
+
 export interface ExecuteLimits {
   defaultLimit?: number;
   maxRows?: number;
@@ -38,6 +55,48 @@ export interface ExplainOutput {
   warnings: string[];
 }
 
+function newClient(cfg: PgConnectionConfig, password: string): pg.Client {
+  return new Client({
+    host: cfg.host,
+    port: cfg.port,
+    database: cfg.database,
+    user: cfg.user,
+    password,
+    ssl: cfg.ssl ? { rejectUnauthorized: false } : false,
+    connectionTimeoutMillis: 10_000,
+  });
+}
+
+/**
+ * Cancel whatever `client` is running once `signal` aborts. Closing the client
+ * would leave the server backend working on the query, so the cancel goes
+ * through a second connection (`pg_cancel_backend`). Returns a function that
+ * stops listening.
+ */
+async function cancelOnAbort(
+  client: pg.Client,
+  cfg: PgConnectionConfig,
+  password: string,
+  signal?: AbortSignal,
+): Promise<() => void> {
+  if (!signal) return () => {};
+  signal.throwIfAborted();
+  const res = await client.query('SELECT pg_backend_pid() AS pid');
+  const pid = res.rows[0]?.pid as number;
+  const onAbort = (): void => {
+    const canceller = newClient(cfg, password);
+    void canceller
+      .connect()
+      .then(() => canceller.query('SELECT pg_cancel_backend($1)', [pid]))
+      .catch(() => {
+        // the query still ends at statement_timeout
+      })
+      .finally(() => canceller.end().catch(() => {}));
+  };
+  signal.addEventListener('abort', onAbort, { once: true });
+  return () => signal.removeEventListener('abort', onAbort);
+}
+
 /**
  * Test a Postgres connection: connect, run SELECT 1, disconnect.
  */
@@ -82,6 +141,10 @@ export async function testConnection(
  * - Sets statement_timeout on the session
  * - Wraps in BEGIN READ ONLY transaction
  * - Hard caps returned rows to limits.maxRows
+ * - Cancels the statement on the server when `signal` aborts
+ *
+ * With `stream`, the rows are read through a cursor and handed over a batch at
+ * a time, so neither side holds the whole result.
  */
 export async function execute(
   cfg: PgConnectionConfig,
@@ -89,6 +152,8 @@ export async function execute(
   sql: string,
   params: unknown[] = [],
   limits: ExecuteLimits = {},
+  signal?: AbortSignal,
+  stream?: RowStream,
 ): Promise<ExecuteResult> {
   const effectiveLimits = {
     maxRows: limits.maxRows ?? SAFE_DEFAULTS.maxRows,
@@ -105,8 +170,10 @@ export async function execute(
     connectionTimeoutMillis: 10_000,
   });
 
+  let stopListening = (): void => {};
   try {
     await client.connect();
+    stopListening = await cancelOnAbort(client, cfg, password, signal);
 
     // Set statement timeout
     await client.query(`SET statement_timeout = ${effectiveLimits.statementTimeoutMs}`);
@@ -114,7 +181,14 @@ export async function execute(
     // Begin read-only transaction for safety
     await client.query('BEGIN READ ONLY');
 
+    signal?.throwIfAborted();
     const start = performance.now();
+    if (stream) {
+      const fetched = await fetchInBatches(client, sql, params, effectiveLimits.maxRows, stream, signal);
+      const execMs = Math.round(performance.now() - start);
+      await client.query('COMMIT');
+      return { ...fetched, rows: [], execMs };
+    }
     const result = await client.query(sql, params);
     const execMs = Math.round(performance.now() - start);
 
@@ -143,6 +217,7 @@ export async function execute(
     }
     throw err;
   } finally {
+    stopListening();
     try {
       await client.end();
     } catch {
@@ -151,9 +226,45 @@ export async function execute(
   }
 }
 
+/**
+ * Read `sql` through a cursor, `stream.batchSize` rows per FETCH, handing each
+ * batch to `stream.onRows` before fetching the next. Stops at `maxRows`; one
+ * more FETCH tells whether that cut the result short. Must run in a transaction.
+ */
+async function fetchInBatches(
+  client: pg.Client,
+  sql: string,
+  params: unknown[],
+  maxRows: number,
+  stream: RowStream,
+  signal?: AbortSignal,
+): Promise<{ columns: string[]; rowCount: number; truncated: boolean }> {
+  await client.query(`DECLARE openquery_rows NO SCROLL CURSOR FOR ${sql}`, params);
+  let columns: string[] = [];
+  let offset = 0;
+  let truncated = false;
+  for (;;) {
+    signal?.throwIfAborted();
+    const wanted = Math.min(stream.batchSize, maxRows - offset);
+    const batch = await client.query(`FETCH FORWARD ${Math.max(wanted, 1)} FROM openquery_rows`);
+    columns = batch.fields?.map((f) => f.name) ?? columns;
+    const rows = (batch.rows ?? []) as Record<string, unknown>[];
+    if (wanted <= 0) {
+      truncated = rows.length > 0;
+      break;
+    }
+    if (rows.length > 0) stream.onRows({ columns, offset, rows });
+    offset += rows.length;
+    if (rows.length < wanted) break;
+  }
+  await client.query('CLOSE openquery_rows');
+  return { columns, rowCount: offset, truncated };
+}
+
 /**
  * Run EXPLAIN (FORMAT JSON) on a SQL statement and parse the plan.
- * Runs under the same statement timeout and read-only transaction.
+ * Runs under the same statement timeout and read-only transaction, and is
+ * cancelled the same way.
  */
 export async function explain(
   cfg: PgConnectionConfig,
@@ -161,6 +272,7 @@ export async function explain(
   sql: string,
   params: unknown[] = [],
   limits: ExecuteLimits = {},
+  signal?: AbortSignal,
 ): Promise<ExplainOutput> {
   const timeoutMs = limits.statementTimeoutMs ?? SAFE_DEFAULTS.statementTimeoutMs;
 
@@ -174,8 +286,10 @@ export async function explain(
     connectionTimeoutMillis: 10_000,
   });
 
+  let stopListening = (): void => {};
   try {
     await client.connect();
+    stopListening = await cancelOnAbort(client, cfg, password, signal);
     await client.query(`SET statement_timeout = ${timeoutMs}`);
     await client.query('BEGIN READ ONLY');
 
@@ -192,6 +306,7 @@ export async function explain(
     }
     throw err;
   } finally {
+    stopListening();
     try {
       await client.end();
     } catch {
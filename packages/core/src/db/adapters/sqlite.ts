@@ -6,7 +6,8 @@
 import Database from 'better-sqlite3';
 import { SAFE_DEFAULTS } from '../defaults.js';
 import type { SchemaSnapshot, TableInfo } from '../types.js';
-import type { ExecuteLimits, ExecuteResult, ExplainOutput } from './postgres.js';
+import { setImmediate as nextTurn } from 'node:timers/promises';
+import type { ExecuteLimits, ExecuteResult, ExplainOutput, RowStream } from './postgres.js';
 
 export interface SqliteConnectionConfig {
   database: string;
@@ -44,13 +45,52 @@ function queryRun(stmt: Database.Statement, params: unknown[]): Database.RunResu
   return stmt.run(...(params as unknown[]));
 }
 
+/**
+ * Step through `stmt`'s rows, handing them to `stream.onRows` a batch at a time
+ * and yielding between batches so they are sent (and a cancellation seen)
+ * while the rest are read.
+ */
+async function iterateInBatches(
+  stmt: Database.Statement,
+  params: unknown[],
+  maxRows: number,
+  stream: RowStream,
+  signal?: AbortSignal,
+): Promise<{ columns: string[]; rowCount: number; truncated: boolean }> {
+  const columns = stmt.columns().map((column) => column.name);
+  const iterator = stmt.iterate(...params) as IterableIterator<Record<string, unknown>>;
+  let batch: Record<string, unknown>[] = [];
+  let offset = 0;
+  let truncated = false;
+  for (const row of iterator) {
+    if (offset + batch.length === maxRows) {
+      truncated = true;
+      break;
+    }
+    batch.push(row);
+    if (batch.length === stream.batchSize) {
+      stream.onRows({ columns, offset, rows: batch });
+      offset += batch.length;
+      batch = [];
+      await nextTurn();
+      signal?.throwIfAborted();
+    }
+  }
+  if (batch.length > 0) stream.onRows({ columns, offset, rows: batch });
+  return { columns, rowCount: offset + batch.length, truncated };
+}
+
 export async function execute(
   cfg: SqliteConnectionConfig,
   _password: string,
   sql: string,
   params: unknown[] = [],
   limits: ExecuteLimits = {},
+  signal?: AbortSignal,
+  stream?: RowStream,
 ): Promise<ExecuteResult> {
+  // better-sqlite3 runs synchronously: a statement cannot be stopped once started.
+  signal?.throwIfAborted();
   const maxRows = limits.maxRows ?? SAFE_DEFAULTS.maxRows;
   const db = openDatabase(cfg, true);
   try {
@@ -68,6 +108,11 @@ export async function execute(
       };
     }
 
+    if (stream) {
+      const fetched = await iterateInBatches(stmt, params, maxRows, stream, signal);
+      return { ...fetched, rows: [], execMs: Math.round(performance.now() - start) };
+    }
+
     const allRows = queryAll(stmt, params);
     const execMs = Math.round(performance.now() - start);
     const columns = stmt.columns().map((column) => column.name);
@@ -91,7 +136,9 @@ export async function explain(
   sql: string,
   params: unknown[] = [],
   _limits: ExecuteLimits = {},
+  signal?: AbortSignal,
 ): Promise<ExplainOutput> {
+  signal?.throwIfAborted();
   const db = openDatabase(cfg, true);
   try {
     const explainStmt = db.prepare(`EXPLAIN QUERY PLAN ${sql}`);
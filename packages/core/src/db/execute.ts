@@ -6,10 +6,10 @@
 import * as postgres from './adapters/postgres.js';
 import * as mysql from './adapters/mysql.js';
 import * as sqlite from './adapters/sqlite.js';
-import type { ExecuteLimits, ExecuteResult, PgConnectionConfig, ExplainOutput } from './adapters/postgres.js';
+import type { ExecuteLimits, ExecuteResult, PgConnectionConfig, ExplainOutput, RowStream } from './adapters/postgres.js';
 import type { SchemaSnapshot } from './types.js';
 
-export type { ExecuteResult, ExecuteLimits, ExplainOutput } from './adapters/postgres.js';
+export type { ExecuteResult, ExecuteLimits, ExplainOutput, RowBatch, RowStream } from './adapters/postgres.js';
 
 export interface ExecuteRequest {
   dbType: string;
@@ -22,6 +22,10 @@ export interface ExecuteRequest {
   sql: string;
   params?: unknown[];
   limits?: ExecuteLimits;
+  /** Stops the query on the server when aborted (Postgres; SQLite checks it before running). */
+  signal?: AbortSignal;
+  /** Hand a read's rows over in batches as they are fetched (Postgres, SQLite). */
+  stream?: RowStream;
 }
 
 export interface TestRequest {
@@ -45,6 +49,8 @@ export interface ExplainRequest {
   sql: string;
   params?: unknown[];
   limits?: ExecuteLimits;
+  /** Stops the query on the server when aborted (Postgres; SQLite checks it before running). */
+  signal?: AbortSignal;
 }
 
 export interface IntrospectRequest {
@@ -68,11 +74,11 @@ function toSqliteConfig(req: { database: string }): sqlite.SqliteConnectionConfi
 export async function executeQuery(req: ExecuteRequest): Promise<ExecuteResult> {
   switch (req.dbType) {
     case 'postgres':
-      return postgres.execute(toPgConfig(req), req.password, req.sql, req.params, req.limits);
+      return postgres.execute(toPgConfig(req), req.password, req.sql, req.params, req.limits, req.signal, req.stream);
     case 'mysql':
       return mysql.execute(toPgConfig(req), req.password, req.sql, req.params, req.limits);
     case 'sqlite':
-      return sqlite.execute(toSqliteConfig(req), req.password, req.sql, req.params, req.limits);
+      return sqlite.execute(toSqliteConfig(req), req.password, req.sql, req.params, req.limits, req.signal, req.stream);
     default:
       throw new Error(`Unsupported database type: ${req.dbType}. Supported: postgres, sqlite.`);
   }
@@ -81,11 +87,11 @@ export async function executeQuery(req: ExecuteRequest): Promise<ExecuteResult>
 export async function explainQuery(req: ExplainRequest): Promise<ExplainOutput> {
   switch (req.dbType) {
     case 'postgres':
-      return postgres.explain(toPgConfig(req), req.password, req.sql, req.params, req.limits);
+      return postgres.explain(toPgConfig(req), req.password, req.sql, req.params, req.limits, req.signal);
     case 'mysql':
       return mysql.explain(toPgConfig(req), req.password, req.sql, req.params, req.limits);
     case 'sqlite':
-      return sqlite.explain(toSqliteConfig(req), req.password, req.sql, req.params, req.limits);
+      return sqlite.explain(toSqliteConfig(req), req.password, req.sql, req.params, req.limits, req.signal);
     default:
       throw new Error(`Unsupported database type: ${req.dbType}. Supported: postgres, sqlite.`);
   }
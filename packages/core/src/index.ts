@@ -36,6 +36,8 @@ export type {
   ExecuteResult,
   ExecuteLimits,
   ExplainOutput,
+  RowBatch,
+  RowStream,
   ExecuteRequest,
   ExplainRequest,
   TestRequest,
@@ -0,0 +1,64 @@
+#!/usr/bin/env bash
+# Stage the Node bridge for `tauri build`.
+#
+# Produces apps/desktop/src-tauri/resources/bridge/, which tauri.conf.json ships
+# as the app's `bridge/` resource:
+#   package.json          (read by the bridge for its version)
+#   bridge/dist/          compiled bridge
+#   node_modules/         production dependencies, including native modules
+#   bin/node              only with OPENQUERY_BUNDLE_NODE=1: the Node runtime the
+#                         native modules were built against
+set -euo pipefail
+
+ROOT_DIR="$(git rev-parse --show-toplevel 2>/dev/null || pwd)"
+DESKTOP_DIR="$ROOT_DIR/apps/desktop"
+OUT_DIR="$DESKTOP_DIR/src-tauri/resources/bridge"
+
+for tool in node pnpm; do
+  if ! command -v "$tool" >/dev/null 2>&1; then
+    echo "Missing required tool: $tool"
+    exit 1
+  fi
+done
+
+echo "Building core and bridge..."
+pnpm --filter @openquery/core build
+pnpm --filter @openquery/desktop build:bridge
+
+STAGE_DIR="$(mktemp -d)"
+trap 'rm -rf "$STAGE_DIR"' EXIT
+
+echo "Collecting production dependencies..."
+pnpm --filter @openquery/desktop deploy --prod "$STAGE_DIR/app" >/dev/null
+
+find "$OUT_DIR" -mindepth 1 ! -name .gitkeep -exec rm -rf {} + 2>/dev/null || true
+mkdir -p "$OUT_DIR/bridge"
+cp "$DESKTOP_DIR/package.json" "$OUT_DIR/package.json"
+cp -R "$DESKTOP_DIR/bridge/dist" "$OUT_DIR/bridge/dist"
+cp -R "$STAGE_DIR/app/node_modules" "$OUT_DIR/node_modules"
+
+if [[ "${OPENQUERY_BUNDLE_NODE:-0}" == "1" ]]; then
+  # Ask Node where it runs from: `command -v node` may be a volta, asdf or mise
+  # shim that only works on this machine.
+  NODE_BIN="$(node -p process.execPath)"
+  if command -v cygpath >/dev/null 2>&1; then
+    NODE_BIN="$(cygpath -u "$NODE_BIN")" # Windows path under Git Bash
+  fi
+  if [[ ! -f "$NODE_BIN" || ! -x "$NODE_BIN" ]]; then
+    echo "Node runtime is not an executable file: $NODE_BIN"
+    exit 1
+  fi
+  # ELF, Mach-O (thin or universal) or PE; anything else is a script wrapper.
+  case "$(head -c 4 "$NODE_BIN" | od -An -tx1 | tr -d ' \n')" in
+    7f454c46 | feedface | feedfacf | cefaedfe | cffaedfe | cafebabe | 4d5a*) ;;
+    *)
+      echo "Node runtime is not a native executable: $NODE_BIN"
+      exit 1
+      ;;
+  esac
+  echo "Bundling Node runtime $(node --version) from $NODE_BIN"
+  mkdir -p "$OUT_DIR/bin"
+  cp "$NODE_BIN" "$OUT_DIR/bin/"
+fi
+
+echo "Bridge staged in $OUT_DIR"